@@ -0,0 +1,285 @@
+//! Python bindings for the `transactionbased` library.
+//!
+//! Every function takes the same options as the command line binary as keyword
+//! arguments (`max_depth=10` is `--max-depth 10`), and table-like results come
+//! back as lists of dicts, so `pandas.DataFrame(records)` works directly.
+
+use std::collections::{HashMap, HashSet};
+
+use pyo3::exceptions::{PyFileNotFoundError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict};
+
+use transactionbased::adapter;
+use transactionbased::analysis::Analysis;
+use transactionbased::annotations;
+use transactionbased::budget::BudgetTracker;
+use transactionbased::config::Config;
+use transactionbased::cycles;
+use transactionbased::dfsstuff::{self, PathReservoir};
+use transactionbased::fileread::{self, Dataset};
+
+/// Builds a `Config` from Python keyword arguments, via the command line parser.
+fn config_from_kwargs(options: Option<&Bound<'_, PyDict>>) -> PyResult<Config> {
+    let mut args = Vec::new();
+    if let Some(options) = options {
+        for (key, value) in options.iter() {
+            let key: String = key.extract()?;
+            let value = if value.is_instance_of::<PyBool>() {
+                value.extract::<bool>()?.to_string()
+            } else {
+                value.str()?.to_string()
+            };
+            args.push(format!("--{}", key.replace('_', "-")));
+            args.push(value);
+        }
+    }
+    Config::from_args(&args).map_err(PyValueError::new_err)
+}
+
+/// A loaded transaction graph with its timesteps and class labels.
+#[pyclass(name = "Dataset")]
+pub struct PyDataset {
+    inner: Dataset,
+    /// `inner.edges` as sorted neighbour lists, built once for the path searches.
+    successors: HashMap<String, Vec<String>>,
+}
+
+impl From<Dataset> for PyDataset {
+    fn from(inner: Dataset) -> Self {
+        PyDataset { successors: dfsstuff::successors(&inner.edges), inner }
+    }
+}
+
+#[pymethods]
+impl PyDataset {
+    /// Builds a dataset from `(src, dst)` edge pairs and txId → timestep / label dicts.
+    #[new]
+    fn new(edges: Vec<(String, String)>, timestamps: HashMap<String, usize>, labels: HashMap<String, String>) -> Self {
+        let mut graph: HashMap<String, HashSet<String>> = HashMap::new();
+        for (src, dst) in edges {
+            graph.entry(src).or_default().insert(dst);
+        }
+        Dataset { edges: graph, timestamps, labels }.into()
+    }
+
+    #[getter]
+    fn num_nodes(&self) -> usize {
+        self.inner.timestamps.len()
+    }
+
+    #[getter]
+    fn num_edges(&self) -> usize {
+        self.inner.edges.values().map(|t| t.len()).sum()
+    }
+
+    /// txIds with the given class label (`"1"` licit, `"2"` illicit, `"unknown"`).
+    fn nodes_with_label(&self, label: &str) -> Vec<String> {
+        let mut nodes: Vec<String> = self
+            .inner
+            .labels
+            .iter()
+            .filter(|(_, l)| *l == label)
+            .map(|(n, _)| n.clone())
+            .collect();
+        nodes.sort();
+        nodes
+    }
+
+    /// Direct successors of a txId.
+    fn successors(&self, tx: &str) -> Vec<String> {
+        let mut next: Vec<String> = self.inner.edges.get(tx).map_or(Vec::new(), |t| t.iter().cloned().collect());
+        next.sort();
+        next
+    }
+
+    /// One record per txId: `txId`, `timestep`, `label`, `in_degree`, `out_degree`.
+    fn nodes<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        let mut in_degree: HashMap<&String, usize> = HashMap::new();
+        for targets in self.inner.edges.values() {
+            for tgt in targets {
+                *in_degree.entry(tgt).or_insert(0) += 1;
+            }
+        }
+        let mut nodes: Vec<&String> = self.inner.timestamps.keys().collect();
+        nodes.sort();
+
+        nodes
+            .into_iter()
+            .map(|node| {
+                let record = PyDict::new(py);
+                record.set_item("txId", node)?;
+                record.set_item("timestep", self.inner.timestamps[node])?;
+                record.set_item("label", self.inner.labels.get(node).map_or("unknown", |l| l.as_str()))?;
+                record.set_item("in_degree", in_degree.get(node).copied().unwrap_or(0))?;
+                record.set_item("out_degree", self.inner.edges.get(node).map_or(0, |t| t.len()))?;
+                Ok(record)
+            })
+            .collect()
+    }
+}
+
+/// Reads and validates the Elliptic CSVs in `data_dir`.
+#[pyfunction]
+fn load_dataset(data_dir: &str) -> PyResult<PyDataset> {
+    for name in ["elliptic_txs_classes.csv", "elliptic_txs_edgelist.csv", "elliptic_txs_features.csv"] {
+        let path = format!("{}/{}", data_dir, name);
+        if !std::path::Path::new(&path).exists() {
+            return Err(PyFileNotFoundError::new_err(path));
+        }
+    }
+    Ok(fileread::load_dataset(data_dir).map_err(PyValueError::new_err)?.into())
+}
+
+/// Reads a dataset described by a spec file (see `adapter::DatasetSpec`),
+/// e.g. the AMLSim or IBM AML transfer CSVs.
+#[pyfunction]
+fn load_dataset_spec(spec_path: &str) -> PyResult<PyDataset> {
+    let spec = adapter::DatasetSpec::read(spec_path).map_err(PyValueError::new_err)?;
+    Ok(adapter::load(&spec).map_err(PyValueError::new_err)?.into())
+}
+
+/// Counts time-respecting paths for every (start, target) pair.
+///
+/// Options: `max_depth`, `max_paths_per_pair`, `max_expansions`, `time_limit`.
+///
+/// # Returns
+/// One record per pair: `start`, `target`, `paths`, `total_depth`, `avg_depth`, `status`.
+#[pyfunction]
+#[pyo3(signature = (dataset, starts, targets, **options))]
+fn summarize_paths_to_targets<'py>(
+    py: Python<'py>,
+    dataset: &PyDataset,
+    starts: Vec<String>,
+    targets: Vec<String>,
+    options: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Vec<Bound<'py, PyDict>>> {
+    let config = config_from_kwargs(options)?;
+    let data = &dataset.inner;
+    let (stats, statuses) = dfsstuff::summarize_paths_to_targets(
+        &dataset.successors, &data.timestamps, &starts, &targets, config.max_depth, &config.budget, None,
+    );
+
+    let mut pairs: Vec<_> = statuses.into_iter().collect();
+    pairs.sort_by(|a, b| a.0.cmp(&b.0));
+    pairs
+        .into_iter()
+        .map(|((start, target), status)| {
+            let (count, total_depth) = stats.get(&(start.clone(), target.clone())).copied().unwrap_or((0, 0));
+            let record = PyDict::new(py);
+            record.set_item("start", start)?;
+            record.set_item("target", target)?;
+            record.set_item("paths", count)?;
+            record.set_item("total_depth", total_depth)?;
+            record.set_item("avg_depth", if count == 0 { f64::NAN } else { total_depth as f64 / count as f64 })?;
+            record.set_item("status", status.to_string())?;
+            Ok(record)
+        })
+        .collect()
+}
+
+/// Collects time-respecting paths from `start` to `target`.
+///
+/// Options: `collect_depth`, `reservoir` (paths kept, uniformly sampled),
+/// `max_expansions`, `time_limit`, `max_paths_per_pair`.
+///
+/// # Returns
+/// A dict with `paths` (list of txId lists), `total` (paths found) and `status`.
+#[pyfunction]
+#[pyo3(signature = (dataset, start, target, **options))]
+fn collect_paths<'py>(
+    py: Python<'py>,
+    dataset: &PyDataset,
+    start: String,
+    target: String,
+    options: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let config = config_from_kwargs(options)?;
+    let data = &dataset.inner;
+    let mut reservoir = PathReservoir::new(config.reservoir_size);
+    let mut tracker = BudgetTracker::new(config.budget);
+    dfsstuff::dfs_sample_paths(
+        &dataset.successors, &data.timestamps,
+        &start, &target,
+        &mut Vec::new(), &mut reservoir, &mut HashSet::new(),
+        1, config.collect_depth, &mut tracker,
+    );
+
+    let result = PyDict::new(py);
+    result.set_item("paths", reservoir.paths)?;
+    result.set_item("total", reservoir.total)?;
+    result.set_item("status", tracker.status().to_string())?;
+    Ok(result)
+}
+
+/// Simple cycles of up to `k + 1` nodes, each reported once (see `cycles::simple_cycles`).
+#[pyfunction]
+#[pyo3(signature = (dataset, k = 3))]
+fn find_cycles(dataset: &PyDataset, k: usize) -> Vec<Vec<String>> {
+    cycles::simple_cycles(&dataset.inner.edges, None, k + 1)
+}
+
+/// Runs the full mixer-scoring experiment.
+///
+/// Takes every command line option as a keyword argument, e.g.
+/// `run_analysis(ds, runs=3, score="logodds", groups="illicit,licit,unknown")`.
+///
+/// # Returns
+/// One record per candidate, ranked: `rank`, `txId`, `mean`, `stddev`, `ci_low`,
+/// `ci_high`, `runs`, `entity_type`, `community`, `communities_bridged` and a
+/// `reuse_<group>` column per label group.
+#[pyfunction]
+#[pyo3(signature = (dataset, **options))]
+fn run_analysis<'py>(
+    py: Python<'py>,
+    dataset: &PyDataset,
+    options: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Vec<Bound<'py, PyDict>>> {
+    let config = config_from_kwargs(options)?;
+    let known = match &config.annotations {
+        Some(path) => annotations::read_annotations(path).map_err(PyValueError::new_err)?,
+        None => HashMap::new(),
+    };
+    let groups: Vec<String> = config.groups.iter().map(|g| format!("reuse_{}", g.name)).collect();
+    let data = &dataset.inner;
+    let result = Analysis::new(data.edges.clone(), data.timestamps.clone(), data.labels.clone())
+        .with_config(config)
+        .with_annotations(known)
+        .run()
+        .map_err(PyValueError::new_err)?;
+
+    result
+        .stats
+        .iter()
+        .enumerate()
+        .map(|(rank, stat)| {
+            let record = PyDict::new(py);
+            record.set_item("rank", rank + 1)?;
+            record.set_item("txId", &stat.node)?;
+            record.set_item("mean", stat.mean)?;
+            record.set_item("stddev", stat.stddev)?;
+            record.set_item("ci_low", stat.ci_low)?;
+            record.set_item("ci_high", stat.ci_high)?;
+            record.set_item("runs", stat.scores.len())?;
+            record.set_item("entity_type", stat.entity.as_deref())?;
+            record.set_item("community", stat.community)?;
+            record.set_item("communities_bridged", stat.communities_bridged)?;
+            for (column, reuse) in groups.iter().zip(&stat.reuse) {
+                record.set_item(column, reuse)?;
+            }
+            Ok(record)
+        })
+        .collect()
+}
+
+#[pymodule]
+fn transactionbased_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyDataset>()?;
+    m.add_function(wrap_pyfunction!(load_dataset, m)?)?;
+    m.add_function(wrap_pyfunction!(load_dataset_spec, m)?)?;
+    m.add_function(wrap_pyfunction!(summarize_paths_to_targets, m)?)?;
+    m.add_function(wrap_pyfunction!(collect_paths, m)?)?;
+    m.add_function(wrap_pyfunction!(find_cycles, m)?)?;
+    m.add_function(wrap_pyfunction!(run_analysis, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,74 @@
+"""Smoke tests for the Python bindings.
+
+Build the module first (`maturin develop --offline`, or copy
+`target/release/libtransactionbased_py.so` to `transactionbased_py.so` on the
+path), then run `python -m unittest discover -s tests` from this folder.
+"""
+
+import os
+import tempfile
+import unittest
+
+import transactionbased_py as tb
+
+
+def toy_dataset():
+    # illicit A and C both route through M, licit B takes its own way to T.
+    edges = [("A", "M"), ("C", "M"), ("M", "T"), ("B", "L"), ("L", "T"), ("T", "A")]
+    timesteps = {"A": 1, "B": 1, "C": 1, "M": 2, "L": 2, "T": 3}
+    labels = {"A": "2", "C": "2", "B": "1", "L": "1", "M": "unknown", "T": "unknown"}
+    return tb.Dataset(edges, timesteps, labels)
+
+
+def write_elliptic(folder, classes, edgelist, features):
+    for name, body in [("classes", classes), ("edgelist", edgelist), ("features", features)]:
+        with open(os.path.join(folder, f"elliptic_txs_{name}.csv"), "w") as f:
+            f.write(body)
+
+
+class BindingsTest(unittest.TestCase):
+    def test_dataset_and_paths(self):
+        ds = toy_dataset()
+        self.assertEqual((ds.num_nodes, ds.num_edges), (6, 6))
+        self.assertEqual(ds.nodes_with_label("2"), ["A", "C"])
+        self.assertEqual(ds.successors("M"), ["T"])
+
+        pairs = tb.summarize_paths_to_targets(ds, ["A", "B"], ["T"], max_depth=4)
+        self.assertEqual([(p["start"], p["paths"], p["status"]) for p in pairs], [("A", 1, "finished"), ("B", 1, "finished")])
+        paths = tb.collect_paths(ds, "A", "T", reservoir=5)
+        self.assertEqual((paths["paths"], paths["total"]), ([["A", "M", "T"]], 1))
+        # T → A goes back in time but still closes a loop in the plain graph.
+        self.assertIn(["A", "M", "T"], tb.find_cycles(ds, 2))
+
+    def test_options_go_through_config(self):
+        ds = toy_dataset()
+        ranking = tb.run_analysis(ds, runs=2, sample_size=2, seed=7, score="ratio")
+        self.assertEqual(ranking[0]["txId"], "M")
+        self.assertEqual(ranking[0]["runs"], 2)
+        self.assertEqual(ranking, tb.run_analysis(ds, runs=2, sample_size=2, seed=7, score="ratio"))
+
+        with self.assertRaises(ValueError):
+            tb.run_analysis(ds, no_such_option=1)
+        with self.assertRaises(ValueError):
+            tb.run_analysis(ds, score="median")
+        with self.assertRaises(ValueError):
+            tb.collect_paths(ds, "A", "T", time_limit=-1)
+        with self.assertRaises(ValueError):
+            tb.run_analysis(ds, annotations="/no/such/annotations.csv")
+
+    def test_bad_files_raise(self):
+        with tempfile.TemporaryDirectory() as folder:
+            with self.assertRaises(FileNotFoundError):
+                tb.load_dataset(folder)
+            write_elliptic(folder, "txId,class\nA,2\n", "txId1,txId2\nA\n", "A,1\n")
+            with self.assertRaises(ValueError):
+                tb.load_dataset(folder)
+            write_elliptic(folder, "txId,class\nA,2\n", "txId1,txId2\nA,B\n", "A,1\n")
+            with self.assertRaises(ValueError):
+                tb.load_dataset(folder)
+            write_elliptic(folder, "txId,class\nA,2\nB,1\n", "txId1,txId2\nA,B\n", "A,1\nB,2\n")
+            self.assertEqual(tb.load_dataset(folder).num_edges, 1)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,521 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::config::Config;
+use crate::dfsstuff;
+use crate::fileread::{self, Dataset};
+use crate::flow::{self, WeightedDataset, WeightedGraph};
+
+/// How the rows of a dataset map onto the transaction graph.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Layout {
+    /// Elliptic-style: an edge list between node ids, plus files giving each node's time and label.
+    Nodes,
+    /// AMLSim / IBM AML-style: each row is a transfer between two accounts. Every row
+    /// becomes a node, with an edge to the next transfers made by the receiving account.
+    Transactions,
+}
+
+/// How the time column is written.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimeType {
+    /// Integer timestep, used as is (Elliptic, AMLSim `step`).
+    Step,
+    /// Unix timestamp in seconds, bucketed into timesteps.
+    Unix,
+    /// `YYYY-MM-DD HH:MM[:SS]` (or `/` separators, or a `T` between date and time), bucketed into timesteps.
+    DateTime,
+}
+
+impl FromStr for TimeType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "step" => Ok(TimeType::Step),
+            "unix" => Ok(TimeType::Unix),
+            "datetime" => Ok(TimeType::DateTime),
+            _ => Err(format!("Unknown time type {} (expected step, unix or datetime)", s)),
+        }
+    }
+}
+
+/// A column given by 0-based index or by header name.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Column {
+    Index(usize),
+    Name(String),
+}
+
+impl Column {
+    fn parse(s: &str) -> Column {
+        match s.parse() {
+            Ok(i) => Column::Index(i),
+            Err(_) => Column::Name(s.to_string()),
+        }
+    }
+
+    fn resolve(&self, header: Option<&[String]>, path: &Path) -> Result<usize, String> {
+        match (self, header) {
+            (Column::Index(i), _) => Ok(*i),
+            (Column::Name(name), Some(header)) => header
+                .iter()
+                .position(|h| h == name)
+                .ok_or_else(|| format!("{} has no column {} (columns: {})", path.display(), name, header.join(", "))),
+            (Column::Name(name), None) => {
+                Err(format!("Column {} is given by name but {} has no header", name, path.display()))
+            }
+        }
+    }
+}
+
+/// One delimited file and how to read it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Table {
+    pub path: PathBuf,
+    pub delimiter: char,
+    pub header: bool,
+    /// Role (`source`, `time`, `label`, ...) → column.
+    pub columns: HashMap<String, Column>,
+}
+
+/// Column index per role, and the data rows of a table with their 1-based line numbers.
+type Rows = (HashMap<String, usize>, Vec<(usize, Vec<String>)>);
+
+/// Description of a dataset on disk, read from a `key = value` file.
+///
+/// Keys (paths are relative to the spec file):
+/// * `layout` — `nodes` (default) or `transactions`.
+/// * `delimiter` — default for every file: a single character, or `tab`.
+/// * `time_type` — `step` (default), `unix` or `datetime`; `time_bucket` — seconds per timestep (default 86400).
+/// * `label_map` — `raw:label,...`, e.g. `1:illicit,0:licit`. `illicit`/`licit`/`unknown`
+///   become Elliptic's `2`/`1`/`unknown`, other names are kept; unmapped raw values become `unknown`.
+/// * `nodes` layout: `edges` (+ `edges.source`, `edges.target`), `times` (+ `times.id`, `times.time`)
+///   and `labels` (+ `labels.id`, `labels.label`). `times` and `labels` may be the same file.
+/// * `transactions` layout: `transactions` (+ `transactions.id`, `.from`, `.to`, `.time`, `.label`)
+///   and `max_successors` (default 10) — edges from a transfer to the receiver's next transfers.
+/// * `edges.amount` / `transactions.amount` — optional transfer amounts, used by `load_weighted`.
+/// * Per file: `<file>.delimiter` and `<file>.header` (`true` by default).
+///
+/// Columns are 0-based indices or header names.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DatasetSpec {
+    pub layout: Layout,
+    pub time_type: TimeType,
+    pub time_bucket: u64,
+    /// Raw label value → label used by the pipeline (`None` keeps raw values).
+    pub label_map: Option<HashMap<String, String>>,
+    /// File name (`edges`, `times`, `labels` or `transactions`) → table.
+    pub tables: HashMap<String, Table>,
+    pub max_successors: usize,
+}
+
+impl DatasetSpec {
+    /// Reads a spec file.
+    ///
+    /// # Returns
+    /// The spec, or a message naming the bad line or missing key.
+    pub fn read(path: &str) -> Result<DatasetSpec, String> {
+        let file = File::open(path).map_err(|e| format!("Could not open dataset spec {}: {}", path, e))?;
+        let base = Path::new(path).parent().unwrap_or(Path::new(".")).to_path_buf();
+        let mut entries = HashMap::new();
+        for (i, line) in BufReader::new(file).lines().map_while(Result::ok).enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim().to_string();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("{}:{}: expected key = value, got {}", path, i + 1, line))?;
+            entries.insert(key.trim().to_string(), value.trim().to_string());
+        }
+        DatasetSpec::from_entries(&entries, &base)
+    }
+
+    fn from_entries(entries: &HashMap<String, String>, base: &Path) -> Result<DatasetSpec, String> {
+        let get = |key: &str| entries.get(key).map(|v| v.as_str());
+        let layout = match get("layout").unwrap_or("nodes") {
+            "nodes" => Layout::Nodes,
+            "transactions" => Layout::Transactions,
+            other => return Err(format!("Unknown layout {} (expected nodes or transactions)", other)),
+        };
+        let default_delimiter = parse_delimiter(get("delimiter").unwrap_or(","))?;
+
+        let files: &[(&str, &[(&str, &str)])] = match layout {
+            Layout::Nodes => &[
+                ("edges", &[("source", "0"), ("target", "1")]),
+                ("times", &[("id", "0"), ("time", "1")]),
+                ("labels", &[("id", "0"), ("label", "1")]),
+            ],
+            Layout::Transactions => &[("transactions", &[("from", "0"), ("to", "1"), ("time", "2")])],
+        };
+        let mut tables = HashMap::new();
+        for (name, roles) in files {
+            let path = get(name).ok_or_else(|| format!("Dataset spec needs {} for layout {:?}", name, layout))?;
+            let mut columns: HashMap<String, Column> = roles
+                .iter()
+                .map(|(role, default)| {
+                    let value = get(&format!("{}.{}", name, role)).unwrap_or(default);
+                    (role.to_string(), Column::parse(value))
+                })
+                .collect();
+            // Optional columns: transaction ids, labels and amounts.
+            for role in ["id", "label", "amount"] {
+                if let Some(value) = get(&format!("{}.{}", name, role)) {
+                    columns.insert(role.to_string(), Column::parse(value));
+                }
+            }
+            let table = Table {
+                path: base.join(path),
+                delimiter: match get(&format!("{}.delimiter", name)) {
+                    Some(d) => parse_delimiter(d)?,
+                    None => default_delimiter,
+                },
+                header: match get(&format!("{}.header", name)) {
+                    Some(h) => h.parse().map_err(|_| format!("{}.header expects true or false, got {}", name, h))?,
+                    None => true,
+                },
+                columns,
+            };
+            tables.insert(name.to_string(), table);
+        }
+
+        let label_map = get("label_map")
+            .map(|map| {
+                map.split(',')
+                    .map(|pair| {
+                        let (raw, label) = pair
+                            .split_once(':')
+                            .ok_or_else(|| format!("label_map entries look like raw:label, got {}", pair))?;
+                        Ok((raw.trim().to_string(), internal_label(label.trim())))
+                    })
+                    .collect::<Result<HashMap<_, _>, String>>()
+            })
+            .transpose()?;
+
+        Ok(DatasetSpec {
+            layout,
+            time_type: get("time_type").unwrap_or("step").parse()?,
+            time_bucket: parse_number(get("time_bucket").unwrap_or("86400"), "time_bucket")?,
+            label_map,
+            tables,
+            max_successors: parse_number(get("max_successors").unwrap_or("10"), "max_successors")? as usize,
+        })
+    }
+
+    fn label(&self, raw: &str) -> String {
+        match &self.label_map {
+            Some(map) => map.get(raw).cloned().unwrap_or_else(|| "unknown".to_string()),
+            None => raw.to_string(),
+        }
+    }
+
+    /// Parses one time cell into seconds (or a step, for `TimeType::Step`).
+    ///
+    /// # Returns
+    /// The time, or a message naming the table line whose time doesn't match `time_type`.
+    fn time(&self, table: &Table, line: usize, raw: &str) -> Result<i64, String> {
+        let time = match self.time_type {
+            TimeType::Step => raw.parse().ok(),
+            TimeType::Unix => raw.parse::<f64>().ok().map(|t| t.floor() as i64),
+            TimeType::DateTime => parse_datetime(raw),
+        };
+        time.ok_or_else(|| format!("{} line {}: cannot read time {:?} as {:?} (check time_type)", table.path.display(), line, raw, self.time_type))
+    }
+
+    /// Turns parsed times into timesteps: steps stay as they are, seconds are
+    /// bucketed so the earliest one falls into timestep 1.
+    fn timesteps(&self, times: HashMap<String, i64>) -> HashMap<String, usize> {
+        match self.time_type {
+            TimeType::Step => times.into_iter().filter(|(_, t)| *t >= 0).map(|(k, t)| (k, t as usize)).collect(),
+            TimeType::Unix | TimeType::DateTime => {
+                let first = times.values().copied().min().unwrap_or(0);
+                let bucket = self.time_bucket.max(1) as i64;
+                times.into_iter().map(|(k, t)| (k, ((t - first) / bucket) as usize + 1)).collect()
+            }
+        }
+    }
+}
+
+/// `illicit`, `licit` and `unknown` in Elliptic's encoding; anything else as is.
+fn internal_label(name: &str) -> String {
+    match name {
+        "illicit" => "2".to_string(),
+        "licit" => "1".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn parse_delimiter(value: &str) -> Result<char, String> {
+    match value {
+        "tab" | "\\t" => Ok('\t'),
+        _ => {
+            let mut chars = value.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(c),
+                _ => Err(format!("Delimiter should be one character or tab, got {}", value)),
+            }
+        }
+    }
+}
+
+fn parse_number(value: &str, key: &str) -> Result<u64, String> {
+    value.parse().map_err(|_| format!("{} expects a number, got {}", key, value))
+}
+
+/// Seconds since 1970-01-01 for `YYYY-MM-DD[ T]HH:MM[:SS[.fff]][Z]`, with `-` or `/` in the date.
+pub fn parse_datetime(raw: &str) -> Option<i64> {
+    let raw = raw.trim().trim_end_matches('Z');
+    let (date, time) = match raw.split_once([' ', 'T']) {
+        Some((date, time)) => (date, time),
+        None => (raw, "00:00"),
+    };
+    let mut date = date.split(['-', '/']).map(|p| p.parse::<i64>().ok());
+    let (y, m, d) = (date.next()??, date.next()??, date.next()??);
+    let mut time = time.split(':');
+    let h: i64 = time.next()?.parse().ok()?;
+    let min: i64 = time.next().unwrap_or("0").parse().ok()?;
+    let s: f64 = time.next().unwrap_or("0").parse().ok()?;
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        return None;
+    }
+
+    // Days since the epoch in the proleptic Gregorian calendar (Howard Hinnant's days_from_civil).
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (m + if m > 2 { -3 } else { 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    Some(days * 86400 + h * 3600 + min * 60 + s.floor() as i64)
+}
+
+/// Rows of a table, with every column resolved to an index.
+///
+/// # Returns
+/// The rows, or a message naming the first line with too few columns.
+fn read_table(table: &Table) -> Result<Rows, String> {
+    let file = File::open(&table.path).map_err(|e| format!("Could not open {}: {}", table.path.display(), e))?;
+    let mut lines = BufReader::new(file).lines().map_while(Result::ok).enumerate().map(|(i, l)| (i + 1, l));
+    let split = |line: &str| -> Vec<String> {
+        line.trim_end_matches(['\r', '\n'])
+            .split(table.delimiter)
+            .map(|cell| cell.trim().trim_matches('"').to_string())
+            .collect()
+    };
+    let header = if table.header { lines.next().map(|(_, l)| split(&l)) } else { None };
+    let indices = table
+        .columns
+        .iter()
+        .map(|(role, column)| Ok((role.clone(), column.resolve(header.as_deref(), &table.path)?)))
+        .collect::<Result<HashMap<_, _>, String>>()?;
+    let width = indices.values().copied().max().unwrap_or(0);
+    let rows = lines
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(n, l)| {
+            let row = split(&l);
+            if row.len() > width {
+                Ok((n, row))
+            } else {
+                Err(format!(
+                    "{} line {}: expected at least {} columns, found {} (check the delimiter)",
+                    table.path.display(), n, width + 1, row.len()
+                ))
+            }
+        })
+        .collect::<Result<_, String>>()?;
+    Ok((indices, rows))
+}
+
+/// Loads a dataset described by `spec` into the maps the pipeline works on.
+///
+/// # Returns
+/// The dataset, or a message if a file or column is missing or edges point at nodes without a time.
+pub fn load(spec: &DatasetSpec) -> Result<Dataset, String> {
+    let dataset = match spec.layout {
+        Layout::Nodes => load_nodes(spec)?,
+        Layout::Transactions => load_transactions(spec)?,
+    };
+
+    let missing = dataset
+        .edges
+        .iter()
+        .flat_map(|(src, targets)| std::iter::once(src).chain(targets))
+        .filter(|n| !dataset.timestamps.contains_key(*n))
+        .count();
+    if missing > 0 {
+        return Err(format!("{} edge endpoints have no time (check the times file and id columns)", missing));
+    }
+    let edge_count: usize = dataset.edges.values().map(|t| t.len()).sum();
+    println!(
+        "Loaded {} txs, {} edges, {} labeled",
+        dataset.timestamps.len(), edge_count, dataset.labels.len()
+    );
+    Ok(dataset)
+}
+
+fn load_nodes(spec: &DatasetSpec) -> Result<Dataset, String> {
+    load_nodes_with_edge_rows(spec).map(|(data, _)| data)
+}
+
+/// `load_nodes`, also handing back the edge table so `load_weighted` can read its amounts.
+fn load_nodes_with_edge_rows(spec: &DatasetSpec) -> Result<(Dataset, Rows), String> {
+    let edge_rows = read_table(&spec.tables["edges"])?;
+    let (col, rows) = &edge_rows;
+    let mut edges: HashMap<String, HashSet<String>> = HashMap::new();
+    for (_, row) in rows {
+        edges.entry(row[col["source"]].clone()).or_default().insert(row[col["target"]].clone());
+    }
+
+    let table = &spec.tables["times"];
+    let (col, rows) = read_table(table)?;
+    let times = rows
+        .iter()
+        .map(|(n, row)| Ok((row[col["id"]].clone(), spec.time(table, *n, &row[col["time"]])?)))
+        .collect::<Result<_, String>>()?;
+
+    let (col, rows) = read_table(&spec.tables["labels"])?;
+    let labels = rows
+        .iter()
+        .map(|(_, row)| (row[col["id"]].clone(), spec.label(&row[col["label"]])))
+        .collect();
+
+    Ok((Dataset { edges, timestamps: spec.timesteps(times), labels }, edge_rows))
+}
+
+fn load_transactions(spec: &DatasetSpec) -> Result<Dataset, String> {
+    let table = &spec.tables["transactions"];
+    let (col, rows) = read_table(table)?;
+
+    let mut times = HashMap::new();
+    let mut labels = HashMap::new();
+    // Account → transfers it sent, as (time, txId).
+    let mut sent: HashMap<&str, Vec<(i64, String)>> = HashMap::new();
+    let mut received: Vec<(String, &str, i64)> = Vec::new();
+    for (i, (n, row)) in rows.iter().enumerate() {
+        let time = spec.time(table, *n, &row[col["time"]])?;
+        let tx = col.get("id").map_or_else(|| i.to_string(), |&c| row[c].clone());
+        if let Some(&c) = col.get("label") {
+            labels.insert(tx.clone(), spec.label(&row[c]));
+        }
+        times.insert(tx.clone(), time);
+        sent.entry(row[col["from"]].as_str()).or_default().push((time, tx.clone()));
+        received.push((tx, row[col["to"]].as_str(), time));
+    }
+    for transfers in sent.values_mut() {
+        transfers.sort();
+    }
+
+    // A transfer into an account can fund that account's later transfers; link to the next few.
+    let mut edges: HashMap<String, HashSet<String>> = HashMap::new();
+    for (tx, account, time) in received {
+        let Some(next) = sent.get(account) else { continue };
+        let first = next.partition_point(|(t, _)| *t < time);
+        let targets: HashSet<String> = next[first..]
+            .iter()
+            .filter(|(_, other)| *other != tx)
+            .take(spec.max_successors)
+            .map(|(_, other)| other.clone())
+            .collect();
+        if !targets.is_empty() {
+            edges.insert(tx, targets);
+        }
+    }
+
+    Ok(Dataset { edges, timestamps: spec.timesteps(times), labels })
+}
+
+/// Loads the spec's transfers as a weighted graph for `flow`, with amounts from the
+/// `amount` column (1 per row without one).
+///
+/// * `nodes` layout: the same nodes as `load`, and its time-respecting edges.
+/// * `transactions` layout: the account graph, one edge per (sender, receiver) summing
+///   their transfers. An account's timestep is that of its first transfer, and its label
+///   the most suspicious label among the transfers it sent (illicit, then licit, then the rest).
+///   This graph is **not** time-respecting: an account's edges merge transfers from all
+///   timesteps, so max flow may route money out before it arrived (`time_respecting` is false).
+pub fn load_weighted(spec: &DatasetSpec) -> Result<WeightedDataset, String> {
+    let amount = |table: &Table, col: &HashMap<String, usize>, n: usize, row: &[String]| match col.get("amount") {
+        Some(&c) => row[c]
+            .parse::<f64>()
+            .map_err(|_| format!("{} line {}: cannot read amount {:?}", table.path.display(), n, row[c])),
+        None => Ok(1.0),
+    };
+
+    match spec.layout {
+        Layout::Nodes => {
+            let (data, (col, rows)) = load_nodes_with_edge_rows(spec)?;
+            let mut graph = WeightedGraph::new();
+            for (n, row) in &rows {
+                let (src, tgt) = (&row[col["source"]], &row[col["target"]]);
+                let amount = amount(&spec.tables["edges"], &col, *n, row)?;
+                if dfsstuff::time_respecting(&data.timestamps, src, tgt) {
+                    flow::add_transfer(&mut graph, src, tgt, amount, data.timestamps.get(src).copied().unwrap_or(0));
+                }
+            }
+            Ok(WeightedDataset { graph, timestamps: data.timestamps, labels: data.labels, time_respecting: true })
+        }
+        Layout::Transactions => {
+            let table = &spec.tables["transactions"];
+            let (col, rows) = read_table(table)?;
+            let times = rows
+                .iter()
+                .enumerate()
+                .map(|(i, (n, row))| Ok((i.to_string(), spec.time(table, *n, &row[col["time"]])?)))
+                .collect::<Result<_, String>>()?;
+            let steps = spec.timesteps(times);
+
+            let mut graph = WeightedGraph::new();
+            let mut timestamps: HashMap<String, usize> = HashMap::new();
+            let mut labels: HashMap<String, String> = HashMap::new();
+            for (i, (n, row)) in rows.iter().enumerate() {
+                let Some(&ts) = steps.get(&i.to_string()) else { continue };
+                let amount = amount(table, &col, *n, row)?;
+                let (from, to) = (&row[col["from"]], &row[col["to"]]);
+                flow::add_transfer(&mut graph, from, to, amount, ts);
+                for account in [from, to] {
+                    let first = timestamps.entry(account.clone()).or_insert(ts);
+                    *first = (*first).min(ts);
+                }
+                if let Some(&c) = col.get("label") {
+                    let label = spec.label(&row[c]);
+                    let current = labels.entry(from.clone()).or_insert_with(|| label.clone());
+                    if label_rank(&label) > label_rank(current) {
+                        *current = label;
+                    }
+                }
+            }
+            Ok(WeightedDataset { graph, timestamps, labels, time_respecting: false })
+        }
+    }
+}
+
+/// Illicit above licit above anything else.
+fn label_rank(label: &str) -> usize {
+    match label {
+        "2" => 2,
+        "1" => 1,
+        _ => 0,
+    }
+}
+
+/// `load_weighted` for whatever the config points at; the Elliptic CSVs have no
+/// amounts, so every edge there carries 1.
+pub fn load_weighted_configured(config: &Config) -> Result<WeightedDataset, String> {
+    match &config.dataset {
+        Some(spec) => load_weighted(&DatasetSpec::read(spec)?),
+        None => Ok(flow::from_dataset(&fileread::load_dataset(&config.data_dir)?)),
+    }
+}
+
+/// Loads the dataset the config points at: the `--dataset` spec if given,
+/// otherwise the Elliptic CSVs in `--data-dir`.
+pub fn load_configured(config: &Config) -> Result<Dataset, String> {
+    match &config.dataset {
+        Some(spec) => load(&DatasetSpec::read(spec)?),
+        None => fileread::load_dataset(&config.data_dir),
+    }
+}
@@ -0,0 +1,622 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::fs::File;
+use rand::prelude::IndexedRandom;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::annotations::{self, Annotation};
+use crate::budget::{BudgetTracker, SearchStatus};
+use crate::checkpoint::{Checkpoint, RunRecord};
+use crate::community;
+use crate::config::{Config, Estimator};
+use crate::dfsstuff::{self, PathReservoir};
+use crate::explain::{self, Explanation};
+use crate::progress::Progress;
+use crate::scoring::{self, ReuseTally, ScoringContext, ScoringMethod};
+use crate::targets::{self, TargetContext};
+use crate::walks;
+
+/// Calculates reachable nodes from given starting points using timestamp-filtered DFS.
+/// 
+/// # Arguments
+/// * `graph` - Adjacency list of the transaction graph.
+/// * `timestamps` - Node → timestamp map.
+/// * `start_nodes` - List of start nodes (e.g. illicit or licit).
+/// * `depth` - Max depth to explore.
+///
+/// # Returns
+/// A map from each reachable node to how many start nodes reach it
+/// (the keys are the reachable set used to define meaningful DFS targets).
+pub fn reachable_calculator(
+    graph: &HashMap<String, HashSet<String>>,
+    timestamps: &HashMap<String, usize>,
+    start_nodes: &Vec<String>,
+    depth: usize,
+) -> HashMap<String, usize> {
+    let mut reach_count = HashMap::new();
+    let mut visited = HashSet::new();
+    for start in start_nodes {
+        let mut reachable = HashSet::new();
+        dfsstuff::dfs_collect_reachable(
+            graph,
+            timestamps,
+            start,
+            1,
+            &mut visited,
+            &mut reachable,
+            depth,
+        );
+        for node in reachable {
+            *reach_count.entry(node).or_insert(0) += 1;
+        }
+    }
+    reach_count
+}
+
+
+/// Samples N nodes from a given list.
+/// 
+/// # Arguments
+/// * `nodes` - Vector of node IDs as Strings.
+/// * `limit` - Number of nodes to sample.
+/// * `rng` - Random source; seed it for a reproducible sample.
+///
+/// # Returns
+/// A randomly sampled vector of node IDs.
+pub fn sampler<R: Rng + ?Sized>(nodes: Vec<String>, limit: usize, rng: &mut R) -> Vec<String> {
+    let sample: Vec<String> = nodes
+        .choose_multiple(rng, limit)
+        .cloned()
+        .collect();
+    sample
+}
+
+
+/// Keys of `reach_count` in sorted order, so sampling from them doesn't depend on hash order.
+fn reachable_sorted(reach_count: &HashMap<String, usize>) -> Vec<String> {
+    let mut nodes: Vec<String> = reach_count.keys().cloned().collect();
+    nodes.sort();
+    nodes
+}
+
+/// Runs the full pipeline on a labeled node group (e.g. illicit, licit or unknown).
+/// 
+/// # Arguments
+/// * `edges` - Full transaction graph.
+/// * `successors` - The same graph as sorted neighbour lists (`dfsstuff::successors`), for the path searches.
+/// * `timestamps` - Timestamps of each node.
+/// * `labels` - Node → class label, used by label-aware target strategies.
+/// * `start_nodes` - Labeled starting points.
+/// * `label` - Group name such as `"illicit"` or `"unknown"` (for logging).
+/// * `config` - Depth/path caps and the target selection strategy.
+/// * `rng` - Drives target sampling and the path reservoirs, so a seeded run repeats exactly.
+///
+/// # Returns
+/// A tally of intermediary nodes appearing in filtered paths: how often,
+/// from which starts, and how widely each node is reachable.
+#[allow(clippy::too_many_arguments)]
+pub fn theory_tester(
+    edges: &HashMap<String, HashSet<String>>,
+    successors: &HashMap<String, Vec<String>>,
+    timestamps: &HashMap<String, usize>,
+    labels: &HashMap<String, String>,
+    start_nodes: &Vec<String>,
+    label: &str,  // group name
+    config: &Config,
+    rng: &mut StdRng,
+) -> ReuseTally {
+    let max_depth = config.max_depth;
+
+    let reach_count = reachable_calculator(edges, timestamps, start_nodes, max_depth); // adjust if needed
+    println!("[{}] Reachable count: {}", label, reach_count.len());
+
+    let mut degrees_reachable: Vec<(String, usize)> = reach_count
+        .keys()
+        .filter_map(|node| edges.get(node).map(|neighbors| (node.clone(), neighbors.len())))
+        .collect();
+
+    degrees_reachable.sort_by_key(|(_, degree)| std::cmp::Reverse(*degree));
+
+    let top_outdegree_reachable: Vec<String> = degrees_reachable
+        .into_iter()
+        .take(100)
+        .map(|(node, _)| node)
+        .collect();
+
+    let target_ctx = TargetContext {
+        graph: edges,
+        timestamps,
+        labels,
+        start_nodes,
+        reachable: reachable_sorted(&reach_count),
+    };
+    let selection = targets::select_targets(config.targets, &target_ctx, rng);
+    if let Some(reachable) = selection.sampled_from {
+        println!("[{}] All {} reachable nodes exceed the pair budget, sampling {}", label, reachable, selection.targets.len());
+    }
+    let sampled_targets = selection.targets;
+    println!("[{}] Target strategy: {}, {} targets", label, config.targets, sampled_targets.len());
+
+    let mut progress = Progress::new("summary", start_nodes.len() * sampled_targets.len());
+    let (stats, statuses) = dfsstuff::summarize_paths_to_targets(
+        successors, timestamps, start_nodes, &sampled_targets, max_depth, &config.budget, Some(&mut progress),
+    );
+    let cut_off = statuses.values().filter(|s| **s != SearchStatus::Finished).count();
+    println!("[{}] {} of {} summary searches cut off", label, cut_off, statuses.len());
+    
+    let mut stat_entries: Vec<_> = stats.iter().collect();
+    stat_entries.sort_by(|a, b| b.1.0.cmp(&a.1.0).then_with(|| a.0.cmp(b.0)));  // by path count, ties by pair
+    let top_pairs: Vec<_> = stat_entries.into_iter().take(10).collect();
+
+    let mut progress = Progress::new("collect", top_pairs.len());
+    let mut collect_log = Vec::new();
+    let mut node_freq = HashMap::new();
+    let mut node_starts: HashMap<String, HashSet<String>> = HashMap::new();
+    let mut all_paths = Vec::new();
+
+    for ((start, target), _) in top_pairs {
+        let mut visited = HashSet::new();
+        let mut path = Vec::new();
+        let mut reservoir = PathReservoir::seeded(config.reservoir_size, rng.random());
+        let mut tracker = BudgetTracker::new(config.budget);
+    
+        dfsstuff::dfs_sample_paths(
+            successors, timestamps,
+            start, target,
+            &mut path, &mut reservoir, &mut visited,
+            1, config.collect_depth, &mut tracker,
+        );
+        progress.tick(tracker.exhausted());
+    
+        collect_log.push(format!(
+            "[{}] {} → {} has {} full paths, kept {} ({})",
+            label, start, target, reservoir.total, reservoir.paths.len(), tracker.status()
+        ));
+
+        // Each pair contributes at most `reservoir_size` paths, so dense pairs don't dominate.
+        for path in &reservoir.paths {
+            for node in &path[1..path.len() - 1] {
+                *node_freq.entry(node.clone()).or_insert(0) += 1;
+                node_starts.entry(node.clone()).or_default().insert(path[0].clone());
+            }
+        }
+        all_paths.extend(reservoir.paths);
+    }
+    progress.finish();
+    for line in collect_log {
+        println!("{}", line);
+    }
+
+    let high_degree_set: HashSet<String> = top_outdegree_reachable.iter().cloned().collect();
+
+    for (node, freq) in &node_freq {
+        // println!("[{}] Non High-degree hub reused: {} ({} times)", label, node, freq);
+        if high_degree_set.contains(node) {
+            println!("[{}] High-degree hub reused: {} ({} times)", label, node, freq); // comparison between intermediary and high-deg nodes
+        }
+    }
+
+    println!("[{}] Done.\n", label);
+    ReuseTally {
+        node_freq,
+        node_starts,
+        reach_count,
+        num_starts: start_nodes.len(),
+        paths: all_paths,
+    }
+}
+
+/// Builds the reuse tally for one group with the configured estimator:
+/// exhaustive DFS (`theory_tester`) or time-respecting random walks.
+///
+/// `successors` is `dfsstuff::successors(edges)`, built once by the caller.
+#[allow(clippy::too_many_arguments)]
+pub fn reuse_tally(
+    edges: &HashMap<String, HashSet<String>>,
+    successors: &HashMap<String, Vec<String>>,
+    timestamps: &HashMap<String, usize>,
+    labels: &HashMap<String, String>,
+    start_nodes: &Vec<String>,
+    label: &str,
+    config: &Config,
+    rng: &mut StdRng,
+) -> ReuseTally {
+    match config.estimator {
+        Estimator::Dfs => theory_tester(edges, successors, timestamps, labels, start_nodes, label, config, rng),
+        Estimator::Walk => {
+            let estimate = walks::estimate_reuse(edges, timestamps, start_nodes, config.walks_per_start, config.walk_length, rng);
+            walks::print_top_rates(&estimate, label, 10);
+            println!("[{}] Done.\n", label);
+            estimate.tally
+        }
+    }
+}
+
+/// One mixer candidate's scores across runs, with its context.
+pub struct MixerStats {
+    pub node: String,
+    pub scores: Vec<f64>,
+    pub mean: f64,
+    pub stddev: f64,
+    pub ci_low: f64,
+    pub ci_high: f64,
+    pub entity: Option<String>, // entity type from the annotation file, if any
+    pub community: Option<usize>, // Louvain community, when --communities is on
+    pub communities_bridged: Option<usize>, // communities touched by paths reusing this node
+    pub reuse: Vec<usize>, // reuse count per label group, summed over runs
+}
+
+
+/// Computes the "mixer score" of nodes based on frequency imbalance between two label groups.
+/// 
+/// # Arguments
+/// * `tallies` - Reuse tally per label group, in `config.groups` order.
+/// * `target`, `baseline` - Indices of the contrasted groups (e.g. illicit vs licit).
+/// * `degrees` - Node → total degree, used by degree-aware scores.
+/// * `method` - Which scoring function to apply.
+///
+/// # Returns
+/// A list of tuples with: node ID, freq per group, and mixer score, for every
+/// node reused by the target or baseline group.
+pub fn compute_mixer_data(
+    tallies: &[ReuseTally],
+    target: usize,
+    baseline: usize,
+    degrees: &HashMap<String, usize>,
+    method: ScoringMethod,
+) -> Vec<(String, Vec<u32>, f64)> {
+    let mut mixer_data = Vec::new();
+    let context = ScoringContext::new(&tallies[target], &tallies[baseline], degrees);
+    let all_nodes: HashSet<_> = 
+        tallies[target].node_freq.keys()
+        .chain(tallies[baseline].node_freq.keys())
+        .collect();
+
+    for node in all_nodes {
+        let freqs = tallies
+            .iter()
+            .map(|t| *t.node_freq.get(node).unwrap_or(&0) as u32)
+            .collect();
+        let score = context.score(method, node);
+        mixer_data.push((node.clone(), freqs, score));
+    }
+
+    mixer_data
+}
+
+/// Aggregates mixer scores from repeated sampling, computing mean and confidence intervals.
+/// 
+/// # Arguments
+/// * `score_map` - Node → list of scores across sampling runs.
+/// * `annotations` - Known entities; matching candidates are tagged with their entity type.
+///
+/// # Returns
+/// A sorted list of mixer candidates and stats (mean, stddev, CI).
+pub fn summarize_scores(
+    score_map: HashMap<String, Vec<f64>>,
+    annotations: &HashMap<String, Annotation>,
+) -> Vec<MixerStats> {
+    let mut result = vec![];
+    for (node, scores) in score_map {
+        let n = scores.len() as f64;
+        let mean = scores.iter().copied().sum::<f64>() / n;
+        let variance = scores.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / n;
+        let stddev = variance.sqrt();
+        let ci_margin = 1.96 * stddev / n.sqrt(); // 95% CI
+
+        result.push(MixerStats {
+            scores,
+            mean,
+            stddev,
+            ci_low: mean - ci_margin,
+            ci_high: mean + ci_margin,
+            entity: annotations.get(&node).map(|a| a.entity_type.clone()),
+            community: None,
+            communities_bridged: None,
+            reuse: Vec::new(),
+            node,
+        });
+    }
+
+    // A NaN mean comes from a degenerate tally, so it ranks last rather than first.
+    result.sort_by(|a, b| {
+        a.mean.is_nan().cmp(&b.mean.is_nan())
+            .then_with(|| b.mean.total_cmp(&a.mean))
+            .then_with(|| a.node.cmp(&b.node))
+    });
+    result
+}
+
+/// Writes the full ranked candidate list to a CSV file.
+///
+/// # Arguments
+/// * `path` - Output file.
+/// * `stats` - Ranked candidates from `summarize_scores`.
+/// * `annotations` - Known entities, used to fill the entity and note columns.
+/// * `config` - Recorded alongside each row (scoring method, target strategy).
+pub fn export_stats(
+    path: &str,
+    stats: &[MixerStats],
+    annotations: &HashMap<String, Annotation>,
+    config: &Config,
+) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
+    let reuse_header: String = config.groups.iter().map(|g| format!(",reuse_{}", g.name)).collect();
+    writeln!(file, "rank,txId,mean,stddev,ci_low,ci_high,runs,entity_type,note,score,targets,contrast,community,communities_bridged{}", reuse_header)?;
+    for (rank, stat) in stats.iter().enumerate() {
+        let note = annotations.get(&stat.node).map_or("", |a| a.note.as_str());
+        let reuse: String = stat.reuse.iter().map(|r| format!(",{}", r)).collect();
+        writeln!(
+            file,
+            "{},{},{},{},{},{},{},{},\"{}\",{},{},{}:{},{},{}{}",
+            rank + 1,
+            stat.node,
+            stat.mean,
+            stat.stddev,
+            stat.ci_low,
+            stat.ci_high,
+            stat.scores.len(),
+            stat.entity.as_deref().unwrap_or(""),
+            note.replace('"', "\"\""),
+            config.scoring.name(),
+            config.targets.name(),
+            config.contrast.target,
+            config.contrast.baseline,
+            stat.community.map_or(String::new(), |c| c.to_string()),
+            stat.communities_bridged.map_or(String::new(), |c| c.to_string()),
+            reuse,
+        )?;
+    }
+    Ok(())
+}
+
+
+/// Builder for the full mixer experiment on an in-memory graph.
+///
+/// Everything it holds is owned, so an `Analysis` can be built once and kept
+/// inside a service or behind an FFI handle.
+///
+/// ```no_run
+/// use transactionbased::analysis::Analysis;
+/// use transactionbased::config::Config;
+/// use transactionbased::fileread;
+///
+/// let data = fileread::load_dataset("../..").expect("Elliptic CSVs in ../..");
+/// let result = Analysis::new(data.edges, data.timestamps, data.labels)
+///     .with_config(Config { num_runs: 3, ..Config::default() })
+///     .run()
+///     .expect("checkpoint matches config");
+/// for stat in result.stats.iter().take(5) {
+///     println!("{} {:.2}", stat.node, stat.mean);
+/// }
+/// ```
+pub struct Analysis {
+    edges: HashMap<String, HashSet<String>>,
+    successors: HashMap<String, Vec<String>>,
+    timestamps: HashMap<String, usize>,
+    labels: HashMap<String, String>,
+    config: Config,
+    annotations: HashMap<String, Annotation>,
+}
+
+/// What `Analysis::run` returns.
+pub struct AnalysisResult {
+    /// Candidates ranked by mean score, highest first.
+    pub stats: Vec<MixerStats>,
+    /// Number of nodes in each of `config.groups`.
+    pub group_sizes: Vec<usize>,
+    /// Louvain community per node and its modularity, when `config.communities` is on.
+    pub communities: Option<(HashMap<String, usize>, f64)>,
+}
+
+impl Analysis {
+    /// Starts an analysis with the default `Config` and no annotations.
+    pub fn new(
+        edges: HashMap<String, HashSet<String>>,
+        timestamps: HashMap<String, usize>,
+        labels: HashMap<String, String>,
+    ) -> Self {
+        Analysis {
+            successors: dfsstuff::successors(&edges),
+            edges,
+            timestamps,
+            labels,
+            config: Config::default(),
+            annotations: HashMap::new(),
+        }
+    }
+
+    /// Replaces the config (sampling, depth caps, scoring, groups, ...).
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Known entities, applied with `config.annotation_action`.
+    pub fn with_annotations(mut self, annotations: HashMap<String, Annotation>) -> Self {
+        self.annotations = annotations;
+        self
+    }
+
+    pub fn edges(&self) -> &HashMap<String, HashSet<String>> {
+        &self.edges
+    }
+
+    /// `edges` as sorted neighbour lists, built once in `new` for the path searches.
+    pub fn successors(&self) -> &HashMap<String, Vec<String>> {
+        &self.successors
+    }
+
+    pub fn timestamps(&self) -> &HashMap<String, usize> {
+        &self.timestamps
+    }
+
+    pub fn labels(&self) -> &HashMap<String, String> {
+        &self.labels
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn annotations(&self) -> &HashMap<String, Annotation> {
+        &self.annotations
+    }
+
+    /// All nodes whose label belongs to each of `config.groups`, in group order.
+    ///
+    /// Each list is sorted, so seeded sampling from it is reproducible.
+    pub fn group_nodes(&self) -> Vec<Vec<String>> {
+        self.config.groups.iter().map(|group| group.members(&self.labels)).collect()
+    }
+
+    /// Runs `config.num_runs` rounds of sampling, reuse tallying and scoring.
+    ///
+    /// With `config.checkpoint` set, every finished run is written to that
+    /// directory and runs already there are loaded instead of recomputed.
+    ///
+    /// # Returns
+    /// The ranked candidates with per-group reuse, entity tags and (optionally)
+    /// community context, or why the contrast or the checkpoint couldn't be used.
+    pub fn run(&self) -> Result<AnalysisResult, String> {
+        let config = &self.config;
+        let contrast = config.contrast_indices()?;
+        let degrees = scoring::total_degrees(&self.edges);
+        let mut score_map: HashMap<String, Vec<f64>> = HashMap::new();
+
+        let communities = config.communities.then(|| community::louvain(&self.edges));
+        let mut bridged: HashMap<String, HashSet<usize>> = HashMap::new();
+
+        let group_nodes = self.group_nodes();
+        for (group, nodes) in config.groups.iter().zip(&group_nodes) {
+            println!("Group {}: {} nodes ({})", group.name, nodes.len(), group.labels.join("+"));
+        }
+        let mut reuse_map: HashMap<String, Vec<usize>> = HashMap::new();
+
+        let (checkpoint, seed) = match &config.checkpoint {
+            Some(dir) => {
+                let (checkpoint, seed) = Checkpoint::open(dir, &config.settings(), config.seed)?;
+                (Some(checkpoint), seed)
+            }
+            None => (None, config.seed.unwrap_or_else(rand::random)),
+        };
+        println!("Base seed: {}", seed);
+
+        for run in 0..config.num_runs {
+            let saved = match checkpoint.as_ref().map(|c| c.load_run(run, &config.groups)) {
+                Some(Ok(saved)) => saved,
+                Some(Err(msg)) => {
+                    eprintln!("Run {} could not be read from the checkpoint ({}), redoing it", run, msg);
+                    None
+                }
+                None => None,
+            };
+            let record = match saved {
+                Some(record) => {
+                    println!("Run {} loaded from checkpoint", run);
+                    record
+                }
+                None => {
+                    let record = self.run_once(seed.wrapping_add(run as u64), &group_nodes, contrast, &degrees, communities.as_ref().map(|c| &c.0));
+                    if let Some(checkpoint) = &checkpoint {
+                        checkpoint
+                            .save_run(run, &config.groups, &record)
+                            .map_err(|e| format!("Could not write checkpoint run {}: {}", run, e))?;
+                    }
+                    record
+                }
+            };
+
+            for (node, score) in record.scores {
+                let reuse = reuse_map.entry(node.clone()).or_insert_with(|| vec![0; config.groups.len()]);
+                for (total, freq) in reuse.iter_mut().zip(&record.node_freq) {
+                    *total += freq.get(&node).copied().unwrap_or(0);
+                }
+                score_map.entry(node).or_default().push(score);
+            }
+            for (node, ids) in record.bridged {
+                bridged.entry(node).or_default().extend(ids);
+            }
+        }
+
+        let mut stats = summarize_scores(score_map, &self.annotations);
+        for stat in stats.iter_mut() {
+            stat.reuse = reuse_map.remove(&stat.node).unwrap_or_default();
+        }
+        if let Some((communities, _)) = &communities {
+            for stat in stats.iter_mut() {
+                stat.community = communities.get(&stat.node).copied();
+                stat.communities_bridged = Some(bridged.get(&stat.node).map_or(0, |c| c.len()));
+            }
+        }
+
+        Ok(AnalysisResult {
+            stats,
+            group_sizes: group_nodes.iter().map(|n| n.len()).collect(),
+            communities,
+        })
+    }
+
+    /// Samples up to `sample_size` start nodes from each group's (sorted) members.
+    fn sample_groups(&self, rng: &mut StdRng, group_nodes: &[Vec<String>]) -> Vec<Vec<String>> {
+        group_nodes.iter().map(|nodes| nodes.choose_multiple(rng, self.config.sample_size).cloned().collect()).collect()
+    }
+
+    /// The start nodes, per group, of the run seeded with `seed`.
+    ///
+    /// Run `r` of `run` is seeded with the base seed plus `r`, so the base seed gives run 0's starts.
+    pub fn run_starts(&self, seed: u64) -> Vec<Vec<String>> {
+        self.sample_groups(&mut StdRng::seed_from_u64(seed), &self.group_nodes())
+    }
+
+    /// Explains `tx` from the starts of the run seeded with `seed`, every group included.
+    pub fn explain(&self, tx: &String, seed: u64) -> Explanation {
+        let mut starts: Vec<String> = Vec::new();
+        for start in self.run_starts(seed).into_iter().flatten() {
+            if !starts.contains(&start) {
+                starts.push(start);
+            }
+        }
+        explain::explain_node(&self.edges, &self.timestamps, &self.labels, tx, &starts, self.config.max_depth, &self.config.budget)
+    }
+
+    /// One sampling run: sample every group with `seed`, tally reuse and score `target` against `baseline`.
+    fn run_once(
+        &self,
+        seed: u64,
+        group_nodes: &[Vec<String>],
+        (target, baseline): (usize, usize),
+        degrees: &HashMap<String, usize>,
+        communities: Option<&HashMap<String, usize>>,
+    ) -> RunRecord {
+        let config = &self.config;
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut bridged: HashMap<String, HashSet<usize>> = HashMap::new();
+        let mut tallies = Vec::new();
+
+        let starts = self.sample_groups(&mut rng, group_nodes);
+        for (group, sampled) in config.groups.iter().zip(&starts) {
+            let mut tally = reuse_tally(&self.edges, &self.successors, &self.timestamps, &self.labels, sampled, &group.name, config, &mut rng);
+            annotations::apply_to_tally(&mut tally, &self.annotations, config.annotation_action);
+            if let Some(communities) = communities {
+                community::tally_bridging(&tally.paths, communities, &mut bridged);
+            }
+            tallies.push(tally);
+        }
+
+        let scores = compute_mixer_data(&tallies, target, baseline, degrees, config.scoring)
+            .into_iter()
+            .map(|(node, _, score)| {
+                let score = annotations::weighted_score(&node, score, &self.annotations, config.annotation_action, config.annotation_weight);
+                (node, score)
+            })
+            .collect();
+
+        RunRecord {
+            node_freq: tallies.into_iter().map(|t| t.node_freq).collect(),
+            scores,
+            bridged,
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::fs::File;
+use std::str::FromStr;
+
+use crate::scoring::ReuseTally;
+
+/// External knowledge about a txId, e.g. that it belongs to an exchange.
+pub struct Annotation {
+    pub entity_type: String,
+    pub note: String,
+}
+
+/// What to do with annotated nodes when ranking mixer candidates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AnnotationAction {
+    /// Drop annotated nodes from the reuse counts entirely.
+    Exclude,
+    /// Keep them, but push their score down by the configured weight (see `weighted_score`).
+    DownWeight,
+    /// Leave scores alone and only label them in the output.
+    Tag,
+}
+
+impl FromStr for AnnotationAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "exclude" => Ok(AnnotationAction::Exclude),
+            "downweight" => Ok(AnnotationAction::DownWeight),
+            "tag" => Ok(AnnotationAction::Tag),
+            _ => Err(format!(
+                "Unknown annotation action {} (expected exclude, downweight or tag)",
+                s
+            )),
+        }
+    }
+}
+
+/// Reads an annotation file with lines of `txId,entity_type,note`.
+///
+/// A header line starting with `txId` is skipped, blank lines and lines
+/// starting with `#` are ignored, and the note may itself contain commas.
+///
+/// # Returns
+/// Map from txId → annotation, or a message naming the file and the bad line.
+pub fn read_annotations(path: &str) -> Result<HashMap<String, Annotation>, String> {
+    let mut result = HashMap::new();
+    let file = File::open(path).map_err(|e| format!("Could not open annotation file {}: {}", path, e))?;
+    for (number, line) in std::io::BufReader::new(file).lines().enumerate() {
+        let line_str = line.map_err(|e| format!("Could not read {}: {}", path, e))?;
+        let line_str = line_str.trim();
+        if line_str.is_empty() || line_str.starts_with('#') {
+            continue;
+        }
+
+        let v: Vec<&str> = line_str.splitn(3, ',').map(|field| field.trim()).collect();
+        if v[0] == "txId" {
+            continue;
+        }
+        if v.len() < 2 {
+            return Err(format!("{} line {}: annotation needs at least txId and entity type: {}", path, number + 1, line_str));
+        }
+
+        result.insert(
+            v[0].to_string(),
+            Annotation {
+                entity_type: v[1].to_string(),
+                note: v.get(2).unwrap_or(&"").to_string(),
+            },
+        );
+    }
+
+    Ok(result)
+}
+
+/// Removes annotated nodes from a reuse tally when the action is `Exclude`.
+///
+/// Their counts go, and so do the collected paths through them, so they no longer
+/// show up in exposure or explain output either. Other actions are applied to scores, not counts, so the tally is left as is.
+pub fn apply_to_tally(
+    tally: &mut ReuseTally,
+    annotations: &HashMap<String, Annotation>,
+    action: AnnotationAction,
+) {
+    if action != AnnotationAction::Exclude {
+        return;
+    }
+    for node in annotations.keys() {
+        tally.node_freq.remove(node);
+        tally.node_starts.remove(node);
+        tally.reach_count.remove(node);
+    }
+    tally.paths.retain(|path| !path.iter().any(|n| annotations.contains_key(n)));
+}
+
+/// A node's score after the annotation action.
+///
+/// `DownWeight` moves an annotated node's score down by `(1 - weight) * |score|`:
+/// positive scores shrink toward 0 and negative ones (LogOdds) move further below 0,
+/// so known entities are demoted whatever the sign.
+pub fn weighted_score(
+    node: &str,
+    score: f64,
+    annotations: &HashMap<String, Annotation>,
+    action: AnnotationAction,
+    weight: f64,
+) -> f64 {
+    if action == AnnotationAction::DownWeight && annotations.contains_key(node) {
+        score - (1.0 - weight) * score.abs()
+    } else {
+        score
+    }
+}
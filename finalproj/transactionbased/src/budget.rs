@@ -0,0 +1,104 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Limits for a single (start, target) search. `None` means unlimited.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SearchBudget {
+    /// Maximum number of nodes the DFS may expand.
+    pub max_expansions: Option<usize>,
+    /// Wall-clock limit for the search.
+    pub time_limit: Option<Duration>,
+    /// Maximum number of paths collected for the pair.
+    pub max_paths: Option<usize>,
+}
+
+/// Why a search stopped early.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CutReason {
+    Expansions,
+    Deadline,
+    Paths,
+}
+
+/// How a search ended.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SearchStatus {
+    Finished,
+    CutOff(CutReason),
+}
+
+impl fmt::Display for SearchStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SearchStatus::Finished => write!(f, "finished"),
+            SearchStatus::CutOff(CutReason::Expansions) => write!(f, "cut off (expansions)"),
+            SearchStatus::CutOff(CutReason::Deadline) => write!(f, "cut off (deadline)"),
+            SearchStatus::CutOff(CutReason::Paths) => write!(f, "cut off (paths)"),
+        }
+    }
+}
+
+/// Counts work done by one search and says when its budget runs out.
+///
+/// The DFS functions call `expand` before visiting a node and `record_path`
+/// after finding one; once either returns `false` they unwind without doing more work.
+pub struct BudgetTracker {
+    budget: SearchBudget,
+    started: Instant,
+    expansions: usize,
+    paths: usize,
+    status: SearchStatus,
+}
+
+impl BudgetTracker {
+    pub fn new(budget: SearchBudget) -> Self {
+        BudgetTracker {
+            budget,
+            started: Instant::now(),
+            expansions: 0,
+            paths: 0,
+            status: SearchStatus::Finished,
+        }
+    }
+
+    /// Records one node expansion. Returns `false` if the search must stop.
+    pub fn expand(&mut self) -> bool {
+        if self.exhausted() {
+            return false;
+        }
+        self.expansions += 1;
+        if self.budget.max_expansions.is_some_and(|max| self.expansions > max) {
+            self.status = SearchStatus::CutOff(CutReason::Expansions);
+            return false;
+        }
+        // Checking the clock on every node is measurable, so only look every 1024 expansions.
+        if self.expansions.is_multiple_of(1024)
+            && self.budget.time_limit.is_some_and(|limit| self.started.elapsed() > limit)
+        {
+            self.status = SearchStatus::CutOff(CutReason::Deadline);
+            return false;
+        }
+        true
+    }
+
+    /// Records one found path. Returns `false` (and doesn't count it) if the cap was already reached.
+    ///
+    /// A search that finds exactly `max_paths` paths and then runs out of paths on
+    /// its own still counts as finished; only a path beyond the cap cuts it off.
+    pub fn record_path(&mut self) -> bool {
+        if self.budget.max_paths.is_some_and(|max| self.paths >= max) {
+            self.status = SearchStatus::CutOff(CutReason::Paths);
+            return false;
+        }
+        self.paths += 1;
+        true
+    }
+
+    pub fn exhausted(&self) -> bool {
+        self.status != SearchStatus::Finished
+    }
+
+    pub fn status(&self) -> SearchStatus {
+        self.status
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use crate::scoring::LabelGroup;
+
+/// Everything one sampling run contributes to the final ranking.
+pub struct RunRecord {
+    /// Reuse counts per label group, in `config.groups` order (after annotations).
+    pub node_freq: Vec<HashMap<String, usize>>,
+    /// Weighted mixer score of every node scored in this run.
+    pub scores: HashMap<String, f64>,
+    /// Communities touched by this run's paths, per intermediary (empty without `--communities`).
+    pub bridged: HashMap<String, HashSet<usize>>,
+}
+
+/// A directory holding the config, seed and finished runs of one experiment.
+///
+/// Layout:
+/// * `config.txt` — `key=value` settings the runs depend on, plus the base seed.
+/// * `run_<i>/node_freq_<group>.csv`, `scores.csv`, `bridged.csv` — one finished run.
+/// * `run_<i>/done` — written last, so a run killed halfway is redone.
+pub struct Checkpoint {
+    dir: PathBuf,
+}
+
+impl Checkpoint {
+    /// Opens (or creates) a checkpoint directory for the given settings.
+    ///
+    /// # Arguments
+    /// * `dir` - Checkpoint directory.
+    /// * `settings` - `key=value` pairs that must match for runs to be reused.
+    /// * `seed` - Base seed asked for on the command line, if any.
+    ///
+    /// # Returns
+    /// The checkpoint and the base seed to use: the stored one when resuming,
+    /// otherwise `seed` or a fresh random one.
+    pub fn open(dir: &str, settings: &[(&str, String)], seed: Option<u64>) -> Result<(Checkpoint, u64), String> {
+        let dir = PathBuf::from(dir);
+        let config_path = dir.join("config.txt");
+
+        if config_path.exists() {
+            let stored = read_settings(&config_path)?;
+            for (key, value) in settings {
+                if stored.get(*key) != Some(value) {
+                    return Err(format!(
+                        "Checkpoint {} was written with {}={}, not {} (use another --checkpoint dir)",
+                        dir.display(), key, stored.get(*key).map_or("<unset>", |v| v.as_str()), value
+                    ));
+                }
+            }
+            let stored_seed: u64 = stored
+                .get("seed")
+                .and_then(|s| s.parse().ok())
+                .ok_or_else(|| format!("{} has no seed", config_path.display()))?;
+            if seed.is_some_and(|s| s != stored_seed) {
+                return Err(format!("Checkpoint {} was written with seed {}", dir.display(), stored_seed));
+            }
+            return Ok((Checkpoint { dir }, stored_seed));
+        }
+
+        let seed = seed.unwrap_or_else(rand::random);
+        fs::create_dir_all(&dir).map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+        let mut file = File::create(&config_path).map_err(|e| e.to_string())?;
+        for (key, value) in settings {
+            writeln!(file, "{}={}", key, value).map_err(|e| e.to_string())?;
+        }
+        writeln!(file, "seed={}", seed).map_err(|e| e.to_string())?;
+        Ok((Checkpoint { dir }, seed))
+    }
+
+    fn run_dir(&self, run: usize) -> PathBuf {
+        self.dir.join(format!("run_{:03}", run))
+    }
+
+    /// Reads a finished run back, or `None` if it never completed.
+    ///
+    /// # Returns
+    /// An error naming the file if a finished run can't be read or parsed; the caller redoes the run.
+    pub fn load_run(&self, run: usize, groups: &[LabelGroup]) -> Result<Option<RunRecord>, String> {
+        let dir = self.run_dir(run);
+        if !dir.join("done").exists() {
+            return Ok(None);
+        }
+
+        let node_freq = groups
+            .iter()
+            .map(|g| {
+                let path = dir.join(format!("node_freq_{}.csv", g.name));
+                read_rows(&path)?
+                    .into_iter()
+                    .map(|(node, freq)| Ok((node, parse_value(&path, &freq)?)))
+                    .collect()
+            })
+            .collect::<Result<_, String>>()?;
+        let path = dir.join("scores.csv");
+        let scores = read_rows(&path)?
+            .into_iter()
+            .map(|(node, score)| Ok((node, parse_value(&path, &score)?)))
+            .collect::<Result<_, String>>()?;
+        let path = dir.join("bridged.csv");
+        let bridged = read_rows(&path)?
+            .into_iter()
+            .map(|(node, ids)| Ok((node, ids.split(';').filter(|c| !c.is_empty()).map(|c| parse_value(&path, c)).collect::<Result<_, _>>()?)))
+            .collect::<Result<_, String>>()?;
+
+        Ok(Some(RunRecord { node_freq, scores, bridged }))
+    }
+
+    /// Writes one finished run, marking it done only after every file is written.
+    pub fn save_run(&self, run: usize, groups: &[LabelGroup], record: &RunRecord) -> std::io::Result<()> {
+        let dir = self.run_dir(run);
+        fs::create_dir_all(&dir)?;
+
+        for (group, freq) in groups.iter().zip(&record.node_freq) {
+            write_rows(&dir.join(format!("node_freq_{}.csv", group.name)), "txId,freq", freq)?;
+        }
+        write_rows(&dir.join("scores.csv"), "txId,score", &record.scores)?;
+        let bridged: HashMap<&String, String> = record
+            .bridged
+            .iter()
+            .map(|(node, ids)| {
+                let mut ids: Vec<usize> = ids.iter().copied().collect();
+                ids.sort();
+                (node, ids.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(";"))
+            })
+            .collect();
+        write_rows(&dir.join("bridged.csv"), "txId,communities", &bridged)?;
+
+        File::create(dir.join("done"))?;
+        Ok(())
+    }
+}
+
+fn read_settings(path: &Path) -> Result<HashMap<String, String>, String> {
+    let file = File::open(path).map_err(|e| format!("Could not open {}: {}", path.display(), e))?;
+    Ok(BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| line.split_once('=').map(|(k, v)| (k.to_string(), v.to_string())))
+        .collect())
+}
+
+/// `key,value` rows of a checkpoint CSV, skipping the header. A missing file has no rows.
+fn read_rows(path: &Path) -> Result<Vec<(String, String)>, String> {
+    let lines: Vec<String> = match File::open(path) {
+        Ok(file) => BufReader::new(file)
+            .lines()
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e))?,
+        Err(_) => Vec::new(),
+    };
+    lines
+        .iter()
+        .skip(1)
+        .map(|line| {
+            line.split_once(',')
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .ok_or_else(|| format!("{}: bad row {}", path.display(), line))
+        })
+        .collect()
+}
+
+fn parse_value<T: std::str::FromStr>(path: &Path, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("{}: bad value {}", path.display(), value))
+}
+
+fn write_rows<K: std::fmt::Display, V: std::fmt::Display>(
+    path: &Path,
+    header: &str,
+    rows: &HashMap<K, V>,
+) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
+    writeln!(file, "{}", header)?;
+    for (key, value) in rows {
+        writeln!(file, "{},{}", key, value)?;
+    }
+    Ok(())
+}
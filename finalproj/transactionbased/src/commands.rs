@@ -0,0 +1,396 @@
+use std::collections::{BTreeMap, HashMap};
+use std::net::TcpListener;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::analysis::{self, Analysis, MixerStats};
+use crate::annotations::{self, Annotation};
+use crate::centrality::{self, PivotMode};
+use crate::config::{Command, Config};
+use crate::fileread::Dataset;
+use crate::scoring::LabelGroup;
+use crate::server::{self, ServerState};
+use crate::{adapter, community, cycles, diff, explain, features, flow, repl, sketch, stream, structure, synthetic, temporal};
+
+/// Runs one parsed command line to completion, printing its output.
+///
+/// # Arguments
+/// * `command` - Subcommand from `config::parse_command`.
+/// * `config` - Options parsed alongside it.
+///
+/// # Returns
+/// `Err` with a printable message if loading, validation or writing an output file fails.
+pub fn execute(command: &Command, config: Config) -> Result<(), String> {
+    match command {
+        Command::Generate(dir) => generate(dir, &config),
+        Command::Repl => repl::run(&mut repl::Repl::new(config)).map_err(|e| format!("Shell failed: {}", e)),
+        Command::Flow(sources, sinks) => max_flow(sources, sinks, &config),
+        Command::Diff(old, new) => diff_versions(old, new, &config),
+        _ => {
+            let data = adapter::load_configured(&config)?;
+            match command {
+                Command::Explain(tx) => explain_tx(tx, data, config),
+                Command::Arrival(sources) => arrival(sources, &data),
+                Command::Path(from, to) => path(from, to, &data),
+                Command::Betweenness => betweenness(&data, &config),
+                Command::Communities => communities(&data),
+                Command::Distances => distances(&data, &config),
+                Command::Report(path) => report(path, data, &config),
+                Command::Stream(dirs) => replay_stream(dirs, data, config),
+                Command::Sketch => sketch_report(&data, &config),
+                Command::Cycles => cycle_report(&data, &config),
+                Command::Classify => classify(&data, &config),
+                Command::Serve => serve(data, config),
+                Command::Run | Command::Generate(_) | Command::Repl | Command::Flow(..) | Command::Diff(..) => run(data, config),
+            }
+        }
+    }
+}
+
+/// Sorted illicit and licit txIds, the two sides of the betweenness and distance comparisons.
+fn illicit_and_licit(labels: &HashMap<String, String>) -> (Vec<String>, Vec<String>) {
+    let members = |name: &str| name.parse::<LabelGroup>().map(|g| g.members(labels)).unwrap_or_default();
+    (members("illicit"), members("licit"))
+}
+
+/// RNG seeded from `--seed`, or from entropy without one.
+fn seeded_rng(config: &Config) -> StdRng {
+    StdRng::seed_from_u64(config.seed.unwrap_or_else(rand::random))
+}
+
+/// Reads `--annotations` if given.
+fn read_annotations(config: &Config) -> Result<HashMap<String, Annotation>, String> {
+    match &config.annotations {
+        Some(path) => annotations::read_annotations(path),
+        None => Ok(HashMap::new()),
+    }
+}
+
+fn generate(dir: &str, config: &Config) -> Result<(), String> {
+    let graph = synthetic::generate(&config.synthetic);
+    synthetic::write_elliptic(dir, &graph).map_err(|e| format!("Could not write synthetic dataset to {}: {}", dir, e))?;
+    let edge_count: usize = graph.edges.values().map(|t| t.len()).sum();
+    let routed: usize = graph.mixers.values().sum();
+    println!(
+        "Wrote {} txs, {} edges, {} planted mixers routing {} illicit txs to {}",
+        graph.timestamps.len(), edge_count, graph.mixers.len(), routed, dir
+    );
+    Ok(())
+}
+
+fn max_flow(sources: &str, sinks: &str, config: &Config) -> Result<(), String> {
+    let data = adapter::load_weighted_configured(config)?;
+    let mut rng = seeded_rng(config);
+    // A group name (or name=label) is sampled down to --sample-size; anything else is a txId list.
+    let mut pick = |arg: &str| match config.groups.iter().find(|g| g.name == arg).cloned().or_else(|| arg.parse::<LabelGroup>().ok()) {
+        Some(group) => analysis::sampler(group.members(&data.labels), config.sample_size, &mut rng),
+        None => arg.split(',').map(|s| s.to_string()).collect(),
+    };
+    let (sources, sinks) = (pick(sources), pick(sinks));
+    println!("Max flow from {} sources to {} sinks", sources.len(), sinks.len());
+    if !data.time_respecting {
+        println!("Note: this account graph merges transfers across time, so the flow below ignores time order");
+    }
+    let result = flow::max_flow(&data.graph, &sources, &sinks);
+    let mut busiest: Vec<(&String, &f64)> = result.throughput.iter().collect();
+    busiest.sort_by(|a, b| b.1.total_cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    let candidates: Vec<String> = busiest.iter().take(20).map(|(n, _)| (*n).clone()).collect();
+    let balance = flow::conservation(&data.graph, &candidates, config.balance_tolerance);
+    flow::print_flow(&result, &balance, 20);
+    Ok(())
+}
+
+fn diff_versions(old: &diff::Version, new: &diff::Version, config: &Config) -> Result<(), String> {
+    let mut base = None;
+    let old_data = old.load(config, &mut base)?;
+    let new_data = new.load(config, &mut base)?;
+    let changes = diff::diff_graphs(&old_data, &new_data, config.sketch_k);
+    let shift = if config.diff_ranking {
+        let mut rankings = Vec::new();
+        for (version, data) in [(old, old_data), (new, new_data)] {
+            println!("\nRanking {}", version);
+            let analysis = Analysis::new(data.edges, data.timestamps, data.labels)
+                .with_config(config.clone())
+                .with_annotations(read_annotations(config)?);
+            rankings.push(analysis.run()?.stats);
+        }
+        Some(diff::rank_shift(&rankings[0], &rankings[1], config.top_k))
+    } else {
+        None
+    };
+    println!("\nDiff of {} -> {}", old, new);
+    diff::print_diff(&changes, shift.as_ref(), config.top_k);
+    if let Some(path) = &config.export {
+        diff::write_diff(path, &changes, shift.as_ref(), &format!("{} -> {}", old, new))
+            .map_err(|e| format!("Could not write diff to {}: {}", path, e))?;
+        println!("Diff written to {}", path);
+    }
+    Ok(())
+}
+
+fn explain_tx(tx: &String, data: Dataset, config: Config) -> Result<(), String> {
+    // Same seed, same starts as run 0 of `run --seed`.
+    let seed = config.seed.unwrap_or_else(rand::random);
+    println!("Base seed: {}", seed);
+    let examples = config.explain_examples;
+    let explanation = Analysis::new(data.edges, data.timestamps, data.labels).with_config(config).explain(tx, seed);
+    explain::print_explanation(&explanation, examples);
+    Ok(())
+}
+
+fn arrival(sources: &[String], data: &Dataset) -> Result<(), String> {
+    let arrivals = temporal::earliest_arrival(&data.edges, &data.timestamps, sources);
+    let mut by_timestep: BTreeMap<usize, (usize, usize)> = BTreeMap::new();
+    let mut by_source: BTreeMap<&String, usize> = BTreeMap::new();
+    for arrival in arrivals.values() {
+        let entry = by_timestep.entry(arrival.timestep).or_insert((0, 0));
+        entry.0 += 1;
+        entry.1 += arrival.hops;
+        *by_source.entry(&arrival.source).or_insert(0) += 1;
+    }
+    println!("\n{} nodes reachable from {} sources", arrivals.len(), sources.len());
+    println!("{:<10} {:>10} {:>10}", "Timestep", "Arrivals", "Avg hops");
+    for (ts, (count, hops)) in by_timestep {
+        println!("{:<10} {:>10} {:>10.2}", ts, count, hops as f64 / count as f64);
+    }
+    println!("\n{:<15} {:>10}", "Source", "First to");
+    for (source, count) in by_source {
+        println!("{:<15} {:>10}", source, count);
+    }
+    Ok(())
+}
+
+fn path(from: &String, to: &String, data: &Dataset) -> Result<(), String> {
+    match temporal::shortest_temporal_path(&data.edges, &data.timestamps, from, to) {
+        Some(path) => {
+            let steps: Vec<String> = path.iter().map(|n| data.timestamps.get(n).copied().unwrap_or(0).to_string()).collect();
+            println!("\n{} hops: {}", path.len() - 1, path.join(" → "));
+            println!("t: {}", steps.join(" → "));
+        }
+        None => println!("\nNo time-respecting path from {} to {}", from, to),
+    }
+    Ok(())
+}
+
+fn betweenness(data: &Dataset, config: &Config) -> Result<(), String> {
+    let mode = if config.pivots == PivotMode::Off { PivotMode::Sampled(100) } else { config.pivots };
+    let (illicit, licit) = illicit_and_licit(&data.labels);
+    let ranking = centrality::label_conditioned_betweenness(&data.edges, &data.timestamps, &illicit, &licit, mode, &mut seeded_rng(config));
+    centrality::print_ranking(&ranking, 20);
+    Ok(())
+}
+
+fn communities(data: &Dataset) -> Result<(), String> {
+    let (communities, modularity) = community::louvain(&data.edges);
+    let summaries = community::summarize_communities(&communities, &data.labels, &data.timestamps);
+    community::print_communities(&summaries, modularity, 50);
+    Ok(())
+}
+
+fn distances(data: &Dataset, config: &Config) -> Result<(), String> {
+    let (illicit, licit) = illicit_and_licit(&data.labels);
+    let mut rng = seeded_rng(config);
+    let illicit = temporal::distance_distribution(&data.edges, &data.timestamps, &analysis::sampler(illicit, config.sample_size, &mut rng));
+    let licit = temporal::distance_distribution(&data.edges, &data.timestamps, &analysis::sampler(licit, config.sample_size, &mut rng));
+    temporal::print_distance_comparison(&illicit, &licit);
+    Ok(())
+}
+
+fn report(path: &str, data: Dataset, config: &Config) -> Result<(), String> {
+    let (data, window) = match config.window {
+        Some((from, to)) => (structure::restrict_to_window(&data, from, to), format!("timesteps {}-{}", from, to)),
+        None => (data, "all timesteps".to_string()),
+    };
+    let report = structure::build_report(&data);
+    let source = format!("{}, {}", config.dataset.as_deref().unwrap_or(&config.data_dir), window);
+    structure::write_report(path, &report, &source).map_err(|e| format!("Could not write report to {}: {}", path, e))?;
+    println!(
+        "\n{} nodes, {} edges, reciprocity {:.4}, max core {}, label assortativity {:.4}",
+        report.nodes, report.edges, report.reciprocity,
+        report.core_numbers.values().max().unwrap_or(&0), report.assortativity
+    );
+    println!("Report written to {}", path);
+    Ok(())
+}
+
+fn replay_stream(dirs: &[String], data: Dataset, config: Config) -> Result<(), String> {
+    let (initial, batches): (Dataset, Vec<(String, stream::Batch)>) = if dirs.is_empty() {
+        let (initial, batches) = stream::replay(&data, config.stream_from);
+        let named = batches
+            .into_iter()
+            .map(|b| (format!("timestep {}", b.timestamps.values().max().copied().unwrap_or(0)), b))
+            .collect();
+        (initial, named)
+    } else {
+        let batches = dirs.iter().map(|d| Ok((d.clone(), stream::Batch::read(d)?))).collect::<Result<_, String>>()?;
+        (data, batches)
+    };
+    let start = std::time::Instant::now();
+    let mut state = stream::StreamState::new(initial, config)?;
+    let (nodes, edges) = state.size();
+    println!(
+        "Initial graph: {} txs, {} edges, {} starts per group, built in {:.2?}",
+        nodes, edges,
+        state.starts().iter().map(|s| s.len().to_string()).collect::<Vec<_>>().join("/"),
+        start.elapsed()
+    );
+    for (name, batch) in &batches {
+        let start = std::time::Instant::now();
+        let summary = state.apply(batch);
+        stream::print_batch(&state, name, &summary, 10);
+        println!("Applied in {:.2?}", start.elapsed());
+    }
+    Ok(())
+}
+
+fn sketch_report(data: &Dataset, config: &Config) -> Result<(), String> {
+    let start = std::time::Instant::now();
+    let sketches = sketch::ReachSketches::build(&data.edges, &data.timestamps, config.sketch_k);
+    println!(
+        "Sketched {} txs ({} time-respecting components, k = {}) in {:.2?}",
+        data.timestamps.len(), sketches.components(), sketches.k, start.elapsed()
+    );
+    let (illicit, licit) = illicit_and_licit(&data.labels);
+    let mut rng = seeded_rng(config);
+    let illicit = analysis::sampler(illicit, config.sample_size, &mut rng);
+    let licit = analysis::sampler(licit, config.sample_size, &mut rng);
+    let exact = [&illicit, &licit].map(|starts| sketch::exact_forward(&data.edges, &data.timestamps, starts));
+    let mut nodes: Vec<String> = data.timestamps.keys().cloned().collect();
+    nodes.sort();
+    sketch::print_sketch_report(&sketches, [("illicit", &illicit), ("licit", &licit)], [&exact[0], &exact[1]], &nodes, 10);
+    Ok(())
+}
+
+fn cycle_report(data: &Dataset, config: &Config) -> Result<(), String> {
+    let start = std::time::Instant::now();
+    let found = cycles::simple_cycles(&data.edges, config.temporal_cycles.then_some(&data.timestamps), config.cycle_length);
+    println!("Enumerated cycles of up to {} members in {:.2?}", config.cycle_length, start.elapsed());
+    let summary = cycles::summarize_cycles(found, &data.timestamps, &data.labels);
+    cycles::print_cycle_summary(&summary, config.temporal_cycles, config.top_k);
+    if let Some(path) = &config.export {
+        cycles::write_cycles(path, &summary).map_err(|e| format!("Could not write cycles to {}: {}", path, e))?;
+        println!("Cycles written to {}", path);
+    }
+    Ok(())
+}
+
+fn classify(data: &Dataset, config: &Config) -> Result<(), String> {
+    let visible = features::visible_labels(data, config.train_until);
+    let graph = features::graph_features(data, &visible, config);
+    // Spec datasets have no Elliptic feature file unless one is given explicitly.
+    let raw_path = config.raw_features.clone().or_else(|| {
+        config.dataset.is_none().then(|| format!("{}/elliptic_txs_features.csv", config.data_dir))
+    });
+    let mut sets = Vec::new();
+    match raw_path.map(|path| features::read_raw_features(&path)).transpose()? {
+        Some(raw) => {
+            let joined = raw.join(&graph);
+            sets.push(("raw", raw));
+            sets.push(("graph", graph));
+            sets.push(("raw+graph", joined));
+        }
+        None => sets.push(("graph", graph)),
+    }
+    if let Some(path) = &config.export {
+        let (_, table) = sets.last().unwrap();
+        features::write_features(path, table, data).map_err(|e| format!("Could not write features to {}: {}", path, e))?;
+        println!("Features written to {}", path);
+    }
+    println!("\n{:?}, trained on timesteps <= {}, tested on later ones", config.model, config.train_until);
+    let mut results = Vec::new();
+    for (name, table) in &sets {
+        results.push((*name, features::temporal_split_eval(table, data, config.train_until, config.model)?));
+    }
+    features::print_evaluations(&results);
+    Ok(())
+}
+
+fn serve(data: Dataset, config: Config) -> Result<(), String> {
+    let annotations = read_annotations(&config)?;
+    let address = format!("127.0.0.1:{}", config.port);
+    let listener = TcpListener::bind(&address).map_err(|e| format!("Could not bind query server to {}: {}", address, e))?;
+    println!("Serving on http://{} (Ctrl-C to stop)", address);
+    let analysis = Analysis::new(data.edges, data.timestamps, data.labels)
+        .with_config(config)
+        .with_annotations(annotations);
+    server::serve(listener, &mut ServerState::new(analysis), None).map_err(|e| format!("Query server failed: {}", e))
+}
+
+/// The default `run` command: cycle summary, the mixer experiment and its follow-up tables.
+fn run(data: Dataset, config: Config) -> Result<(), String> {
+    // Longer cycles get expensive quickly; 4 members is fine on the full graph.
+    let found = cycles::simple_cycles(&data.edges, config.temporal_cycles.then_some(&data.timestamps), config.cycle_length);
+    cycles::print_cycle_summary(&cycles::summarize_cycles(found, &data.timestamps, &data.labels), config.temporal_cycles, 5);
+
+    let annotations = read_annotations(&config)?;
+    if !annotations.is_empty() {
+        println!("Loaded {} annotated txIds ({:?})", annotations.len(), config.annotation_action);
+    }
+
+    let analysis = Analysis::new(data.edges, data.timestamps, data.labels)
+        .with_config(config)
+        .with_annotations(annotations);
+    let result = analysis.run()?;
+    let final_stats = &result.stats;
+    let (edges, timestamps, config, annotations) = (analysis.edges(), analysis.timestamps(), analysis.config(), analysis.annotations());
+
+    if let Some((communities, modularity)) = &result.communities {
+        let summaries = community::summarize_communities(communities, analysis.labels(), timestamps);
+        community::print_communities(&summaries, *modularity, 20);
+    }
+
+    println!("\nTop 20 Mixer Candidates by Mean Score ({}, {}, {}):", config.scoring.name(), config.contrast_description(), config.estimator_description());
+    let reuse_header: String = config.groups.iter().map(|g| format!(" {:>10}", g.name)).collect();
+    println!("{:<15} {:>10} {:>10} {:>15} {:>20}{}  {:<10}",
+        "Node", "Mean", "StdDev", "95% CI Low", "95% CI High", reuse_header, "Entity");
+
+    for stat in final_stats.iter().take(20) {
+        let reuse: String = stat.reuse.iter().map(|r| format!(" {:>10}", r)).collect();
+        println!("{:<15} {:>10.2} {:>10.2} {:>15.2} {:>20.2}{}  {}",
+            stat.node, stat.mean, stat.stddev, stat.ci_low, stat.ci_high, reuse,
+            stat.entity.as_deref().unwrap_or(""));
+    }
+
+    if !annotations.is_empty() {
+        // With a `generate`d dataset and its planted_mixers.csv this is the recovery check.
+        let ranked: Vec<(usize, &MixerStats)> = final_stats
+            .iter()
+            .enumerate()
+            .filter(|(_, stat)| annotations.contains_key(&stat.node))
+            .collect();
+        println!("\nRanks of annotated txIds ({} of {} scored, {} candidates):", ranked.len(), annotations.len(), final_stats.len());
+        for (rank, stat) in ranked.iter().take(20) {
+            println!("#{:<8} {:<15} {}", rank + 1, stat.node, stat.entity.as_deref().unwrap_or(""));
+        }
+    }
+
+    if let Some(path) = &config.export {
+        analysis::export_stats(path, final_stats, annotations, config).map_err(|e| format!("Could not write export file {}: {}", path, e))?;
+        println!("Wrote {} candidates to {}", final_stats.len(), path);
+    }
+
+    if result.communities.is_some() {
+        println!("\nCommunities of the Top 20 Mixer Candidates:");
+        println!("{:<15} {:>10} {:>10} {:>10}", "Node", "Mean", "Community", "Bridged");
+        for stat in final_stats.iter().take(20) {
+            let community = stat.community.map_or("-".to_string(), |c| c.to_string());
+            println!("{:<15} {:>10.2} {:>10} {:>10}", stat.node, stat.mean, community, stat.communities_bridged.unwrap_or(0));
+        }
+    }
+
+    if config.pivots != PivotMode::Off {
+        let (illicit, licit) = illicit_and_licit(analysis.labels());
+        let ranking = centrality::label_conditioned_betweenness(edges, timestamps, &illicit, &licit, config.pivots, &mut seeded_rng(config));
+        centrality::print_ranking(&ranking, 20);
+
+        let by_node: HashMap<&String, &centrality::CentralityRank> = ranking.iter().map(|r| (&r.node, r)).collect();
+        println!("\nBetweenness of the Top 20 Mixer Candidates:");
+        println!("{:<15} {:>10} {:>14} {:>14}", "Node", "Mean", "Illicit BC", "Licit BC");
+        for stat in final_stats.iter().take(20) {
+            let (i, l) = by_node.get(&stat.node).map_or((0.0, 0.0), |r| (r.illicit, r.licit));
+            println!("{:<15} {:>10.2} {:>14.4} {:>14.4}", stat.node, stat.mean, i, l);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,245 @@
+use std::collections::{HashMap, HashSet};
+
+/// Size, label mix and time span of one community.
+pub struct CommunitySummary {
+    pub id: usize,
+    pub size: usize,
+    pub illicit: usize,
+    pub licit: usize,
+    pub unknown: usize,
+    pub first_timestep: usize,
+    pub last_timestep: usize,
+}
+
+/// Weighted undirected adjacency on dense node indices. A self-loop stores
+/// twice its internal weight so that a node's degree is the sum of its row.
+type Adjacency = Vec<HashMap<usize, f64>>;
+
+/// Louvain community detection on the undirected projection of `graph`.
+///
+/// Edge directions are dropped and an edge present in both directions gets weight 2.
+/// Nodes are indexed in sorted order so the result is deterministic.
+///
+/// # Returns
+/// Node → community id (ids are dense, starting at 0), and the final modularity.
+pub fn louvain(graph: &HashMap<String, HashSet<String>>) -> (HashMap<String, usize>, f64) {
+    let mut names: Vec<&String> = graph.keys().chain(graph.values().flatten()).collect::<HashSet<_>>().into_iter().collect();
+    names.sort();
+    let index: HashMap<&String, usize> = names.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+
+    let mut adj: Adjacency = vec![HashMap::new(); names.len()];
+    for (src, targets) in graph {
+        for tgt in targets {
+            let (a, b) = (index[src], index[tgt]);
+            if a == b {
+                *adj[a].entry(a).or_insert(0.0) += 2.0;
+            } else {
+                *adj[a].entry(b).or_insert(0.0) += 1.0;
+                *adj[b].entry(a).or_insert(0.0) += 1.0;
+            }
+        }
+    }
+
+    // membership[i] is the community of original node i at the current level.
+    let mut membership: Vec<usize> = (0..names.len()).collect();
+    loop {
+        let (assignment, moved) = local_moving(&adj);
+        let (assignment, count) = renumber(&assignment);
+        for community in membership.iter_mut() {
+            *community = assignment[*community];
+        }
+        if !moved || count == adj.len() {
+            break;
+        }
+        adj = aggregate(&adj, &assignment, count);
+    }
+
+    let result: HashMap<String, usize> = names.iter().map(|n| ((*n).clone(), membership[index[n]])).collect();
+    let q = modularity(graph, &result);
+    (result, q)
+}
+
+/// One Louvain phase: move single nodes to the neighboring community with the
+/// best modularity gain until nothing moves.
+///
+/// # Returns
+/// Community per node and whether any node changed community.
+fn local_moving(adj: &Adjacency) -> (Vec<usize>, bool) {
+    let degree: Vec<f64> = adj.iter().map(|row| row.values().sum()).collect();
+    let m2: f64 = degree.iter().sum();
+    let mut community: Vec<usize> = (0..adj.len()).collect();
+    let mut total: Vec<f64> = degree.clone();
+    let mut moved_any = false;
+
+    if m2 == 0.0 {
+        return (community, false);
+    }
+
+    loop {
+        let mut moved = false;
+        for node in 0..adj.len() {
+            let current = community[node];
+            total[current] -= degree[node];
+
+            let mut links: HashMap<usize, f64> = HashMap::new();
+            for (&neighbor, &weight) in &adj[node] {
+                if neighbor != node {
+                    *links.entry(community[neighbor]).or_insert(0.0) += weight;
+                }
+            }
+
+            let gain = |c: usize, k_in: f64| k_in - total[c] * degree[node] / m2;
+            let mut best = current;
+            let mut best_gain = gain(current, *links.get(&current).unwrap_or(&0.0));
+            let mut candidates: Vec<(usize, f64)> = links.into_iter().collect();
+            candidates.sort_by_key(|(c, _)| *c);
+            for (c, k_in) in candidates {
+                let g = gain(c, k_in);
+                if g > best_gain + 1e-12 {
+                    best = c;
+                    best_gain = g;
+                }
+            }
+
+            total[best] += degree[node];
+            if best != current {
+                community[node] = best;
+                moved = true;
+                moved_any = true;
+            }
+        }
+        if !moved {
+            break;
+        }
+    }
+
+    (community, moved_any)
+}
+
+/// Maps community labels to 0..count.
+fn renumber(assignment: &[usize]) -> (Vec<usize>, usize) {
+    let mut ids: HashMap<usize, usize> = HashMap::new();
+    let renumbered = assignment
+        .iter()
+        .map(|c| {
+            let next = ids.len();
+            *ids.entry(*c).or_insert(next)
+        })
+        .collect();
+    (renumbered, ids.len())
+}
+
+/// Collapses each community into a single node, summing edge weights.
+fn aggregate(adj: &Adjacency, assignment: &[usize], count: usize) -> Adjacency {
+    let mut next: Adjacency = vec![HashMap::new(); count];
+    for (node, row) in adj.iter().enumerate() {
+        for (&neighbor, &weight) in row {
+            *next[assignment[node]].entry(assignment[neighbor]).or_insert(0.0) += weight;
+        }
+    }
+    next
+}
+
+/// Newman modularity of a partition of the undirected projection.
+pub fn modularity(graph: &HashMap<String, HashSet<String>>, communities: &HashMap<String, usize>) -> f64 {
+    let mut inside: HashMap<usize, f64> = HashMap::new();
+    let mut total: HashMap<usize, f64> = HashMap::new();
+    let mut m = 0.0;
+    for (src, targets) in graph {
+        for tgt in targets {
+            let (a, b) = (communities[src], communities[tgt]);
+            m += 1.0;
+            *total.entry(a).or_insert(0.0) += 1.0;
+            *total.entry(b).or_insert(0.0) += 1.0;
+            if a == b {
+                *inside.entry(a).or_insert(0.0) += 2.0;
+            }
+        }
+    }
+    if m == 0.0 {
+        return 0.0;
+    }
+    let m2 = 2.0 * m;
+    total
+        .iter()
+        .map(|(c, tot)| inside.get(c).unwrap_or(&0.0) / m2 - (tot / m2).powi(2))
+        .sum()
+}
+
+/// Per-community size, label composition and timestep span.
+///
+/// # Returns
+/// Summaries sorted by size, largest first.
+pub fn summarize_communities(
+    communities: &HashMap<String, usize>,
+    labels: &HashMap<String, String>,
+    timestamps: &HashMap<String, usize>,
+) -> Vec<CommunitySummary> {
+    let mut summaries: HashMap<usize, CommunitySummary> = HashMap::new();
+    for (node, &id) in communities {
+        let summary = summaries.entry(id).or_insert(CommunitySummary {
+            id,
+            size: 0,
+            illicit: 0,
+            licit: 0,
+            unknown: 0,
+            first_timestep: usize::MAX,
+            last_timestep: 0,
+        });
+        summary.size += 1;
+        match labels.get(node).map(|l| l.as_str()) {
+            Some("1") => summary.licit += 1,
+            Some("2") => summary.illicit += 1,
+            _ => summary.unknown += 1,
+        }
+        if let Some(&ts) = timestamps.get(node) {
+            summary.first_timestep = summary.first_timestep.min(ts);
+            summary.last_timestep = summary.last_timestep.max(ts);
+        }
+    }
+
+    let mut result: Vec<CommunitySummary> = summaries.into_values().collect();
+    result.sort_by(|a, b| b.size.cmp(&a.size).then(a.id.cmp(&b.id)));
+    result
+}
+
+/// Prints the largest communities with their label mix and time span.
+pub fn print_communities(summaries: &[CommunitySummary], modularity: f64, top: usize) {
+    println!("\n{} communities, modularity {:.4}", summaries.len(), modularity);
+    println!(
+        "{:<10} {:>8} {:>8} {:>8} {:>8} {:>10} {:>12}",
+        "Community", "Size", "Illicit", "Licit", "Unknown", "Illicit %", "Timesteps"
+    );
+    for s in summaries.iter().take(top) {
+        let span = if s.first_timestep == usize::MAX {
+            "-".to_string()
+        } else {
+            format!("{}-{}", s.first_timestep, s.last_timestep)
+        };
+        println!(
+            "{:<10} {:>8} {:>8} {:>8} {:>8} {:>10.1} {:>12}",
+            s.id, s.size, s.illicit, s.licit, s.unknown,
+            100.0 * s.illicit as f64 / s.size as f64, span
+        );
+    }
+}
+
+/// Adds the communities touched by each path to every intermediary on it.
+///
+/// # Updates
+/// * `bridged`: node → communities its reused paths pass through.
+pub fn tally_bridging(
+    paths: &[Vec<String>],
+    communities: &HashMap<String, usize>,
+    bridged: &mut HashMap<String, HashSet<usize>>,
+) {
+    for path in paths {
+        if path.len() < 3 {
+            continue;
+        }
+        let touched: HashSet<usize> = path.iter().filter_map(|n| communities.get(n).copied()).collect();
+        for node in &path[1..path.len() - 1] {
+            bridged.entry(node.clone()).or_default().extend(&touched);
+        }
+    }
+}
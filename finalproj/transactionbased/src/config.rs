@@ -0,0 +1,417 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::annotations::AnnotationAction;
+use crate::budget::SearchBudget;
+use crate::centrality::PivotMode;
+use crate::diff::Version;
+use crate::features::Model;
+use crate::scoring::{Contrast, LabelGroup, ScoringMethod};
+use crate::synthetic::SyntheticConfig;
+use crate::targets::TargetStrategy;
+
+/// What the binary was asked to do.
+pub enum Command {
+    /// The full sampling + scoring experiment.
+    Run,
+    /// Report why one txId scores the way it does.
+    Explain(String),
+    /// Earliest-arrival timesteps from a comma-separated set of source txIds.
+    Arrival(Vec<String>),
+    /// Minimum-hop time-respecting path between two txIds.
+    Path(String, String),
+    /// Temporal distance distribution from sampled illicit vs licit sources.
+    Distances,
+    /// Label-conditioned time-respecting betweenness ranking.
+    Betweenness,
+    /// Louvain communities with their label composition.
+    Communities,
+    /// Write a synthetic Elliptic-format dataset with planted mixers to a folder.
+    Generate(String),
+    /// Keep the dataset loaded and answer JSON queries over HTTP on localhost.
+    Serve,
+    /// Interactive shell that keeps the graph and the last results in memory.
+    Repl,
+    /// Max flow / min cut between two node sets (group names or comma-separated txIds).
+    Flow(String, String),
+    /// Degree distributions, k-cores, reciprocity, timesteps and label mixing, written to a file.
+    Report(String),
+    /// Append timestep batches (replayed from `--stream-from`, or read from folders) and score arrivals.
+    Stream(Vec<String>),
+    /// Bottom-k sketches of every node's time-respecting forward and backward reach.
+    Sketch,
+    /// Simple cycles with timestep spans and label mixes, optionally time-respecting.
+    Cycles,
+    /// Compare two dataset versions or time windows: nodes, edges, labels, reach, SCCs and ranking.
+    Diff(Version, Version),
+    /// Train and evaluate an illicit-transaction classifier on graph + raw features (temporal split).
+    Classify,
+}
+
+/// Splits the command line into a command and its `--key value` options.
+///
+/// # Arguments
+/// * `args` - Command line arguments, without the program name.
+///
+/// # Returns
+/// The command (defaulting to `Run`) and the parsed config.
+pub fn parse_command(args: &[String]) -> Result<(Command, Config), String> {
+    match args.first().map(|a| a.as_str()) {
+        Some("explain") => {
+            let tx = args.get(1).ok_or("explain needs a txId")?;
+            Ok((Command::Explain(tx.clone()), Config::from_args(&args[2..])?))
+        }
+        Some("arrival") => {
+            let sources = args.get(1).ok_or("arrival needs one or more comma-separated txIds")?;
+            let sources = sources.split(',').map(|s| s.to_string()).collect();
+            Ok((Command::Arrival(sources), Config::from_args(&args[2..])?))
+        }
+        Some("path") => {
+            let (Some(from), Some(to)) = (args.get(1), args.get(2)) else {
+                return Err("path needs two txIds".to_string());
+            };
+            Ok((Command::Path(from.clone(), to.clone()), Config::from_args(&args[3..])?))
+        }
+        Some("betweenness") => Ok((Command::Betweenness, Config::from_args(&args[1..])?)),
+        Some("communities") => Ok((Command::Communities, Config::from_args(&args[1..])?)),
+        Some("distances") => Ok((Command::Distances, Config::from_args(&args[1..])?)),
+        Some("generate") => {
+            let dir = args.get(1).ok_or("generate needs an output folder")?;
+            Ok((Command::Generate(dir.clone()), Config::from_args(&args[2..])?))
+        }
+        Some("flow") => {
+            let (Some(sources), Some(sinks)) = (args.get(1), args.get(2)) else {
+                return Err("flow needs a source set and a sink set (group names or comma-separated txIds)".to_string());
+            };
+            Ok((Command::Flow(sources.clone(), sinks.clone()), Config::from_args(&args[3..])?))
+        }
+        Some("report") => {
+            let path = args.get(1).ok_or("report needs an output file")?;
+            Ok((Command::Report(path.clone()), Config::from_args(&args[2..])?))
+        }
+        Some("stream") => {
+            // Batch folders come first, then the options.
+            let dirs: Vec<String> = args[1..].iter().take_while(|a| !a.starts_with("--")).cloned().collect();
+            Ok((Command::Stream(dirs.clone()), Config::from_args(&args[1 + dirs.len()..])?))
+        }
+        Some("sketch") => Ok((Command::Sketch, Config::from_args(&args[1..])?)),
+        Some("diff") => {
+            let (Some(old), Some(new)) = (args.get(1), args.get(2)) else {
+                return Err("diff needs two versions (data folders, .spec files or a:b windows)".to_string());
+            };
+            Ok((Command::Diff(old.parse()?, new.parse()?), Config::from_args(&args[3..])?))
+        }
+        Some("cycles") => Ok((Command::Cycles, Config::from_args(&args[1..])?)),
+        Some("classify") => Ok((Command::Classify, Config::from_args(&args[1..])?)),
+        Some("repl") => Ok((Command::Repl, Config::from_args(&args[1..])?)),
+        Some("serve") => Ok((Command::Serve, Config::from_args(&args[1..])?)),
+        Some("run") => Ok((Command::Run, Config::from_args(&args[1..])?)),
+        _ => Ok((Command::Run, Config::from_args(args)?)),
+    }
+}
+
+/// How per-group reuse counts are gathered.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Estimator {
+    /// Exhaustive depth-capped path search (`theory_tester`).
+    Dfs,
+    /// Time-respecting random walks (`walks::estimate_reuse`).
+    Walk,
+}
+
+impl FromStr for Estimator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dfs" => Ok(Estimator::Dfs),
+            "walk" => Ok(Estimator::Walk),
+            _ => Err(format!("Unknown estimator {} (expected dfs or walk)", s)),
+        }
+    }
+}
+
+/// Run-time knobs for the mixer experiment.
+///
+/// Every field has a default matching the old hardcoded values in `main`,
+/// and can be overridden from the command line as `--key value`.
+#[derive(Clone)]
+pub struct Config {
+    pub data_dir: String,
+    /// Dataset spec file (see `adapter::DatasetSpec`); replaces `data_dir` when set.
+    pub dataset: Option<String>,
+    pub num_runs: usize,
+    pub sample_size: usize,
+    pub max_depth: usize,
+    pub scoring: ScoringMethod,
+    pub annotations: Option<String>,
+    pub annotation_action: AnnotationAction,
+    pub annotation_weight: f64,
+    pub export: Option<String>,
+    pub explain_examples: usize,
+    pub targets: TargetStrategy,
+    pub budget: SearchBudget,
+    pub collect_depth: usize,
+    pub reservoir_size: usize,
+    pub estimator: Estimator,
+    pub walks_per_start: usize,
+    pub walk_length: usize,
+    pub pivots: PivotMode,
+    pub communities: bool,
+    /// Label groups sampled every run; each gets its own reuse tally.
+    pub groups: Vec<LabelGroup>,
+    /// Which two of `groups` the mixer score compares.
+    pub contrast: Contrast,
+    /// Settings for the `generate` command.
+    pub synthetic: SyntheticConfig,
+    /// Directory where finished runs are saved and resumed from.
+    pub checkpoint: Option<String>,
+    /// Base seed for start sampling; run `i` samples with `seed + i`.
+    pub seed: Option<u64>,
+    /// Inclusive timestep range `report` is restricted to.
+    pub window: Option<(usize, usize)>,
+    /// Largest relative in/out volume gap for `flow` to call a node balanced.
+    pub balance_tolerance: f64,
+    /// Port the `serve` command listens on (127.0.0.1 only).
+    pub port: u16,
+    /// Classifier trained by `classify`.
+    pub model: Model,
+    /// Last timestep of the `classify` training period; later timesteps are the test set.
+    pub train_until: usize,
+    /// Raw per-node feature file (`txId,timestep,f1,...`); defaults to the Elliptic features file in `data_dir`.
+    pub raw_features: Option<String>,
+    /// First timestep `stream` replays as a batch; earlier ones form the initial graph.
+    pub stream_from: usize,
+    /// Hashes kept per reachability sketch (`sketch`, `/sketch`); smaller sets are exact.
+    pub sketch_k: usize,
+    /// Length of the top-k lists `diff` compares and `cycles` prints.
+    pub top_k: usize,
+    /// Whether `diff` runs the mixer experiment on both versions to compare rankings.
+    pub diff_ranking: bool,
+    /// Most members a cycle may have in cycle analysis (`cycles` and the run summary).
+    pub cycle_length: usize,
+    /// Whether cycles must have non-decreasing timesteps going round (one edge back home allowed).
+    pub temporal_cycles: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            data_dir: "../..".to_string(),
+            dataset: None,
+            num_runs: 10,
+            sample_size: 100,
+            max_depth: 10,
+            scoring: ScoringMethod::Ratio,
+            annotations: None,
+            annotation_action: AnnotationAction::Tag,
+            annotation_weight: 0.1,
+            export: None,
+            explain_examples: 10,
+            targets: TargetStrategy::TopOutDegree(100),
+            budget: SearchBudget { max_paths: Some(100), ..SearchBudget::default() },
+            collect_depth: 20,
+            reservoir_size: 100,
+            estimator: Estimator::Dfs,
+            walks_per_start: 100,
+            walk_length: 10,
+            pivots: PivotMode::Off,
+            communities: false,
+            groups: vec!["illicit".parse().unwrap(), "licit".parse().unwrap()],
+            contrast: "illicit:licit".parse().unwrap(),
+            synthetic: SyntheticConfig::default(),
+            checkpoint: None,
+            seed: None,
+            window: None,
+            balance_tolerance: 0.1,
+            port: 8210,
+            model: Model::LogReg,
+            train_until: 34,
+            raw_features: None,
+            stream_from: 35,
+            sketch_k: 64,
+            top_k: 20,
+            diff_ranking: true,
+            cycle_length: 4,
+            temporal_cycles: false,
+        }
+    }
+}
+
+impl Config {
+    /// Builds a config from `--key value` pairs, starting from the defaults.
+    ///
+    /// # Arguments
+    /// * `args` - Command line arguments, without the program name.
+    ///
+    /// # Returns
+    /// The parsed config, or a message naming the bad flag or value.
+    pub fn from_args(args: &[String]) -> Result<Config, String> {
+        let mut config = Config::default();
+        let mut iter = args.iter();
+
+        while let Some(flag) = iter.next() {
+            let value = iter
+                .next()
+                .ok_or_else(|| format!("Missing value for {}", flag))?;
+
+            match flag.as_str() {
+                "--data-dir" => config.data_dir = value.clone(),
+                "--dataset" => config.dataset = Some(value.clone()),
+                "--runs" => config.num_runs = parse_usize(flag, value)?,
+                "--sample-size" => config.sample_size = parse_usize(flag, value)?,
+                "--max-depth" => config.max_depth = parse_usize(flag, value)?,
+                "--score" => config.scoring = value.parse()?,
+                "--annotations" => config.annotations = Some(value.clone()),
+                "--annotation-action" => config.annotation_action = value.parse()?,
+                "--annotation-weight" => match parse_f64(flag, value)? {
+                    w if (0.0..=1.0).contains(&w) => config.annotation_weight = w,
+                    w => return Err(format!("{} must be between 0 and 1, got {}", flag, w)),
+                },
+                "--export" => config.export = Some(value.clone()),
+                "--targets" => config.targets = value.parse()?,
+                "--max-expansions" => config.budget.max_expansions = Some(parse_usize(flag, value)?),
+                "--time-limit" => {
+                    let limit = Duration::try_from_secs_f64(parse_f64(flag, value)?)
+                        .map_err(|_| format!("{} expects a non-negative number of seconds, got {}", flag, value))?;
+                    config.budget.time_limit = Some(limit);
+                }
+                "--max-paths-per-pair" => config.budget.max_paths = Some(parse_usize(flag, value)?),
+                "--collect-depth" => config.collect_depth = parse_usize(flag, value)?,
+                "--reservoir" => config.reservoir_size = parse_usize(flag, value)?,
+                "--estimator" => config.estimator = value.parse()?,
+                "--walks" => config.walks_per_start = parse_usize(flag, value)?,
+                "--walk-length" => config.walk_length = parse_usize(flag, value)?,
+                "--pivots" => config.pivots = value.parse()?,
+                "--communities" => config.communities = parse_bool(flag, value)?,
+                "--groups" => {
+                    config.groups = value.split(',').map(|g| g.parse()).collect::<Result<_, _>>()?
+                }
+                "--contrast" => config.contrast = value.parse()?,
+                "--nodes" => config.synthetic.nodes = parse_usize(flag, value)?,
+                "--timesteps" => match parse_usize(flag, value)? {
+                    ts @ 1..=49 => config.synthetic.timesteps = ts,
+                    ts => return Err(format!("{} must be between 1 and 49, got {}", flag, ts)),
+                },
+                "--illicit-share" => config.synthetic.illicit_share = parse_f64(flag, value)?,
+                "--licit-share" => config.synthetic.licit_share = parse_f64(flag, value)?,
+                "--degree" => config.synthetic.degree = value.parse()?,
+                "--homophily" => config.synthetic.homophily = parse_f64(flag, value)?,
+                "--mixers" => config.synthetic.mixers = parse_usize(flag, value)?,
+                "--mixer-share" => config.synthetic.mixer_share = parse_f64(flag, value)?,
+                "--mixer-fanout" => config.synthetic.mixer_fanout = parse_usize(flag, value)?,
+                "--features" => config.synthetic.features = parse_usize(flag, value)?,
+                "--seed" => {
+                    let seed = value.parse().map_err(|_| format!("{} expects a number, got {}", flag, value))?;
+                    config.seed = Some(seed);
+                    config.synthetic.seed = seed;
+                }
+                "--checkpoint" => config.checkpoint = Some(value.clone()),
+                "--window" => {
+                    let (from, to) = value
+                        .split_once(':')
+                        .ok_or_else(|| format!("{} should look like 5:10, got {}", flag, value))?;
+                    let (from, to) = (parse_usize(flag, from)?, parse_usize(flag, to)?);
+                    if from > to {
+                        return Err(format!("{} starts after it ends: {}", flag, value));
+                    }
+                    config.window = Some((from, to));
+                }
+                "--balance-tolerance" => config.balance_tolerance = parse_f64(flag, value)?,
+                "--port" => {
+                    config.port = value.parse().map_err(|_| format!("{} expects a port number, got {}", flag, value))?
+                }
+                "--model" => config.model = value.parse()?,
+                "--train-until" => config.train_until = parse_usize(flag, value)?,
+                "--raw-features" => config.raw_features = Some(value.clone()),
+                "--stream-from" => config.stream_from = parse_usize(flag, value)?,
+                "--sketch-k" => config.sketch_k = parse_usize(flag, value)?,
+                "--top" => config.top_k = parse_usize(flag, value)?,
+                "--ranking" => config.diff_ranking = parse_bool(flag, value)?,
+                "--cycle-length" => config.cycle_length = parse_usize(flag, value)?,
+                "--temporal-cycles" => config.temporal_cycles = parse_bool(flag, value)?,
+                "--examples" => config.explain_examples = parse_usize(flag, value)?,
+                _ => return Err(format!("Unknown option {}", flag)),
+            }
+        }
+
+        config.contrast_indices()?;
+        Ok(config)
+    }
+
+    /// Budget for one interactive path query (`serve`, `repl`): the configured
+    /// one, with a 10 s time limit unless `--time-limit` was given.
+    pub fn query_budget(&self) -> SearchBudget {
+        let mut budget = self.budget;
+        budget.time_limit.get_or_insert(Duration::from_secs(10));
+        budget
+    }
+
+    /// Positions of the contrast's target and baseline groups in `groups`.
+    pub fn contrast_indices(&self) -> Result<(usize, usize), String> {
+        let find = |name: &str| {
+            self.groups
+                .iter()
+                .position(|g| g.name == name)
+                .ok_or_else(|| format!("Contrast group {} is not in --groups", name))
+        };
+        Ok((find(&self.contrast.target)?, find(&self.contrast.baseline)?))
+    }
+
+    /// The settings a run's results depend on, as `key=value` pairs for checkpoints.
+    pub fn settings(&self) -> Vec<(&'static str, String)> {
+        let groups: Vec<String> = self.groups.iter().map(|g| format!("{}={}", g.name, g.labels.join("+"))).collect();
+        // The same folder or spec given by another relative path is still the same data.
+        let source = self.dataset.as_ref().unwrap_or(&self.data_dir);
+        let source = std::fs::canonicalize(source).map_or(source.clone(), |p| p.display().to_string());
+        vec![
+            ("data", source),
+            ("sample_size", self.sample_size.to_string()),
+            ("max_depth", self.max_depth.to_string()),
+            ("score", self.scoring.name().to_string()),
+            ("estimator", self.estimator_description()),
+            ("collect_depth", self.collect_depth.to_string()),
+            ("reservoir", self.reservoir_size.to_string()),
+            ("budget", format!("{:?}", self.budget)),
+            ("groups", groups.join(",")),
+            ("contrast", self.contrast_description()),
+            ("annotations", self.annotations.clone().unwrap_or_default()),
+            ("annotation_action", format!("{:?}", self.annotation_action)),
+            ("annotation_weight", self.annotation_weight.to_string()),
+            ("communities", self.communities.to_string()),
+        ]
+    }
+
+    /// Short description of the contrast, e.g. `illicit vs licit`.
+    pub fn contrast_description(&self) -> String {
+        format!("{} vs {}", self.contrast.target, self.contrast.baseline)
+    }
+
+    /// Short description of how reuse was estimated, for output headers.
+    pub fn estimator_description(&self) -> String {
+        match self.estimator {
+            Estimator::Dfs => format!("dfs, targets: {}", self.targets),
+            Estimator::Walk => format!("walk, {} walks/start, length {}", self.walks_per_start, self.walk_length),
+        }
+    }
+}
+
+fn parse_usize(flag: &str, value: &str) -> Result<usize, String> {
+    value
+        .parse::<usize>()
+        .map_err(|_| format!("{} expects a number, got {}", flag, value))
+}
+
+fn parse_f64(flag: &str, value: &str) -> Result<f64, String> {
+    value
+        .parse::<f64>()
+        .map_err(|_| format!("{} expects a number, got {}", flag, value))
+}
+
+fn parse_bool(flag: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "true" | "yes" | "on" => Ok(true),
+        "false" | "no" | "off" => Ok(false),
+        _ => Err(format!("{} expects true or false, got {}", flag, value)),
+    }
+}
@@ -1,69 +1,308 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::Write;
 
-/// Performs a depth-first search to detect "loose" cycles of up to length `k + 1`.
-///
-/// A *loose* cycle here refers to a cycle that:
-/// - Starts and ends at the same node (`start`)
-/// - Has length at most `k + 1`
-/// - May contain repeated nodes (except immediate revisits are avoided by construction)
+use crate::budget::BudgetTracker;
+use crate::dfsstuff;
+use crate::explain::label_name;
+
+/// Extends `path` with every neighbour not already on it, recording a cycle whenever an edge closes back to `path[0]`.
 ///
 /// # Arguments
 /// * `graph` - Directed graph represented as an adjacency list.
-/// * `current` - Node currently being visited.
-/// * `start` - Original node where cycle search began.
-/// * `k` - Maximum cycle length (not including the repeated start node).
-/// * `path` - Current path being built.
+/// * `max_len` - Largest number of members.
+/// * `path` - Current path, starting at the node the cycles go through.
 /// * `cycles` - Collected list of valid cycles.
-fn dfs_loose_cycle(
+/// * `tracker` - Budget for the search; it unwinds once this runs out.
+fn dfs_cycles_through(
     graph: &HashMap<String, HashSet<String>>,
-    current: &String,
-    start: &String,
-    k: usize,
+    max_len: usize,
     path: &mut Vec<String>,
     cycles: &mut Vec<Vec<String>>,
+    tracker: &mut BudgetTracker,
 ) {
-    if path.len() > k + 1 {
+    if !tracker.expand() {
         return;
     }
-
-    path.push(current.clone());
-
-    if let Some(neighbors) = graph.get(current) {
-        for neighbor in neighbors {
-            // println!("Don't worry, I'm doing sth");
-            if neighbor == start && path.len() > 2 {
+    let current = path.last().unwrap().clone();
+    let Some(neighbors) = graph.get(&current) else { return };
+    for neighbor in neighbors {
+        if tracker.exhausted() {
+            break;
+        }
+        if *neighbor == path[0] {
+            if path.len() > 1 {
                 cycles.push(path.clone());
-            } else {
-                dfs_loose_cycle(graph, neighbor, start, k, path, cycles);
             }
+        } else if path.len() < max_len && !path.contains(neighbor) {
+            path.push(neighbor.clone());
+            dfs_cycles_through(graph, max_len, path, cycles, tracker);
+            path.pop();
         }
     }
-    // println!("Don't worry, I'm doing sth"); // for those of you that doubt if this is working
+}
+
+/// Simple cycles (no repeated txId) of 2 to `k + 1` members through `node`.
+///
+/// Time is ignored. The number of cycles grows exponentially with `k`; the
+/// search stops (keeping what it found) when `tracker` runs out.
+///
+/// # Returns
+/// Cycles in edge order, each starting from `node`, sorted.
+pub fn cycles_through(
+    graph: &HashMap<String, HashSet<String>>,
+    node: &str,
+    k: usize,
+    tracker: &mut BudgetTracker,
+) -> Vec<Vec<String>> {
+    let mut cycles = Vec::new();
+    dfs_cycles_through(graph, k + 1, &mut vec![node.to_string()], &mut cycles, tracker);
+    cycles.sort();
+    cycles
+}
+
+/// Strongly connected components of the directed graph (iterative Tarjan).
+///
+/// Time is ignored, as in `cycles_through` above.
+///
+/// # Returns
+/// Every component (singletons included) as a sorted list of node IDs, in reverse
+/// topological order: no component has an edge into a component listed after it.
+pub fn strongly_connected_components(graph: &HashMap<String, HashSet<String>>) -> Vec<Vec<String>> {
+    let mut names: Vec<&String> = graph.keys().chain(graph.values().flatten()).collect();
+    names.sort();
+    names.dedup();
+    let index: HashMap<&String, usize> = names.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+    let adjacency: Vec<Vec<usize>> = names
+        .iter()
+        .map(|n| graph.get(*n).map_or(Vec::new(), |t| t.iter().map(|t| index[t]).collect()))
+        .collect();
 
-    path.pop();
+    let n = names.len();
+    let (mut order, mut low, mut on_stack) = (vec![usize::MAX; n], vec![0; n], vec![false; n]);
+    let (mut stack, mut components, mut counter) = (Vec::new(), Vec::new(), 0);
+    for root in 0..n {
+        if order[root] != usize::MAX {
+            continue;
+        }
+        // Explicit call stack of (node, next neighbour to look at).
+        let mut calls = vec![(root, 0)];
+        order[root] = counter;
+        low[root] = counter;
+        counter += 1;
+        stack.push(root);
+        on_stack[root] = true;
+        while let Some(&(v, next)) = calls.last() {
+            if let Some(&w) = adjacency[v].get(next) {
+                calls.last_mut().unwrap().1 += 1;
+                if order[w] == usize::MAX {
+                    order[w] = counter;
+                    low[w] = counter;
+                    counter += 1;
+                    stack.push(w);
+                    on_stack[w] = true;
+                    calls.push((w, 0));
+                } else if on_stack[w] {
+                    low[v] = low[v].min(order[w]);
+                }
+                continue;
+            }
+            calls.pop();
+            if let Some(&(parent, _)) = calls.last() {
+                low[parent] = low[parent].min(low[v]);
+            }
+            if low[v] == order[v] {
+                let mut component = Vec::new();
+                while let Some(w) = stack.pop() {
+                    on_stack[w] = false;
+                    component.push(names[w].clone());
+                    if w == v {
+                        break;
+                    }
+                }
+                component.sort();
+                components.push(component);
+            }
+        }
+    }
+    components
 }
 
+/// Extends `path` with every neighbour larger than `start`, recording a cycle whenever an edge closes back to `start`.
+///
+/// Restricting members to nodes after `start` reports each simple cycle once, from its smallest txId.
+/// With `timestamps`, a path is dropped once it has gone back in time twice: a cycle can then be
+/// read from some member with non-decreasing timesteps all the way round, except for the edge home.
+fn dfs_simple_cycle(
+    graph: &HashMap<String, HashSet<String>>,
+    timestamps: Option<&HashMap<String, usize>>,
+    start: &String,
+    max_len: usize,
+    path: &mut Vec<String>,
+    descents: usize,
+    cycles: &mut Vec<Vec<String>>,
+) {
+    let current = path.last().unwrap().clone();
+    let Some(neighbors) = graph.get(&current) else { return };
+    let mut neighbors: Vec<&String> = neighbors.iter().filter(|n| *n >= start).collect();
+    neighbors.sort();
+    for neighbor in neighbors {
+        let descents = descents + timestamps.map_or(0, |ts| !dfsstuff::time_respecting(ts, &current, neighbor) as usize);
+        if descents > 1 {
+            continue;
+        }
+        if neighbor == start {
+            if path.len() > 1 {
+                cycles.push(path.clone());
+            }
+        } else if path.len() < max_len && !path.contains(neighbor) {
+            path.push(neighbor.clone());
+            dfs_simple_cycle(graph, timestamps, start, max_len, path, descents, cycles);
+            path.pop();
+        }
+    }
+}
 
-/// Initiates DFS from each node to detect cycles of length up to `k + 1`.
+/// Every simple cycle (no repeated txId) with 2 to `max_len` members, each reported once.
 ///
 /// # Arguments
 /// * `graph` - The input graph as a directed adjacency list.
-/// * `k` - Max cycle length (excluding start node repetition).
+/// * `timestamps` - When given, keep only cycles whose timesteps never decrease going round,
+///   starting from the right member (at most one edge goes back in time).
+/// * `max_len` - Largest number of members.
 ///
 /// # Returns
-/// A vector of all found cycles represented as vectors of node IDs.
-pub fn find_k_cycles(
-    graph: &HashMap<String, HashSet<String>>, 
-    k: usize
+/// Cycles in edge order, each starting from its smallest txId, sorted.
+pub fn simple_cycles(
+    graph: &HashMap<String, HashSet<String>>,
+    timestamps: Option<&HashMap<String, usize>>,
+    max_len: usize,
 ) -> Vec<Vec<String>> {
-    println!("Started finding cycles!");
+    let mut starts: Vec<&String> = graph.keys().collect();
+    starts.sort();
     let mut cycles = Vec::new();
+    for start in starts {
+        dfs_simple_cycle(graph, timestamps, start, max_len, &mut vec![start.clone()], 0, &mut cycles);
+    }
+    cycles.sort();
+    cycles
+}
+
+/// One cycle with its timing and member labels.
+pub struct CycleInfo {
+    /// Members in edge order.
+    pub nodes: Vec<String>,
+    /// Earliest and latest member timestep.
+    pub span: (usize, usize),
+    /// Label name of each member, in `nodes` order.
+    pub labels: Vec<String>,
+}
 
-    for start in graph.keys() {
-        // println!("Yoohoo!");
-        let mut path = Vec::new();
-        dfs_loose_cycle(graph, start, start, k, &mut path, &mut cycles);
+impl CycleInfo {
+    /// The member labels as a sorted multiset, e.g. `illicit+unknown+unknown`.
+    pub fn label_mix(&self) -> String {
+        let mut labels = self.labels.clone();
+        labels.sort();
+        labels.join("+")
     }
+}
 
-    cycles
+/// Cycles with per-cycle details and aggregate counts.
+pub struct CycleSummary {
+    pub cycles: Vec<CycleInfo>,
+    /// Number of members → number of cycles.
+    pub by_length: BTreeMap<usize, usize>,
+    /// (label mix, number of cycles), most common first.
+    pub by_label_mix: Vec<(String, usize)>,
+    /// (txId, number of cycles it is in), most involved first.
+    pub involvement: Vec<(String, usize)>,
+}
+
+/// Attaches timestep spans and labels to `cycles` and counts them by length, label mix and member.
+///
+/// # Arguments
+/// * `cycles` - Cycles from `simple_cycles`.
+/// * `timestamps`, `labels` - The loaded dataset; unlabelled members count as `unknown`.
+pub fn summarize_cycles(
+    cycles: Vec<Vec<String>>,
+    timestamps: &HashMap<String, usize>,
+    labels: &HashMap<String, String>,
+) -> CycleSummary {
+    let mut by_length = BTreeMap::new();
+    let mut by_label_mix: HashMap<String, usize> = HashMap::new();
+    let mut involvement: HashMap<String, usize> = HashMap::new();
+    let mut infos = Vec::with_capacity(cycles.len());
+    for nodes in cycles {
+        let times: Vec<usize> = nodes.iter().filter_map(|n| timestamps.get(n).copied()).collect();
+        let span = (times.iter().copied().min().unwrap_or(0), times.iter().copied().max().unwrap_or(0));
+        let member_labels = nodes
+            .iter()
+            .map(|n| label_name(labels.get(n).map_or("unknown", |l| l.as_str())).to_string())
+            .collect();
+        let info = CycleInfo { nodes, span, labels: member_labels };
+        *by_length.entry(info.nodes.len()).or_insert(0) += 1;
+        *by_label_mix.entry(info.label_mix()).or_insert(0) += 1;
+        for node in &info.nodes {
+            *involvement.entry(node.clone()).or_insert(0) += 1;
+        }
+        infos.push(info);
+    }
+    let by_count = |counts: HashMap<String, usize>| {
+        let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    };
+    CycleSummary { cycles: infos, by_length, by_label_mix: by_count(by_label_mix), involvement: by_count(involvement) }
+}
+
+/// Prints cycle counts by length and label mix, the most cycle-involved txIds and the first few cycles.
+pub fn print_cycle_summary(summary: &CycleSummary, temporal: bool, top: usize) {
+    let kind = if temporal { "time-respecting cycles" } else { "cycles (time ignored)" };
+    println!("\nFound {} {}", summary.cycles.len(), kind);
+    if summary.cycles.is_empty() {
+        return;
+    }
+    println!("{:<8} {:>10}", "Length", "Cycles");
+    for (length, count) in &summary.by_length {
+        println!("{:<8} {:>10}", length, count);
+    }
+    println!("\n{:<40} {:>10}", "Label mix", "Cycles");
+    for (mix, count) in summary.by_label_mix.iter().take(top) {
+        println!("{:<40} {:>10}", mix, count);
+    }
+    println!("\nMost cycle-involved txIds:");
+    println!("{:<15} {:>10}", "txId", "Cycles");
+    for (node, count) in summary.involvement.iter().take(top) {
+        println!("{:<15} {:>10}", node, count);
+    }
+    println!("\n{:<8} {:<10} Cycle", "Length", "Timesteps");
+    for cycle in summary.cycles.iter().take(top) {
+        let members: Vec<String> = cycle.nodes.iter().zip(&cycle.labels).map(|(n, l)| format!("{} ({})", n, l)).collect();
+        println!("{:<8} {:<10} {}", cycle.nodes.len(), format!("{}-{}", cycle.span.0, cycle.span.1), members.join(" -> "));
+    }
+}
+
+/// Writes every cycle and the aggregate counts as `# section` CSV blocks.
+pub fn write_cycles(path: &str, summary: &CycleSummary) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
+    writeln!(file, "# cycles\nlength,first_timestep,last_timestep,members,labels")?;
+    for cycle in &summary.cycles {
+        writeln!(
+            file, "{},{},{},{},{}",
+            cycle.nodes.len(), cycle.span.0, cycle.span.1, cycle.nodes.join(";"), cycle.labels.join(";")
+        )?;
+    }
+    writeln!(file, "\n# by_length\nlength,cycles")?;
+    for (length, count) in &summary.by_length {
+        writeln!(file, "{},{}", length, count)?;
+    }
+    writeln!(file, "\n# by_label_mix\nlabel_mix,cycles")?;
+    for (mix, count) in &summary.by_label_mix {
+        writeln!(file, "{},{}", mix, count)?;
+    }
+    writeln!(file, "\n# involvement\ntxId,cycles")?;
+    for (node, count) in &summary.involvement {
+        writeln!(file, "{},{}", node, count)?;
+    }
+    Ok(())
 }
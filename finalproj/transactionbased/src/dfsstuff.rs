@@ -1,12 +1,16 @@
-use plotters::prelude::*;
-use std::error::Error;
 use std::collections::{HashMap, HashSet};
-use std::vec;
-use std::io::{BufReader, BufRead};
-use std::fs::File;
-use rand::thread_rng;
-use rand::seq::SliceRandom;
-use rand::prelude::IndexedRandom;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::budget::{BudgetTracker, SearchBudget, SearchStatus};
+use crate::progress::Progress;
+
+/// (start, target) → (num paths, total depth).
+pub type PairStats = HashMap<(String, String), (usize, usize)>;
+
+/// (start, target) → how that pair's search ended.
+pub type PairStatuses = HashMap<(String, String), SearchStatus>;
 
 /// Performs timestamp-filtered DFS to collect all reachable nodes from start nodes.
 /// 
@@ -57,9 +61,12 @@ pub fn dfs_collect_reachable(
 
 /// DFS to collect full valid paths from `start → target` while respecting timestamp ordering.
 /// 
+/// Stops early once `tracker` runs out of expansions, time or paths.
+///
 /// # Returns
 /// Fills `all_paths` with paths satisfying the constraints.
 ///
+#[allow(clippy::too_many_arguments)]
 pub fn dfs_collect_paths(
     graph: &HashMap<String, HashSet<String>>,
     timestamps: &HashMap<String, usize>,
@@ -70,8 +77,9 @@ pub fn dfs_collect_paths(
     visited: &mut HashSet<String>,
     depth: usize,
     max_depth: usize,
+    tracker: &mut BudgetTracker,
 ) {
-    if depth > max_depth || visited.contains(current) {
+    if depth > max_depth || visited.contains(current) || !tracker.expand() {
         return;
     }
 
@@ -79,16 +87,108 @@ pub fn dfs_collect_paths(
     visited.insert(current.clone());
 
     if current == target && depth > 1 {
-        all_paths.push(path.clone());
+        if tracker.record_path() {
+            all_paths.push(path.clone());
+        }
     } else if let Some(neighbors) = graph.get(current) {
         let current_ts = timestamps.get(current).copied().unwrap_or(0);
         for neighbor in neighbors {
+            if tracker.exhausted() {
+                break;
+            }
             let neighbor_ts = timestamps.get(neighbor).copied().unwrap_or(usize::MAX);
             if neighbor_ts >= current_ts {
                 // println!("how long are you? : {}", all_paths.len());
                 dfs_collect_paths(
                     graph, timestamps, neighbor, target,
-                    path, all_paths, visited, depth + 1, max_depth
+                    path, all_paths, visited, depth + 1, max_depth, tracker
+                );
+            }
+        }
+    }
+
+    path.pop();
+    visited.remove(current);
+}
+
+/// Uniform sample of at most `capacity` paths, plus the exact number of paths offered.
+pub struct PathReservoir {
+    capacity: usize,
+    pub total: usize,
+    pub paths: Vec<Vec<String>>,
+    rng: StdRng,
+}
+
+impl PathReservoir {
+    /// A reservoir with a random seed.
+    pub fn new(capacity: usize) -> Self {
+        PathReservoir::seeded(capacity, rand::random())
+    }
+
+    /// A reservoir whose sample only depends on `seed` and the order paths are offered in.
+    pub fn seeded(capacity: usize, seed: u64) -> Self {
+        PathReservoir {
+            capacity,
+            total: 0,
+            paths: Vec::new(),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Offers one path to the sample (Algorithm R): every path seen so far
+    /// ends up kept with probability `capacity / total`.
+    pub fn offer(&mut self, path: &[String]) {
+        self.total += 1;
+        if self.paths.len() < self.capacity {
+            self.paths.push(path.to_vec());
+        } else {
+            let slot = self.rng.random_range(0..self.total);
+            if slot < self.capacity {
+                self.paths[slot] = path.to_vec();
+            }
+        }
+    }
+}
+
+/// Same traversal as `dfs_collect_paths`, but keeps a uniform reservoir sample
+/// of the paths instead of all of them, while still counting every path found.
+/// `graph` is the sorted adjacency from `successors`, so a seeded reservoir repeats.
+///
+/// # Updates
+/// * `reservoir`: sampled paths and exact total for this (start, target) pair.
+#[allow(clippy::too_many_arguments)]
+pub fn dfs_sample_paths(
+    graph: &HashMap<String, Vec<String>>,
+    timestamps: &HashMap<String, usize>,
+    current: &String,
+    target: &String,
+    path: &mut Vec<String>,
+    reservoir: &mut PathReservoir,
+    visited: &mut HashSet<String>,
+    depth: usize,
+    max_depth: usize,
+    tracker: &mut BudgetTracker,
+) {
+    if depth > max_depth || visited.contains(current) || !tracker.expand() {
+        return;
+    }
+
+    path.push(current.clone());
+    visited.insert(current.clone());
+
+    if current == target && depth > 1 {
+        if tracker.record_path() {
+            reservoir.offer(path);
+        }
+    } else {
+        for neighbor in graph.get(current).into_iter().flatten() {
+            if tracker.exhausted() {
+                break;
+            }
+            if time_respecting(timestamps, current, neighbor) {
+                dfs_sample_paths(
+                    graph, timestamps, neighbor, target,
+                    path, reservoir, visited, depth + 1, max_depth, tracker
                 );
             }
         }
@@ -100,12 +200,14 @@ pub fn dfs_collect_paths(
 
 /// Summary DFS: Instead of storing all paths, just records number of valid paths and their cumulative depth.
 /// 
-/// Enforces max path count per (start, target) to avoid explosion.
+/// The per-pair path cap is the budget's `max_paths`; going past it, or running
+/// out of any other `tracker` limit, stops the search.
 /// 
 /// # Updates
 /// * `stats`: (start, target) → (num_paths, total_depth)
+#[allow(clippy::too_many_arguments)]
 pub fn dfs_summary(
-    graph: &HashMap<String, HashSet<String>>,
+    graph: &HashMap<String, Vec<String>>,
     timestamps: &HashMap<String, usize>,
     current: &String,
     target: &String,  
@@ -114,59 +216,58 @@ pub fn dfs_summary(
     stats: &mut HashMap<(String, String), (usize, usize)>,
     start: &String,
     max_depth: usize,
-    max_path: usize,
+    tracker: &mut BudgetTracker,
 ) {
     if depth >= max_depth {
         // println!("Too deep");
         return;
     }    
     
-    if visited_on_path.contains(current) {
+    if visited_on_path.contains(current) || !tracker.expand() {
         return;
     }
 
     if current == target && depth > 1 {
-        let entry = stats.entry((start.clone(), target.clone())).or_insert((0, 0));
-        if entry.0 >= max_path {
-            return;
+        if tracker.record_path() {
+            let entry = stats.entry((start.clone(), target.clone())).or_insert((0, 0));
+            entry.0 += 1;
+            entry.1 += depth;
         }
-        entry.0 += 1;
-        entry.1 += depth;
-        // println!("Counting... {:?}", entry);
-        return; 
+        return;
     }
 
     visited_on_path.insert(current.clone());
 
-    if let Some(neighbors) = graph.get(current) {
-        let current_ts = timestamps.get(current).copied().unwrap_or(0);
-        for neighbor in neighbors {
-            let neighbor_ts = timestamps.get(neighbor).copied().unwrap_or(usize::MAX);
-            // println!("how long are you? : {}", depth);
-            if neighbor_ts >= current_ts && depth < max_depth {
-                // println!(
-                    // "Depth {}: {} → {} | cur_ts: {} neigh_ts: {} | visited: {:?}",
-                    // depth,
-                    // current,
-                    // neighbor,
-                    // current_ts,
-                    // neighbor_ts,
-                    // visited_on_path
-                // );
-                
-                dfs_summary(
-                    graph,
-                    timestamps,
-                    neighbor,
-                    target,
-                    depth + 1,
-                    visited_on_path,
-                    stats,
-                    start,
-                    max_depth,
-                    max_path,
-                );
-            }
+    let current_ts = timestamps.get(current).copied().unwrap_or(0);
+    for neighbor in graph.get(current).into_iter().flatten() {
+        if tracker.exhausted() {
+            break;
+        }
+        let neighbor_ts = timestamps.get(neighbor).copied().unwrap_or(usize::MAX);
+        // println!("how long are you? : {}", depth);
+        if neighbor_ts >= current_ts && depth < max_depth {
+            // println!(
+                // "Depth {}: {} → {} | cur_ts: {} neigh_ts: {} | visited: {:?}",
+                // depth,
+                // current,
+                // neighbor,
+                // current_ts,
+                // neighbor_ts,
+                // visited_on_path
+            // );
+            
+            dfs_summary(
+                graph,
+                timestamps,
+                neighbor,
+                target,
+                depth + 1,
+                visited_on_path,
+                stats,
+                start,
+                max_depth,
+                tracker,
+            );
         }
     }
 
@@ -175,29 +276,117 @@ pub fn dfs_summary(
 
 /// Entry point for DFS summary given multiple start/end node combinations.
 /// 
+/// `graph` is the sorted adjacency from `successors`.
+/// Each (start, target) search gets its own `budget`. Nothing is printed; callers
+/// that want a progress line with pairs completed and ETA pass one in `progress`.
+///
 /// # Returns
-/// Map from (start, end) → (num paths, total depth).
+/// Map from (start, end) → (num paths, total depth), and map from
+/// (start, end) → whether that search finished or was cut off.
+#[allow(clippy::too_many_arguments)]
 pub fn summarize_paths_to_targets(
-    graph: &HashMap<String, HashSet<String>>,
+    graph: &HashMap<String, Vec<String>>,
     timestamps: &HashMap<String, usize>,
     start_nodes: &Vec<String>,
     end_nodes: &Vec<String>,
     max_depth: usize,
-    max_path: usize,
-) -> HashMap<(String, String), (usize, usize)> {
+    budget: &SearchBudget,
+    mut progress: Option<&mut Progress>,
+) -> (PairStats, PairStatuses) {
     let mut stats = HashMap::new();
-    let target_set: HashSet<String> = end_nodes.iter().cloned().collect();
+    let mut statuses = HashMap::new();
 
     for start in start_nodes {
         for target in end_nodes {
             let mut visited = HashSet::new();
-            dfs_summary(graph, timestamps, start, target, 1, &mut visited, &mut stats, start, max_depth, max_path);
+            let mut tracker = BudgetTracker::new(*budget);
+            dfs_summary(graph, timestamps, start, target, 1, &mut visited, &mut stats, start, max_depth, &mut tracker);
+            let status = tracker.status();
+            if let Some(progress) = progress.as_deref_mut() {
+                progress.tick(status != SearchStatus::Finished);
+            }
+            statuses.insert((start.clone(), target.clone()), status);
         }
     }
-    
-    for ((start, end), (count, total_depth)) in &stats {
-        let avg_depth = *total_depth as f64 / *count as f64;
-        println!("{} → {}: {} paths, avg depth {:.2}", start, end, count, avg_depth);
+    if let Some(progress) = progress {
+        progress.finish();
     }
-    stats
-}
\ No newline at end of file
+    (stats, statuses)
+}
+
+/// True if the edge `from → to` can be taken without going back in time.
+///
+/// Uses the same defaults as the DFS functions above: a missing source timestamp
+/// counts as 0 and a missing target timestamp as `usize::MAX`.
+pub fn time_respecting(timestamps: &HashMap<String, usize>, from: &String, to: &String) -> bool {
+    let from_ts = timestamps.get(from).copied().unwrap_or(0);
+    let to_ts = timestamps.get(to).copied().unwrap_or(usize::MAX);
+    to_ts >= from_ts
+}
+
+/// Nodes reachable from `start` in at most `hops` time-respecting steps, sorted, without `start` itself.
+///
+/// Whether an edge can be taken doesn't depend on how the walk got there, so a
+/// level-by-level BFS with one visited set finds the same nodes as a path search,
+/// in time linear in the edges. Stops early (keeping what it found) when `tracker` runs out.
+pub fn reachable_within(
+    graph: &HashMap<String, HashSet<String>>,
+    timestamps: &HashMap<String, usize>,
+    start: &String,
+    hops: usize,
+    tracker: &mut BudgetTracker,
+) -> Vec<String> {
+    let mut seen: HashSet<&String> = HashSet::from([start]);
+    let mut frontier = vec![start];
+    for _ in 0..hops {
+        let mut next = Vec::new();
+        for node in frontier {
+            if !tracker.expand() {
+                break;
+            }
+            for tgt in graph.get(node).into_iter().flatten() {
+                if time_respecting(timestamps, node, tgt) && seen.insert(tgt) {
+                    next.push(tgt);
+                }
+            }
+        }
+        if next.is_empty() || tracker.exhausted() {
+            break;
+        }
+        frontier = next;
+    }
+    seen.remove(start);
+    let mut nodes: Vec<String> = seen.into_iter().cloned().collect();
+    nodes.sort();
+    nodes
+}
+
+/// Forward adjacency: node → sorted list of its out-neighbours.
+///
+/// `HashSet` order changes from one process to the next, so searches that stop
+/// early or sample what they find walk this instead to stay reproducible under a
+/// seed. Build it once per graph; the searches never sort.
+pub fn successors(graph: &HashMap<String, HashSet<String>>) -> HashMap<String, Vec<String>> {
+    graph
+        .iter()
+        .map(|(src, targets)| {
+            let mut list: Vec<String> = targets.iter().cloned().collect();
+            list.sort();
+            (src.clone(), list)
+        })
+        .collect()
+}
+
+/// Reverse adjacency: node → sorted list of nodes with an edge into it.
+pub fn predecessors(graph: &HashMap<String, HashSet<String>>) -> HashMap<String, Vec<String>> {
+    let mut preds: HashMap<String, Vec<String>> = HashMap::new();
+    for (src, targets) in graph {
+        for tgt in targets {
+            preds.entry(tgt.clone()).or_default().push(src.clone());
+        }
+    }
+    for list in preds.values_mut() {
+        list.sort();
+    }
+    preds
+}
@@ -0,0 +1,299 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use std::str::FromStr;
+
+use crate::adapter;
+use crate::analysis::MixerStats;
+use crate::config::Config;
+use crate::cycles;
+use crate::explain::label_name;
+use crate::fileread::Dataset;
+use crate::sketch::ReachSketches;
+use crate::structure;
+
+/// One side of a diff.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Version {
+    /// Timesteps `a..=b` of the configured dataset.
+    Window(usize, usize),
+    /// A dataset spec file (`*.spec`) or a folder of Elliptic CSVs.
+    Source(String),
+}
+
+impl FromStr for Version {
+    type Err = String;
+
+    /// Parses `a:b` as a window, anything else as a path.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((from, to)) = s.split_once(':')
+            && let (Ok(from), Ok(to)) = (from.parse::<usize>(), to.parse::<usize>())
+        {
+            if from > to {
+                return Err(format!("Window {} ends before it starts", s));
+            }
+            return Ok(Version::Window(from, to));
+        }
+        Ok(Version::Source(s.to_string()))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Version::Window(from, to) => write!(f, "timesteps {}-{}", from, to),
+            Version::Source(path) => write!(f, "{}", path),
+        }
+    }
+}
+
+impl Version {
+    /// Loads this side. Windows are cut from `base` (the configured dataset), loaded once on first use.
+    pub fn load(&self, config: &Config, base: &mut Option<Dataset>) -> Result<Dataset, String> {
+        match self {
+            Version::Window(from, to) => {
+                if base.is_none() {
+                    *base = Some(adapter::load_configured(config)?);
+                }
+                Ok(structure::restrict_to_window(base.as_ref().unwrap(), *from, *to))
+            }
+            Version::Source(path) => {
+                let mut config = config.clone();
+                if path.ends_with(".spec") {
+                    config.dataset = Some(path.clone());
+                } else {
+                    config.dataset = None;
+                    config.data_dir = path.clone();
+                }
+                adapter::load_configured(&config)
+            }
+        }
+    }
+}
+
+/// What changed between two versions of a graph.
+pub struct GraphDiff {
+    pub added_nodes: Vec<String>,
+    pub removed_nodes: Vec<String>,
+    pub added_edges: Vec<(String, String)>,
+    pub removed_edges: Vec<(String, String)>,
+    /// (node, old label, new label) for nodes in both versions, by name.
+    pub label_changes: Vec<(String, String, String)>,
+    /// (node, old, new) estimated forward reach of nodes in both versions whose
+    /// reach sketch changed, largest change first.
+    pub reach_changes: Vec<(String, f64, f64)>,
+    /// (node, old, new) size of the strongly connected component of nodes in both
+    /// versions whose component gained or lost members, largest first.
+    pub scc_changes: Vec<(String, usize, usize)>,
+}
+
+fn all_nodes(data: &Dataset) -> BTreeSet<&String> {
+    data.timestamps.keys().chain(data.edges.keys()).chain(data.edges.values().flatten()).collect()
+}
+
+fn edge_set(data: &Dataset) -> BTreeSet<(&String, &String)> {
+    data.edges.iter().flat_map(|(src, targets)| targets.iter().map(move |t| (src, t))).collect()
+}
+
+/// Node → sorted members of its strongly connected component, for nodes on a cycle.
+fn scc_members(data: &Dataset) -> HashMap<String, Vec<String>> {
+    let mut members = HashMap::new();
+    for component in cycles::strongly_connected_components(&data.edges).into_iter().filter(|c| c.len() > 1) {
+        for node in &component {
+            members.insert(node.clone(), component.clone());
+        }
+    }
+    members
+}
+
+/// Compares two versions node by node and edge by edge.
+///
+/// Reachability is compared through `ReachSketches` (time-respecting, any depth):
+/// exact for reach sets smaller than `k`, and for larger ones a change shows up
+/// whenever it touches the `k` smallest hashes. SCCs ignore time, as in `cycles.rs`.
+pub fn diff_graphs(old: &Dataset, new: &Dataset, k: usize) -> GraphDiff {
+    let (old_nodes, new_nodes) = (all_nodes(old), all_nodes(new));
+    let (old_edges, new_edges) = (edge_set(old), edge_set(new));
+    let owned = |nodes: Vec<&&String>| nodes.into_iter().map(|n| (*n).clone()).collect::<Vec<String>>();
+    let owned_edges = |edges: Vec<&(&String, &String)>| edges.into_iter().map(|(a, b)| ((*a).clone(), (*b).clone())).collect::<Vec<_>>();
+    let common: Vec<&String> = old_nodes.intersection(&new_nodes).copied().collect();
+
+    let label_of = |data: &Dataset, node: &String| label_name(data.labels.get(node).map_or("unknown", |l| l.as_str())).to_string();
+    let label_changes = common
+        .iter()
+        .map(|n| ((*n).clone(), label_of(old, n), label_of(new, n)))
+        .filter(|(_, a, b)| a != b)
+        .collect();
+
+    let (old_sketches, new_sketches) = (ReachSketches::build(&old.edges, &old.timestamps, k), ReachSketches::build(&new.edges, &new.timestamps, k));
+    let mut reach_changes: Vec<(String, f64, f64)> = common
+        .iter()
+        .filter(|n| old_sketches.forward(n) != new_sketches.forward(n))
+        .map(|n| ((*n).clone(), old_sketches.forward_size(n), new_sketches.forward_size(n)))
+        .collect();
+    reach_changes.sort_by(|a, b| (b.2 - b.1).abs().total_cmp(&(a.2 - a.1).abs()).then_with(|| a.0.cmp(&b.0)));
+
+    let (old_scc, new_scc) = (scc_members(old), scc_members(new));
+    let size = |members: &HashMap<String, Vec<String>>, node: &String| members.get(node).map_or(1, |m| m.len());
+    let mut scc_changes: Vec<(String, usize, usize)> = common
+        .iter()
+        .filter(|n| old_scc.get(**n) != new_scc.get(**n))
+        .map(|n| ((*n).clone(), size(&old_scc, n), size(&new_scc, n)))
+        .collect();
+    scc_changes.sort_by(|a, b| b.2.max(b.1).cmp(&a.2.max(a.1)).then_with(|| a.0.cmp(&b.0)));
+
+    GraphDiff {
+        added_nodes: owned(new_nodes.difference(&old_nodes).collect()),
+        removed_nodes: owned(old_nodes.difference(&new_nodes).collect()),
+        added_edges: owned_edges(new_edges.difference(&old_edges).collect()),
+        removed_edges: owned_edges(old_edges.difference(&new_edges).collect()),
+        label_changes,
+        reach_changes,
+        scc_changes,
+    }
+}
+
+/// How the mixer ranking moved between two versions.
+pub struct RankShift {
+    /// Candidates ranked in both versions.
+    pub common: usize,
+    /// Spearman correlation of the common candidates' ranks (None with fewer than 2).
+    pub spearman: Option<f64>,
+    /// (node, old rank if ranked, new rank) for nodes new to the top k.
+    pub entered: Vec<(String, Option<usize>, usize)>,
+    /// (node, old rank, new rank if ranked) for nodes that left the top k.
+    pub exited: Vec<(String, usize, Option<usize>)>,
+}
+
+/// Compares two rankings (each sorted best first, as in `AnalysisResult::stats`).
+///
+/// Ranks are 1-based positions in the full list. The correlation re-ranks the
+/// candidates present in both lists among themselves, so there are no ties.
+pub fn rank_shift(old: &[MixerStats], new: &[MixerStats], k: usize) -> RankShift {
+    let rank = |stats: &[MixerStats]| -> HashMap<String, usize> { stats.iter().enumerate().map(|(i, s)| (s.node.clone(), i + 1)).collect() };
+    let (old_rank, new_rank) = (rank(old), rank(new));
+
+    let common: Vec<&MixerStats> = old.iter().filter(|s| new_rank.contains_key(&s.node)).collect();
+    let n = common.len();
+    let spearman = (n >= 2).then(|| {
+        let mut by_new: Vec<&String> = common.iter().map(|s| &s.node).collect();
+        by_new.sort_by_key(|node| new_rank[*node]);
+        let new_position: HashMap<&String, usize> = by_new.into_iter().enumerate().map(|(i, node)| (node, i)).collect();
+        let d2: f64 = common.iter().enumerate().map(|(i, s)| (i as f64 - new_position[&s.node] as f64).powi(2)).sum();
+        let n = n as f64;
+        1.0 - 6.0 * d2 / (n * (n * n - 1.0))
+    });
+
+    let top = |stats: &[MixerStats]| -> HashSet<String> { stats.iter().take(k).map(|s| s.node.clone()).collect() };
+    let (old_top, new_top) = (top(old), top(new));
+    let entered = new
+        .iter()
+        .take(k)
+        .filter(|s| !old_top.contains(&s.node))
+        .map(|s| (s.node.clone(), old_rank.get(&s.node).copied(), new_rank[&s.node]))
+        .collect();
+    let exited = old
+        .iter()
+        .take(k)
+        .filter(|s| !new_top.contains(&s.node))
+        .map(|s| (s.node.clone(), old_rank[&s.node], new_rank.get(&s.node).copied()))
+        .collect();
+
+    RankShift { common: n, spearman, entered, exited }
+}
+
+fn rank_text(rank: Option<usize>) -> String {
+    rank.map_or("-".to_string(), |r| r.to_string())
+}
+
+/// Prints the change counts and the first `top` entries of each list.
+pub fn print_diff(diff: &GraphDiff, shift: Option<&RankShift>, top: usize) {
+    println!("\nNodes: +{} -{}   Edges: +{} -{}", diff.added_nodes.len(), diff.removed_nodes.len(), diff.added_edges.len(), diff.removed_edges.len());
+    println!(
+        "Label changes: {}   Reach changes: {}   SCC membership changes: {}",
+        diff.label_changes.len(), diff.reach_changes.len(), diff.scc_changes.len()
+    );
+
+    let mut transitions: HashMap<(&String, &String), usize> = HashMap::new();
+    for (_, old, new) in &diff.label_changes {
+        *transitions.entry((old, new)).or_insert(0) += 1;
+    }
+    let mut transitions: Vec<_> = transitions.into_iter().collect();
+    transitions.sort();
+    for ((old, new), count) in transitions {
+        println!("  {} -> {}: {}", old, new, count);
+    }
+
+    println!("\nLargest reach changes:");
+    println!("{:<15} {:>12} {:>12}", "txId", "Old reach", "New reach");
+    for (node, old, new) in diff.reach_changes.iter().take(top) {
+        println!("{:<15} {:>12.0} {:>12.0}", node, old, new);
+    }
+    println!("\nLargest SCC changes:");
+    println!("{:<15} {:>10} {:>10}", "txId", "Old SCC", "New SCC");
+    for (node, old, new) in diff.scc_changes.iter().take(top) {
+        println!("{:<15} {:>10} {:>10}", node, old, new);
+    }
+
+    if let Some(shift) = shift {
+        match shift.spearman {
+            Some(rho) => println!("\nRanking: Spearman {:.4} over {} candidates ranked in both", rho, shift.common),
+            None => println!("\nRanking: {} candidates ranked in both, too few to correlate", shift.common),
+        }
+        println!("{:<10} {:<15} {:>9} {:>9}", "Top-k", "txId", "Old rank", "New rank");
+        for (node, old, new) in &shift.entered {
+            println!("{:<10} {:<15} {:>9} {:>9}", "entered", node, rank_text(*old), new);
+        }
+        for (node, old, new) in &shift.exited {
+            println!("{:<10} {:<15} {:>9} {:>9}", "exited", node, old, rank_text(*new));
+        }
+    }
+}
+
+/// Writes every list of the diff as `# section` CSV blocks, like `structure::write_report`.
+pub fn write_diff(path: &str, diff: &GraphDiff, shift: Option<&RankShift>, source: &str) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
+    writeln!(file, "# graph diff: {}", source)?;
+
+    writeln!(file, "\n# nodes\nchange,txId")?;
+    for node in &diff.added_nodes {
+        writeln!(file, "added,{}", node)?;
+    }
+    for node in &diff.removed_nodes {
+        writeln!(file, "removed,{}", node)?;
+    }
+    writeln!(file, "\n# edges\nchange,source,target")?;
+    for (src, tgt) in &diff.added_edges {
+        writeln!(file, "added,{},{}", src, tgt)?;
+    }
+    for (src, tgt) in &diff.removed_edges {
+        writeln!(file, "removed,{},{}", src, tgt)?;
+    }
+    writeln!(file, "\n# labels\ntxId,old,new")?;
+    for (node, old, new) in &diff.label_changes {
+        writeln!(file, "{},{},{}", node, old, new)?;
+    }
+    writeln!(file, "\n# reach\ntxId,old_reach,new_reach")?;
+    for (node, old, new) in &diff.reach_changes {
+        writeln!(file, "{},{:.0},{:.0}", node, old, new)?;
+    }
+    writeln!(file, "\n# scc\ntxId,old_size,new_size")?;
+    for (node, old, new) in &diff.scc_changes {
+        writeln!(file, "{},{},{}", node, old, new)?;
+    }
+
+    if let Some(shift) = shift {
+        writeln!(file, "\n# ranking\nstat,value")?;
+        writeln!(file, "common,{}", shift.common)?;
+        writeln!(file, "spearman,{}", shift.spearman.map_or(String::new(), |r| format!("{:.6}", r)))?;
+        writeln!(file, "\n# top_k\nchange,txId,old_rank,new_rank")?;
+        for (node, old, new) in &shift.entered {
+            writeln!(file, "entered,{},{},{}", node, old.map_or(String::new(), |r| r.to_string()), new)?;
+        }
+        for (node, old, new) in &shift.exited {
+            writeln!(file, "exited,{},{},{}", node, old, new.map_or(String::new(), |r| r.to_string()))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,177 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::budget::{BudgetTracker, SearchBudget, SearchStatus};
+use crate::dfsstuff;
+
+/// One example route from a start node to the explained node.
+pub struct PathExample {
+    pub path: Vec<String>,
+    pub timesteps: Vec<usize>,
+    /// Share of nodes after the start that are labeled licit.
+    pub licit_exposure: f64,
+}
+
+/// A start node that reaches the explained node, with its shortest route.
+pub struct StartReach {
+    pub start: String,
+    pub label: String,
+    pub num_paths: usize,
+    pub status: SearchStatus,
+    pub example: PathExample,
+}
+
+/// Why a node scored the way it did: who reaches it, how, and when.
+pub struct Explanation {
+    pub node: String,
+    pub label: String,
+    pub timestep: Option<usize>,
+    pub starts_searched: usize,
+    /// Searches that hit their budget before finishing.
+    pub cut_off_searches: usize,
+    pub reaching_starts: Vec<StartReach>,
+    /// Start label → number of starts that reach the node.
+    pub label_breakdown: BTreeMap<String, usize>,
+    /// Neighbor timestep → (in-degree, out-degree) contributed at that timestep.
+    pub degree_over_time: BTreeMap<usize, (usize, usize)>,
+}
+
+/// Human readable name for an Elliptic class value.
+pub fn label_name(label: &str) -> &str {
+    match label {
+        "1" => "licit",
+        "2" => "illicit",
+        other => other,
+    }
+}
+
+/// Reruns `dfs_collect_paths` from each start to `target` and summarizes what it finds.
+///
+/// # Arguments
+/// * `graph`, `timestamps`, `labels` - The loaded dataset.
+/// * `target` - The node being explained.
+/// * `starts` - Start nodes to search from (typically sampled illicit and licit nodes).
+/// * `max_depth` - Depth cap for each path search.
+/// * `budget` - Per-start search budget.
+///
+/// # Returns
+/// An `Explanation` with the starts that reach `target`, sorted by number of paths.
+pub fn explain_node(
+    graph: &HashMap<String, HashSet<String>>,
+    timestamps: &HashMap<String, usize>,
+    labels: &HashMap<String, String>,
+    target: &String,
+    starts: &[String],
+    max_depth: usize,
+    budget: &SearchBudget,
+) -> Explanation {
+    let label_of = |node: &String| label_name(labels.get(node).map_or("unknown", |l| l.as_str())).to_string();
+
+    let mut reaching_starts = Vec::new();
+    let mut label_breakdown = BTreeMap::new();
+    let mut cut_off_searches = 0;
+
+    for start in starts {
+        if start == target {
+            continue;
+        }
+        let mut all_paths = Vec::new();
+        let mut path = Vec::new();
+        let mut visited = HashSet::new();
+        let mut tracker = BudgetTracker::new(*budget);
+        dfsstuff::dfs_collect_paths(
+            graph, timestamps,
+            start, target,
+            &mut path, &mut all_paths, &mut visited,
+            1, max_depth, &mut tracker,
+        );
+        if tracker.exhausted() {
+            cut_off_searches += 1;
+        }
+
+        if let Some(shortest) = all_paths.iter().min_by_key(|p| p.len()) {
+            let licit_hops = shortest[1..].iter().filter(|n| labels.get(*n).map(|l| l.as_str()) == Some("1")).count();
+            let example = PathExample {
+                timesteps: shortest.iter().map(|n| timestamps.get(n).copied().unwrap_or(0)).collect(),
+                licit_exposure: licit_hops as f64 / (shortest.len() - 1) as f64,
+                path: shortest.clone(),
+            };
+            let label = label_of(start);
+            *label_breakdown.entry(label.clone()).or_insert(0) += 1;
+            reaching_starts.push(StartReach {
+                start: start.clone(),
+                label,
+                num_paths: all_paths.len(),
+                status: tracker.status(),
+                example,
+            });
+        }
+    }
+    reaching_starts.sort_by_key(|s| std::cmp::Reverse(s.num_paths));
+
+    let mut degree_over_time: BTreeMap<usize, (usize, usize)> = BTreeMap::new();
+    for (src, targets) in graph {
+        if src == target {
+            for tgt in targets {
+                let ts = timestamps.get(tgt).copied().unwrap_or(0);
+                degree_over_time.entry(ts).or_insert((0, 0)).1 += 1;
+            }
+        } else if targets.contains(target) {
+            let ts = timestamps.get(src).copied().unwrap_or(0);
+            degree_over_time.entry(ts).or_insert((0, 0)).0 += 1;
+        }
+    }
+
+    Explanation {
+        node: target.clone(),
+        label: label_of(target),
+        timestep: timestamps.get(target).copied(),
+        starts_searched: starts.len(),
+        cut_off_searches,
+        reaching_starts,
+        label_breakdown,
+        degree_over_time,
+    }
+}
+
+/// Prints an explanation as a sectioned report.
+///
+/// # Arguments
+/// * `explanation` - Result of `explain_node`.
+/// * `max_examples` - How many reaching starts to show example paths for.
+pub fn print_explanation(explanation: &Explanation, max_examples: usize) {
+    println!("\n=== Explanation for {} ===", explanation.node);
+    println!("Label: {}", explanation.label);
+    match explanation.timestep {
+        Some(ts) => println!("Timestep: {}", ts),
+        None => println!("Timestep: (none)"),
+    }
+
+    println!(
+        "\n[starts] {} of {} searched starts reach this node",
+        explanation.reaching_starts.len(),
+        explanation.starts_searched
+    );
+    if explanation.cut_off_searches > 0 {
+        println!("  ({} searches were cut off by the budget)", explanation.cut_off_searches);
+    }
+    for (label, count) in &explanation.label_breakdown {
+        println!("  {:<10} {:>6}", label, count);
+    }
+
+    println!("\n[paths] Representative paths (shortest per start):");
+    for reach in explanation.reaching_starts.iter().take(max_examples) {
+        println!(
+            "  {} ({}) - {} paths ({}), licit exposure {:.2}",
+            reach.start, reach.label, reach.num_paths, reach.status, reach.example.licit_exposure
+        );
+        println!("    {}", reach.example.path.join(" → "));
+        let steps: Vec<String> = reach.example.timesteps.iter().map(|t| t.to_string()).collect();
+        println!("    t: {}", steps.join(" → "));
+    }
+
+    println!("\n[degree] In/out degree by neighbor timestep:");
+    println!("  {:<10} {:>6} {:>6}", "Timestep", "In", "Out");
+    for (ts, (in_deg, out_deg)) in &explanation.degree_over_time {
+        println!("  {:<10} {:>6} {:>6}", ts, in_deg, out_deg);
+    }
+}
@@ -0,0 +1,514 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::str::FromStr;
+use rand::prelude::IndexedRandom;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::analysis;
+use crate::budget::{BudgetTracker, SearchBudget};
+use crate::config::Config;
+use crate::cycles;
+use crate::dfsstuff;
+use crate::explain::label_name;
+use crate::fileread::Dataset;
+
+/// Depths at which reachable-set sizes are counted.
+const REACH_DEPTHS: [usize; 3] = [1, 2, 3];
+/// Cycle length cap for the cycle participation feature (same `k` the `run` command uses).
+const CYCLE_K: usize = 3;
+/// Number of quantile bins the boosted trees split on.
+const BINS: usize = 32;
+
+/// Per-node feature vectors with a name per column.
+pub struct FeatureTable {
+    pub names: Vec<String>,
+    pub rows: HashMap<String, Vec<f64>>,
+}
+
+impl FeatureTable {
+    /// Column-wise concatenation; nodes missing from `other` get zeros there.
+    pub fn join(&self, other: &FeatureTable) -> FeatureTable {
+        let names = self.names.iter().chain(&other.names).cloned().collect();
+        let rows = self
+            .rows
+            .iter()
+            .map(|(node, row)| {
+                let mut joined = row.clone();
+                match other.rows.get(node) {
+                    Some(extra) => joined.extend(extra),
+                    None => joined.extend(std::iter::repeat_n(0.0, other.names.len())),
+                }
+                (node.clone(), joined)
+            })
+            .collect();
+        FeatureTable { names, rows }
+    }
+}
+
+/// Which classifier `classify` trains.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Model {
+    /// L2-regularised logistic regression on standardised features.
+    LogReg,
+    /// Gradient-boosted depth-limited regression trees with logistic loss.
+    Gbt,
+}
+
+impl FromStr for Model {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "logreg" => Ok(Model::LogReg),
+            "gbt" => Ok(Model::Gbt),
+            _ => Err(format!("Unknown model {} (expected logreg or gbt)", s)),
+        }
+    }
+}
+
+/// Labels a model may look at: only nodes up to `train_until` keep their label.
+///
+/// Every label-derived feature is built from these, so nothing about the
+/// test period's labels leaks into training or into the test features.
+pub fn visible_labels(data: &Dataset, train_until: usize) -> HashMap<String, String> {
+    data.labels
+        .iter()
+        .filter(|(node, _)| data.timestamps.get(*node).is_some_and(|ts| *ts <= train_until))
+        .map(|(n, l)| (n.clone(), l.clone()))
+        .collect()
+}
+
+/// Topological features of every node with a timestamp.
+///
+/// Columns: in/out degree, time-respecting reachable-set size at depths 1-3,
+/// number of simple cycles of up to `CYCLE_K + 1` nodes through the node,
+/// `theory_tester` reuse count per configured group (starts sampled from the
+/// training period), and the share of illicit/licit/unknown neighbours.
+///
+/// # Arguments
+/// * `data` - The loaded dataset.
+/// * `labels` - Labels the features may use (see `visible_labels`).
+/// * `config` - Groups, sample size, seed and depth caps for the reuse counts.
+pub fn graph_features(data: &Dataset, labels: &HashMap<String, String>, config: &Config) -> FeatureTable {
+    let mut names: Vec<String> = vec!["in_degree".to_string(), "out_degree".to_string()];
+    names.extend(REACH_DEPTHS.iter().map(|d| format!("reach_{}", d)));
+    names.push(format!("cycles_{}", CYCLE_K));
+    names.extend(config.groups.iter().map(|g| format!("reuse_{}", g.name)));
+    names.extend(["nbr_illicit", "nbr_licit", "nbr_unknown"].iter().map(|n| n.to_string()));
+
+    let predecessors = dfsstuff::predecessors(&data.edges);
+
+    let successors = dfsstuff::successors(&data.edges);
+    let mut rng = StdRng::seed_from_u64(config.seed.unwrap_or_else(rand::random));
+    let reuse: Vec<HashMap<String, usize>> = config
+        .groups
+        .iter()
+        .map(|group| {
+            let sampled: Vec<String> = group.members(labels).choose_multiple(&mut rng, config.sample_size).cloned().collect();
+            analysis::reuse_tally(&data.edges, &successors, &data.timestamps, labels, &sampled, &group.name, config, &mut rng).node_freq
+        })
+        .collect();
+
+    let rows = data
+        .timestamps
+        .keys()
+        .map(|node| {
+            let successors = data.edges.get(node);
+            let preds = predecessors.get(node);
+            let mut row = vec![
+                preds.map_or(0, |p| p.len()) as f64,
+                successors.map_or(0, |s| s.len()) as f64,
+            ];
+            let unlimited = || BudgetTracker::new(SearchBudget::default());
+            for depth in REACH_DEPTHS {
+                row.push(dfsstuff::reachable_within(&data.edges, &data.timestamps, node, depth, &mut unlimited()).len() as f64);
+            }
+            let cycle_count = if successors.is_some() { cycles::cycles_through(&data.edges, node, CYCLE_K, &mut unlimited()).len() } else { 0 };
+            row.push(cycle_count as f64);
+            for freq in &reuse {
+                row.push(freq.get(node).copied().unwrap_or(0) as f64);
+            }
+
+            let neighbours: HashSet<&String> = successors
+                .into_iter()
+                .flatten()
+                .chain(preds.into_iter().flatten())
+                .filter(|n| *n != node)
+                .collect();
+            let mut shares = [0.0; 3];
+            for n in &neighbours {
+                let slot = match label_name(labels.get(*n).map_or("unknown", |l| l.as_str())) {
+                    "illicit" => 0,
+                    "licit" => 1,
+                    _ => 2,
+                };
+                shares[slot] += 1.0 / neighbours.len() as f64;
+            }
+            row.extend(shares);
+            (node.clone(), row)
+        })
+        .collect();
+
+    FeatureTable { names, rows }
+}
+
+/// Reads the raw Elliptic feature file (`txId,timestep,f1,...`, no header),
+/// dropping the timestep column.
+pub fn read_raw_features(path: &str) -> Result<FeatureTable, String> {
+    let file = File::open(path).map_err(|e| format!("Could not open {}: {}", path, e))?;
+    let mut rows = HashMap::new();
+    let mut width = None;
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("Could not read {}: {}", path, e))?;
+        let mut parts = line.trim().split(',');
+        let Some(node) = parts.next().filter(|n| !n.is_empty()) else { continue };
+        let values: Vec<f64> = parts
+            .skip(1)
+            .map(|v| v.parse::<f64>().map_err(|_| format!("{} line {}: {} is not a number", path, i + 1, v)))
+            .collect::<Result<_, _>>()?;
+        if *width.get_or_insert(values.len()) != values.len() {
+            return Err(format!("{} line {}: expected {} features, found {}", path, i + 1, width.unwrap(), values.len()));
+        }
+        rows.insert(node.to_string(), values);
+    }
+    let names = (1..=width.unwrap_or(0)).map(|i| format!("raw_{}", i)).collect();
+    Ok(FeatureTable { names, rows })
+}
+
+/// Writes `txId,timestep,label,<features...>` for every node in the table.
+pub fn write_features(path: &str, table: &FeatureTable, data: &Dataset) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
+    writeln!(file, "txId,timestep,label,{}", table.names.join(","))?;
+    let mut nodes: Vec<&String> = table.rows.keys().collect();
+    nodes.sort();
+    for node in nodes {
+        let values: Vec<String> = table.rows[node].iter().map(|v| v.to_string()).collect();
+        writeln!(
+            file,
+            "{},{},{},{}",
+            node,
+            data.timestamps.get(node).copied().unwrap_or(0),
+            label_name(data.labels.get(node).map_or("unknown", |l| l.as_str())),
+            values.join(",")
+        )?;
+    }
+    Ok(())
+}
+
+/// A regression tree over raw feature values; leaves hold log-odds increments.
+pub enum Tree {
+    Leaf(f64),
+    Split { feature: usize, threshold: f64, left: Box<Tree>, right: Box<Tree> },
+}
+
+impl Tree {
+    fn predict(&self, row: &[f64]) -> f64 {
+        match self {
+            Tree::Leaf(value) => *value,
+            Tree::Split { feature, threshold, left, right } => {
+                if row[*feature] <= *threshold { left.predict(row) } else { right.predict(row) }
+            }
+        }
+    }
+}
+
+/// A trained binary classifier; `predict` gives the probability of the positive (illicit) class.
+pub enum Classifier {
+    Logistic { mean: Vec<f64>, scale: Vec<f64>, weights: Vec<f64>, bias: f64 },
+    Boosted { base: f64, rate: f64, trees: Vec<Tree> },
+}
+
+fn sigmoid(z: f64) -> f64 {
+    1.0 / (1.0 + (-z).exp())
+}
+
+impl Classifier {
+    pub fn predict(&self, row: &[f64]) -> f64 {
+        match self {
+            Classifier::Logistic { mean, scale, weights, bias } => {
+                let z: f64 = row.iter().zip(mean).zip(scale).zip(weights).map(|(((x, m), s), w)| w * (x - m) / s).sum();
+                sigmoid(z + bias)
+            }
+            Classifier::Boosted { base, rate, trees } => {
+                sigmoid(base + rate * trees.iter().map(|t| t.predict(row)).sum::<f64>())
+            }
+        }
+    }
+}
+
+/// Logistic regression by full-batch gradient descent on standardised features.
+///
+/// # Arguments
+/// * `x`, `y` - Training rows and 0/1 targets.
+/// * `epochs` - Gradient steps.
+/// * `rate` - Step size.
+/// * `l2` - Weight penalty (the bias is not penalised).
+pub fn train_logreg(x: &[Vec<f64>], y: &[f64], epochs: usize, rate: f64, l2: f64) -> Classifier {
+    let (n, width) = (x.len().max(1) as f64, x.first().map_or(0, |r| r.len()));
+    let mut mean = vec![0.0; width];
+    let mut scale = vec![0.0; width];
+    for row in x {
+        for (m, v) in mean.iter_mut().zip(row) {
+            *m += v / n;
+        }
+    }
+    for row in x {
+        for ((s, m), v) in scale.iter_mut().zip(&mean).zip(row) {
+            *s += (v - m).powi(2) / n;
+        }
+    }
+    // Constant columns keep a scale of 1 so they standardise to 0 instead of NaN.
+    let scale: Vec<f64> = scale.into_iter().map(|v| if v > 1e-12 { v.sqrt() } else { 1.0 }).collect();
+    let standardised: Vec<Vec<f64>> = x
+        .iter()
+        .map(|row| row.iter().zip(&mean).zip(&scale).map(|((v, m), s)| (v - m) / s).collect())
+        .collect();
+
+    let mut weights = vec![0.0; width];
+    let mut bias = 0.0;
+    for _ in 0..epochs {
+        let mut grad = vec![0.0; width];
+        let mut grad_bias = 0.0;
+        for (row, target) in standardised.iter().zip(y) {
+            let z: f64 = row.iter().zip(&weights).map(|(v, w)| v * w).sum();
+            let err = sigmoid(z + bias) - target;
+            for (g, v) in grad.iter_mut().zip(row) {
+                *g += err * v;
+            }
+            grad_bias += err;
+        }
+        for (w, g) in weights.iter_mut().zip(&grad) {
+            *w -= rate * (g / n + l2 * *w);
+        }
+        bias -= rate * grad_bias / n;
+    }
+    Classifier::Logistic { mean, scale, weights, bias }
+}
+
+/// Gradient-boosted trees with logistic loss (second-order leaf values, L2 of 1 on the leaves).
+///
+/// Splits are searched over `BINS` quantile cut points per feature.
+///
+/// # Arguments
+/// * `rounds` - Number of trees.
+/// * `depth` - Maximum tree depth.
+/// * `rate` - Shrinkage applied to every tree.
+pub fn train_gbt(x: &[Vec<f64>], y: &[f64], rounds: usize, depth: usize, rate: f64) -> Classifier {
+    let width = x.first().map_or(0, |r| r.len());
+    let thresholds: Vec<Vec<f64>> = (0..width)
+        .map(|f| {
+            let mut values: Vec<f64> = x.iter().map(|r| r[f]).collect();
+            values.sort_by(f64::total_cmp);
+            if values.is_empty() {
+                return Vec::new();
+            }
+            let mut cuts: Vec<f64> = (1..BINS).map(|b| values[b * (values.len() - 1) / BINS]).collect();
+            cuts.dedup();
+            cuts
+        })
+        .collect();
+    // bins[i][f] = index of the first cut point >= x[i][f] (cuts.len() if none).
+    let bins: Vec<Vec<usize>> = x
+        .iter()
+        .map(|row| row.iter().zip(&thresholds).map(|(v, cuts)| cuts.partition_point(|c| c < v)).collect())
+        .collect();
+
+    let positives: f64 = y.iter().sum();
+    let prior = (positives / y.len().max(1) as f64).clamp(1e-6, 1.0 - 1e-6);
+    let base = (prior / (1.0 - prior)).ln();
+    let mut margin = vec![base; x.len()];
+    let mut trees = Vec::new();
+    for _ in 0..rounds {
+        let (grad, hess): (Vec<f64>, Vec<f64>) = margin
+            .iter()
+            .zip(y)
+            .map(|(m, t)| {
+                let p = sigmoid(*m);
+                (p - t, (p * (1.0 - p)).max(1e-6))
+            })
+            .unzip();
+        let rows: Vec<usize> = (0..x.len()).collect();
+        let tree = grow_tree(&rows, &bins, &thresholds, &grad, &hess, depth);
+        for (i, m) in margin.iter_mut().enumerate() {
+            *m += rate * tree.predict(&x[i]);
+        }
+        trees.push(tree);
+    }
+    Classifier::Boosted { base, rate, trees }
+}
+
+fn grow_tree(rows: &[usize], bins: &[Vec<usize>], thresholds: &[Vec<f64>], grad: &[f64], hess: &[f64], depth: usize) -> Tree {
+    const LAMBDA: f64 = 1.0;
+    let (g, h): (f64, f64) = rows.iter().fold((0.0, 0.0), |(g, h), &i| (g + grad[i], h + hess[i]));
+    let leaf = Tree::Leaf(-g / (h + LAMBDA));
+    if depth == 0 || rows.len() < 2 {
+        return leaf;
+    }
+
+    let parent = g * g / (h + LAMBDA);
+    let mut best: Option<(f64, usize, usize)> = None;
+    for (feature, cuts) in thresholds.iter().enumerate() {
+        let mut hist = vec![(0.0, 0.0); cuts.len() + 1];
+        for &i in rows {
+            let entry = &mut hist[bins[i][feature]];
+            entry.0 += grad[i];
+            entry.1 += hess[i];
+        }
+        let (mut gl, mut hl) = (0.0, 0.0);
+        for (cut, (bg, bh)) in hist.iter().take(cuts.len()).enumerate() {
+            gl += bg;
+            hl += bh;
+            let (gr, hr) = (g - gl, h - hl);
+            let gain = gl * gl / (hl + LAMBDA) + gr * gr / (hr + LAMBDA) - parent;
+            if gain > 1e-9 && best.is_none_or(|(b, _, _)| gain > b) {
+                best = Some((gain, feature, cut));
+            }
+        }
+    }
+
+    let Some((_, feature, cut)) = best else { return leaf };
+    let (left, right): (Vec<usize>, Vec<usize>) = rows.iter().partition(|&&i| bins[i][feature] <= cut);
+    if left.is_empty() || right.is_empty() {
+        return leaf;
+    }
+    Tree::Split {
+        feature,
+        threshold: thresholds[feature][cut],
+        left: Box::new(grow_tree(&left, bins, thresholds, grad, hess, depth - 1)),
+        right: Box::new(grow_tree(&right, bins, thresholds, grad, hess, depth - 1)),
+    }
+}
+
+/// Trains the chosen model with the settings `classify` uses.
+pub fn train(model: Model, x: &[Vec<f64>], y: &[f64]) -> Classifier {
+    match model {
+        Model::LogReg => train_logreg(x, y, 500, 0.5, 1e-4),
+        Model::Gbt => train_gbt(x, y, 100, 3, 0.1),
+    }
+}
+
+/// Illicit-class metrics on the test period.
+pub struct Evaluation {
+    pub tp: usize,
+    pub fp: usize,
+    pub tn: usize,
+    pub fn_: usize,
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+    pub accuracy: f64,
+    /// Area under the ROC curve of the predicted probabilities.
+    pub auc: f64,
+    /// Timestep → illicit F1 within that timestep.
+    pub f1_by_timestep: BTreeMap<usize, f64>,
+}
+
+fn f1_score(tp: usize, fp: usize, fn_: usize) -> (f64, f64, f64) {
+    let precision = if tp + fp > 0 { tp as f64 / (tp + fp) as f64 } else { 0.0 };
+    let recall = if tp + fn_ > 0 { tp as f64 / (tp + fn_) as f64 } else { 0.0 };
+    let f1 = if precision + recall > 0.0 { 2.0 * precision * recall / (precision + recall) } else { 0.0 };
+    (precision, recall, f1)
+}
+
+/// Scores `(probability, is_illicit, timestep)` predictions at a 0.5 threshold.
+pub fn evaluate(predictions: &[(f64, bool, usize)]) -> Evaluation {
+    let (mut tp, mut fp, mut tn, mut fn_) = (0, 0, 0, 0);
+    let mut per_ts: BTreeMap<usize, (usize, usize, usize)> = BTreeMap::new();
+    for &(p, positive, ts) in predictions {
+        let entry = per_ts.entry(ts).or_insert((0, 0, 0));
+        match (p >= 0.5, positive) {
+            (true, true) => { tp += 1; entry.0 += 1; }
+            (true, false) => { fp += 1; entry.1 += 1; }
+            (false, true) => { fn_ += 1; entry.2 += 1; }
+            (false, false) => tn += 1,
+        }
+    }
+    let (precision, recall, f1) = f1_score(tp, fp, fn_);
+
+    // AUC as the share of (positive, negative) pairs ranked correctly, ties counting half.
+    let mut sorted: Vec<(f64, bool)> = predictions.iter().map(|&(p, pos, _)| (p, pos)).collect();
+    sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let (mut negatives_below, mut correct) = (0.0, 0.0);
+    let mut i = 0;
+    while i < sorted.len() {
+        let j = sorted[i..].iter().position(|s| s.0 != sorted[i].0).map_or(sorted.len(), |k| i + k);
+        let (pos, neg) = sorted[i..j].iter().fold((0.0, 0.0), |(p, n), s| if s.1 { (p + 1.0, n) } else { (p, n + 1.0) });
+        correct += pos * (negatives_below + neg / 2.0);
+        negatives_below += neg;
+        i = j;
+    }
+    let pairs = (tp + fn_) as f64 * (fp + tn) as f64;
+
+    Evaluation {
+        tp, fp, tn, fn_,
+        precision, recall, f1,
+        accuracy: (tp + tn) as f64 / predictions.len().max(1) as f64,
+        auc: if pairs > 0.0 { correct / pairs } else { 0.0 },
+        f1_by_timestep: per_ts.into_iter().map(|(ts, (tp, fp, fn_))| (ts, f1_score(tp, fp, fn_).2)).collect(),
+    }
+}
+
+/// Trains on labelled nodes with timestep `<= train_until` and evaluates on the later ones.
+///
+/// Only illicit (`2`) and licit (`1`) nodes are used; illicit is the positive class.
+///
+/// # Returns
+/// The test-period evaluation, or an error if either side of the split has no labelled nodes.
+pub fn temporal_split_eval(table: &FeatureTable, data: &Dataset, train_until: usize, model: Model) -> Result<Evaluation, String> {
+    let mut nodes: Vec<(&String, usize, bool)> = data
+        .labels
+        .iter()
+        .filter(|(node, label)| (*label == "1" || *label == "2") && table.rows.contains_key(*node))
+        .filter_map(|(node, label)| data.timestamps.get(node).map(|ts| (node, *ts, label == "2")))
+        .collect();
+    nodes.sort();
+    let (train_nodes, test): (Vec<_>, Vec<_>) = nodes.into_iter().partition(|(_, ts, _)| *ts <= train_until);
+    if train_nodes.is_empty() || test.is_empty() {
+        return Err(format!(
+            "Temporal split at timestep {} leaves {} labelled training and {} test nodes",
+            train_until, train_nodes.len(), test.len()
+        ));
+    }
+
+    let x: Vec<Vec<f64>> = train_nodes.iter().map(|(n, _, _)| table.rows[*n].clone()).collect();
+    let y: Vec<f64> = train_nodes.iter().map(|(_, _, pos)| if *pos { 1.0 } else { 0.0 }).collect();
+    let classifier = train(model, &x, &y);
+    let predictions: Vec<(f64, bool, usize)> = test
+        .iter()
+        .map(|(n, ts, pos)| (classifier.predict(&table.rows[*n]), *pos, *ts))
+        .collect();
+    Ok(evaluate(&predictions))
+}
+
+/// Prints one metrics row per feature set, then the per-timestep F1 of each.
+pub fn print_evaluations(results: &[(&str, Evaluation)]) {
+    println!(
+        "\n{:<12} {:>6} {:>6} {:>6} {:>6} {:>10} {:>8} {:>8} {:>8} {:>8}",
+        "Features", "TP", "FP", "TN", "FN", "Precision", "Recall", "F1", "Acc", "AUC"
+    );
+    for (name, e) in results {
+        println!(
+            "{:<12} {:>6} {:>6} {:>6} {:>6} {:>10.4} {:>8.4} {:>8.4} {:>8.4} {:>8.4}",
+            name, e.tp, e.fp, e.tn, e.fn_, e.precision, e.recall, e.f1, e.accuracy, e.auc
+        );
+    }
+
+    println!("\nIllicit F1 per test timestep:");
+    print!("{:<10}", "Timestep");
+    for (name, _) in results {
+        print!(" {:>12}", name);
+    }
+    println!();
+    let timesteps: HashSet<usize> = results.iter().flat_map(|(_, e)| e.f1_by_timestep.keys().copied()).collect();
+    let mut timesteps: Vec<usize> = timesteps.into_iter().collect();
+    timesteps.sort();
+    for ts in timesteps {
+        print!("{:<10}", ts);
+        for (_, e) in results {
+            print!(" {:>12.4}", e.f1_by_timestep.get(&ts).copied().unwrap_or(0.0));
+        }
+        println!();
+    }
+}
@@ -1,81 +1,111 @@
-use plotters::prelude::*;
-use std::error::Error;
 use std::collections::{HashMap, HashSet};
-use std::vec;
-use std::io::{BufReader, BufRead};
+use std::io::BufRead;
 use std::fs::File;
-use rand::seq::SliceRandom;
-use rand::prelude::IndexedRandom;
 
-pub fn read_to_hashmap(path: &str) -> HashMap<String, String> {
+/// Opens `path` for line-by-line reading, naming the file in the error.
+fn open_lines(path: &str) -> Result<std::io::Lines<std::io::BufReader<File>>, String> {
+    let file = File::open(path).map_err(|e| format!("Could not open {}: {}", path, e))?;
+    Ok(std::io::BufReader::new(file).lines())
+}
+
+/// Splits a `first,second,...` row into its first two columns.
+fn two_columns(line_str: &str, path: &str, line_number: usize) -> Result<(String, String), String> {
+    let v: Vec<&str> = line_str.trim().split(',').collect();
+    if v.len() < 2 {
+        return Err(format!("{} line {}: expected at least two comma-separated columns", path, line_number));
+    }
+    Ok((v[0].to_string(), v[1].to_string()))
+}
+
+pub fn read_to_hashmap(path: &str) -> Result<HashMap<String, String>, String> {
     let mut result: HashMap<String, String> = HashMap::new();
-    let file = File::open(path).expect("Could not open file");
-    let buf_reader = std::io::BufReader::new(file).lines();
-    for line in buf_reader {
-        let line_str = line.expect("Error reading");
-        let v: Vec<&str> = line_str.trim().split(',').collect();
-        let k = v[0].to_string();
-        let y = v[1].to_string();
+    for (i, line) in open_lines(path)?.enumerate() {
+        let line_str = line.map_err(|e| format!("Error reading {}: {}", path, e))?;
+        let (k, y) = two_columns(&line_str, path, i + 1)?;
 
         result.insert(k, y);
     }
 
-    return result
+    Ok(result)
 }
 
-pub fn read_file_directed(path: &str) -> HashMap<String, HashSet<String>> {
+pub fn read_file_directed(path: &str) -> Result<HashMap<String, HashSet<String>>, String> {
     let mut result: HashMap<String, HashSet<String>> = HashMap::new();
-    let file = File::open(path).expect("Could not open file");
-    let buf_reader = std::io::BufReader::new(file).lines();
     let mut line_number = 0;
-    for line in buf_reader {
-        let line_str = line.expect("Error reading");
-        // println!("{}", line_str);
-        let v: Vec<&str> = line_str.trim().split(',').collect();
+    for line in open_lines(path)? {
+        let line_str = line.map_err(|e| format!("Error reading {}: {}", path, e))?;
         line_number += 1;
         if line_number == 1 {
             continue;
         }
-        let k = v[0].to_string();
-        let y = v[1].to_string();
-        // println!("{}, {}", k, y);
-        
-        if let Some(nodes) = result.get(&k) { // if k exists in hashmap
-            // insert y into the hashset
-            let mut nodes = nodes.clone();
-            nodes.insert(y);
-            result.insert(k, nodes);
-        }
-        else { // if k doesn't exist in hashmap
-            // create a new hashset and insert y into it
-            let mut first: HashSet<String> = HashSet::new();
-            first.insert(y);
-            result.insert(k, first);
-        }
-        
+        let (k, y) = two_columns(&line_str, path, line_number)?;
+
+        // create the set the first time k shows up, then insert y into it
+        result.entry(k).or_default().insert(y);
     }
-    return result
+    Ok(result)
+}
+
+/// The three Elliptic files, parsed into the maps every analysis works on.
+pub struct Dataset {
+    pub edges: HashMap<String, HashSet<String>>,
+    pub timestamps: HashMap<String, usize>,
+    pub labels: HashMap<String, String>,
+}
+
+/// Reads and validates `elliptic_txs_{classes,edgelist,features}.csv` from `data_dir`.
+///
+/// # Returns
+/// The dataset, or a message naming the missing file, malformed line or failed check.
+pub fn load_dataset(data_dir: &str) -> Result<Dataset, String> {
+    println!("Reading.");
+    let mut labels = read_to_hashmap(&format!("{}/elliptic_txs_classes.csv", data_dir))?;
+    labels.remove("txId"); // only file w first row as header
+    println!("Reading..");
+    let edges = read_file_directed(&format!("{}/elliptic_txs_edgelist.csv", data_dir))?;
+    println!("Reading...");
+    let raw_timestamps = read_to_hashmap(&format!("{}/elliptic_txs_features.csv", data_dir))?;
+
+    let timestamps: HashMap<String, usize> = raw_timestamps
+        .into_iter()
+        .filter_map(|(k, v)| v.parse::<usize>().ok().map(|ts| (k, ts)))
+        .collect();
+
+    println!("Finished reading!");
+
+    validate_dataset(&edges, &timestamps, &labels)?;
+
+    Ok(Dataset { edges, timestamps, labels })
 }
 
 pub fn validate_dataset(
     edgelist: &HashMap<String, HashSet<String>>,
     features: &HashMap<String, usize>,
     labels: &HashMap<String, String>,
-) {
+) -> Result<(), String> {
     for (src, targets) in edgelist {
-        assert!(features.contains_key(src), "Missing features for src: {}", src);
+        if !features.contains_key(src) {
+            return Err(format!("Missing features for src: {}", src));
+        }
         for tgt in targets {
-            assert!(features.contains_key(tgt), "Missing features for target: {}", tgt);
+            if !features.contains_key(tgt) {
+                return Err(format!("Missing features for target: {}", tgt));
+            }
         }
     }
 
     for (tx, ts) in features {
-        assert!((1..=49).contains(ts), "Invalid timestamp {} for {}", ts, tx);
+        if !(1..=49).contains(ts) {
+            return Err(format!("Invalid timestamp {} for {}", ts, tx));
+        }
     }
 
     for (tx, label) in labels {
-        assert!(["unknown", "1", "2"].contains(&label.as_str()), "Invalid label {} for {}", label, tx);
+        if !["unknown", "1", "2"].contains(&label.as_str()) {
+            return Err(format!("Invalid label {} for {}", label, tx));
+        }
     }
 
     println!("No Probs!");
+    Ok(())
 }
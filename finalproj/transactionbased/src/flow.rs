@@ -0,0 +1,283 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::dfsstuff;
+use crate::fileread::Dataset;
+
+/// Flow below this is treated as zero.
+const EPSILON: f64 = 1e-9;
+
+/// What is known about the transfers along one edge.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EdgeAttrs {
+    /// Total amount moved along the edge (1 per edge when the data has no amounts).
+    pub amount: f64,
+    /// Earliest timestep of a transfer along the edge.
+    pub timestamp: usize,
+    /// Number of transfers merged into the edge.
+    pub count: usize,
+}
+
+/// Directed graph with attributes per edge: node → successor → attributes.
+pub type WeightedGraph = HashMap<String, HashMap<String, EdgeAttrs>>;
+
+/// A weighted graph with the node timesteps and labels used to pick sources and sinks.
+pub struct WeightedDataset {
+    pub graph: WeightedGraph,
+    pub timestamps: HashMap<String, usize>,
+    pub labels: HashMap<String, String>,
+    /// Whether every edge goes forward in time. The account graph of a `transactions`
+    /// spec aggregates transfers across time, so flow through it can go backwards.
+    pub time_respecting: bool,
+}
+
+/// Adds one transfer, merging it into an existing edge if there is one.
+pub fn add_transfer(graph: &mut WeightedGraph, from: &str, to: &str, amount: f64, timestamp: usize) {
+    let attrs = graph
+        .entry(from.to_string())
+        .or_default()
+        .entry(to.to_string())
+        .or_insert(EdgeAttrs { amount: 0.0, timestamp, count: 0 });
+    attrs.amount += amount;
+    attrs.timestamp = attrs.timestamp.min(timestamp);
+    attrs.count += 1;
+}
+
+/// Unit-amount weighted graph of the time-respecting edges of a dataset without amounts.
+///
+/// With every capacity 1, the max flow is the number of edge-disjoint routes.
+pub fn from_dataset(data: &Dataset) -> WeightedDataset {
+    let mut graph = WeightedGraph::new();
+    for (src, targets) in &data.edges {
+        for tgt in targets {
+            if dfsstuff::time_respecting(&data.timestamps, src, tgt) {
+                add_transfer(&mut graph, src, tgt, 1.0, data.timestamps.get(src).copied().unwrap_or(0));
+            }
+        }
+    }
+    WeightedDataset {
+        graph,
+        timestamps: data.timestamps.clone(),
+        labels: data.labels.clone(),
+        time_respecting: true,
+    }
+}
+
+/// Result of `max_flow`.
+pub struct FlowResult {
+    /// Total amount that can move from the sources to the sinks.
+    pub value: f64,
+    /// Edges of a minimum cut (source side → sink side), with their attributes.
+    pub cut: Vec<(String, String, EdgeAttrs)>,
+    /// Flow passing through each node other than the sources and sinks.
+    pub throughput: HashMap<String, f64>,
+}
+
+/// Residual edge of the flow network.
+struct Arc {
+    to: usize,
+    capacity: f64,
+    /// Index of the reverse arc in `arcs[to]`.
+    rev: usize,
+}
+
+fn add_arc(arcs: &mut [Vec<Arc>], from: usize, to: usize, capacity: f64) {
+    let (rev_from, rev_to) = (arcs[to].len(), arcs[from].len());
+    arcs[from].push(Arc { to, capacity, rev: rev_from });
+    arcs[to].push(Arc { to: from, capacity: 0.0, rev: rev_to });
+}
+
+/// Maximum flow and a minimum cut from a set of sources to a set of sinks (Edmonds–Karp).
+///
+/// Edge capacities are the edge amounts. Sources and sinks are joined to a super
+/// source and a super sink with unlimited capacity; nodes in both sets count as sinks.
+///
+/// # Arguments
+/// * `graph` - Weighted graph; only its edges are used, self-loops are skipped.
+/// * `sources`, `sinks` - Node ids (unknown ids are ignored).
+///
+/// # Returns
+/// The flow value, the cut edges sorted by amount, and per-node throughput.
+pub fn max_flow(graph: &WeightedGraph, sources: &[String], sinks: &[String]) -> FlowResult {
+    let mut index: HashMap<&String, usize> = HashMap::new();
+    let mut names: Vec<&String> = Vec::new();
+    for (src, targets) in graph {
+        for node in std::iter::once(src).chain(targets.keys()) {
+            index.entry(node).or_insert_with(|| {
+                names.push(node);
+                names.len() - 1
+            });
+        }
+    }
+    let (super_source, super_sink) = (names.len(), names.len() + 1);
+    let mut arcs: Vec<Vec<Arc>> = (0..names.len() + 2).map(|_| Vec::new()).collect();
+    // Original edge → (tail, arc index), to read its flow back afterwards.
+    let mut edge_arcs = Vec::new();
+    for (src, targets) in graph {
+        for (tgt, attrs) in targets.iter().filter(|(tgt, _)| *tgt != src) {
+            edge_arcs.push((src, tgt, attrs, index[src], arcs[index[src]].len()));
+            add_arc(&mut arcs, index[src], index[tgt], attrs.amount);
+        }
+    }
+    let sink_set: HashSet<usize> = sinks.iter().filter_map(|s| index.get(s).copied()).collect();
+    for &sink in &sink_set {
+        add_arc(&mut arcs, sink, super_sink, f64::INFINITY);
+    }
+    let source_set: HashSet<usize> = sources
+        .iter()
+        .filter_map(|s| index.get(s).copied())
+        .filter(|s| !sink_set.contains(s))
+        .collect();
+    for &source in &source_set {
+        add_arc(&mut arcs, super_source, source, f64::INFINITY);
+    }
+
+    let mut value = 0.0;
+    loop {
+        // Shortest augmenting path by BFS; `parent[v]` = (node, arc index) it was reached by.
+        let mut parent: Vec<Option<(usize, usize)>> = vec![None; arcs.len()];
+        let mut queue = VecDeque::from([super_source]);
+        let mut seen = vec![false; arcs.len()];
+        seen[super_source] = true;
+        while let Some(u) = queue.pop_front() {
+            if u == super_sink {
+                break;
+            }
+            for (i, arc) in arcs[u].iter().enumerate() {
+                if arc.capacity > EPSILON && !seen[arc.to] {
+                    seen[arc.to] = true;
+                    parent[arc.to] = Some((u, i));
+                    queue.push_back(arc.to);
+                }
+            }
+        }
+        if !seen[super_sink] {
+            break;
+        }
+
+        let mut bottleneck = f64::INFINITY;
+        let mut v = super_sink;
+        while let Some((u, i)) = parent[v] {
+            bottleneck = bottleneck.min(arcs[u][i].capacity);
+            v = u;
+        }
+        let mut v = super_sink;
+        while let Some((u, i)) = parent[v] {
+            arcs[u][i].capacity -= bottleneck;
+            let rev = arcs[u][i].rev;
+            arcs[v][rev].capacity += bottleneck;
+            v = u;
+        }
+        value += bottleneck;
+    }
+
+    // Nodes still reachable in the residual graph form the source side of the cut.
+    let mut source_side = vec![false; arcs.len()];
+    source_side[super_source] = true;
+    let mut queue = VecDeque::from([super_source]);
+    while let Some(u) = queue.pop_front() {
+        for arc in &arcs[u] {
+            if arc.capacity > EPSILON && !source_side[arc.to] {
+                source_side[arc.to] = true;
+                queue.push_back(arc.to);
+            }
+        }
+    }
+
+    let mut cut = Vec::new();
+    let mut throughput = HashMap::new();
+    for (src, tgt, attrs, u, arc) in edge_arcs {
+        let v = arcs[u][arc].to;
+        if source_side[u] && !source_side[v] {
+            cut.push((src.clone(), tgt.clone(), *attrs));
+        }
+        let pushed = attrs.amount - arcs[u][arc].capacity;
+        if pushed > EPSILON && !source_set.contains(&v) && !sink_set.contains(&v) {
+            *throughput.entry(tgt.clone()).or_insert(0.0) += pushed;
+        }
+    }
+    cut.sort_by(|a, b| b.2.amount.total_cmp(&a.2.amount).then_with(|| a.0.cmp(&b.0)));
+
+    FlowResult { value, cut, throughput }
+}
+
+/// How closely a node's outgoing volume matches its incoming volume.
+pub struct Conservation {
+    pub node: String,
+    pub in_volume: f64,
+    pub out_volume: f64,
+    pub in_count: usize,
+    pub out_count: usize,
+    /// `min(in, out) / max(in, out)`; 1 means everything that came in went out.
+    pub ratio: f64,
+    /// In and out volume within the tolerance, with at least two transfers each way.
+    pub balanced: bool,
+}
+
+/// Amount-conservation check for candidate mixers.
+///
+/// A mixer pools many incoming transfers and pays (almost) the same total back
+/// out in many transfers, so its in and out volume should roughly match.
+///
+/// # Arguments
+/// * `tolerance` - Largest allowed `|in - out| / max(in, out)` to count as balanced.
+///
+/// # Returns
+/// One entry per candidate, most balanced first.
+pub fn conservation(graph: &WeightedGraph, candidates: &[String], tolerance: f64) -> Vec<Conservation> {
+    let wanted: HashSet<&String> = candidates.iter().collect();
+    let mut incoming: HashMap<&String, (f64, usize)> = HashMap::new();
+    for targets in graph.values() {
+        for (tgt, attrs) in targets {
+            if wanted.contains(tgt) {
+                let entry = incoming.entry(tgt).or_insert((0.0, 0));
+                entry.0 += attrs.amount;
+                entry.1 += attrs.count;
+            }
+        }
+    }
+
+    let mut report: Vec<Conservation> = candidates
+        .iter()
+        .map(|node| {
+            let (in_volume, in_count) = incoming.get(node).copied().unwrap_or((0.0, 0));
+            let (out_volume, out_count) = graph.get(node).map_or((0.0, 0), |targets| {
+                targets.values().fold((0.0, 0), |(v, c), a| (v + a.amount, c + a.count))
+            });
+            let largest = in_volume.max(out_volume);
+            let ratio = if largest > 0.0 { in_volume.min(out_volume) / largest } else { 0.0 };
+            Conservation {
+                node: node.clone(),
+                in_volume,
+                out_volume,
+                in_count,
+                out_count,
+                ratio,
+                balanced: largest > 0.0 && 1.0 - ratio <= tolerance && in_count >= 2 && out_count >= 2,
+            }
+        })
+        .collect();
+    report.sort_by(|a, b| b.ratio.total_cmp(&a.ratio).then_with(|| a.node.cmp(&b.node)));
+    report
+}
+
+/// Prints the flow value, the heaviest cut edges and the conservation check of the busiest intermediaries.
+pub fn print_flow(result: &FlowResult, balance: &[Conservation], top: usize) {
+    println!("\nMax flow: {:.4} across a cut of {} edges", result.value, result.cut.len());
+    println!("{:<15} {:<15} {:>14} {:>8} {:>10}", "From", "To", "Amount", "Count", "Timestep");
+    for (src, tgt, attrs) in result.cut.iter().take(top) {
+        println!("{:<15} {:<15} {:>14.4} {:>8} {:>10}", src, tgt, attrs.amount, attrs.count, attrs.timestamp);
+    }
+
+    println!("\nAmount conservation of the {} intermediaries carrying the most flow:", balance.len());
+    println!(
+        "{:<15} {:>12} {:>14} {:>14} {:>8} {:>8} {:>8} {:>9}",
+        "Node", "Flow", "In volume", "Out volume", "In", "Out", "Ratio", "Balanced"
+    );
+    for c in balance {
+        println!(
+            "{:<15} {:>12.4} {:>14.4} {:>14.4} {:>8} {:>8} {:>8.3} {:>9}",
+            c.node, result.throughput.get(&c.node).copied().unwrap_or(0.0), c.in_volume, c.out_volume,
+            c.in_count, c.out_count, c.ratio, if c.balanced { "yes" } else { "no" }
+        );
+    }
+}
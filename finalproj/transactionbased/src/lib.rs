@@ -0,0 +1,997 @@
+//! Time-respecting path analysis of the Elliptic Bitcoin transaction graph.
+//!
+//! The binary in `main.rs` is a thin command line wrapper around this crate.
+//! The usual entry points are:
+//!
+//! * [`fileread::load_dataset`] — read the Elliptic CSVs into a [`fileread::Dataset`].
+//! * [`analysis::Analysis`] — builder for the full mixer experiment, returning
+//!   ranked [`analysis::MixerStats`].
+//! * [`dfsstuff`], [`temporal`], [`centrality`], [`community`], [`cycles`] — the
+//!   individual graph queries, usable on any `HashMap<String, HashSet<String>>` graph.
+//! * [`commands::execute`] — one subcommand end to end, as `main.rs` runs it.
+
+pub mod adapter;
+pub mod analysis;
+pub mod annotations;
+pub mod budget;
+pub mod centrality;
+pub mod checkpoint;
+pub mod commands;
+pub mod community;
+pub mod config;
+pub mod cycles;
+pub mod dfsstuff;
+pub mod diff;
+pub mod explain;
+pub mod features;
+pub mod fileread;
+pub mod flow;
+pub mod progress;
+pub mod repl;
+pub mod scoring;
+pub mod server;
+pub mod sketch;
+pub mod stream;
+pub mod structure;
+pub mod synthetic;
+pub mod targets;
+pub mod temporal;
+pub mod walks;
+
+#[cfg(test)]
+use std::collections::{HashMap, HashSet};
+#[cfg(test)]
+use budget::{BudgetTracker, SearchStatus};
+#[cfg(test)]
+use dfsstuff::PathReservoir;
+#[cfg(test)]
+use scoring::{ReuseTally, ScoringMethod};
+
+/// Test fixture: an adjacency list from `(src, tgt)` pairs and timesteps from `(txId, timestep)` pairs.
+#[cfg(test)]
+fn graph(edges: &[(&str, &str)], timestamps: &[(&str, usize)]) -> (HashMap<String, HashSet<String>>, HashMap<String, usize>) {
+    let mut adjacency: HashMap<String, HashSet<String>> = HashMap::new();
+    for (src, tgt) in edges {
+        adjacency.entry(src.to_string()).or_default().insert(tgt.to_string());
+    }
+    (adjacency, timestamps.iter().map(|(n, t)| (n.to_string(), *t)).collect())
+}
+
+#[test]
+fn test_dfs_summarize() {
+    let mut graph: HashMap<String, HashSet<String>> = HashMap::new();
+    graph.insert("A".into(), ["B"].iter().map(|s| s.to_string()).collect());
+    graph.insert("B".into(), ["C", "F"].iter().map(|s| s.to_string()).collect());
+    graph.insert("C".into(), ["F", "D"].iter().map(|s| s.to_string()).collect());
+    graph.insert("D".into(), ["F"].iter().map(|s| s.to_string()).collect());
+    graph.insert("F".into(), HashSet::new());
+
+    let mut ts: HashMap<String, usize> = HashMap::new();
+    ts.insert("A".into(), 1);
+    ts.insert("B".into(), 2);
+    ts.insert("C".into(), 3);
+    ts.insert("D".into(), 4);
+    ts.insert("F".into(), 5);
+
+    let start_nodes = vec!["A".to_string()];
+    let end_nodes = vec!["F".to_string()];
+
+
+    let (stats_test, _) = dfsstuff::summarize_paths_to_targets(&dfsstuff::successors(&graph), &ts, &start_nodes, &end_nodes, 10, &budget::SearchBudget::default(), None);
+    for ((start, end), (count, total_depth)) in &stats_test {
+        let avg_depth = *total_depth as f64 / *count as f64;
+        println!("{} → {}: {} paths, avg depth {:.2}", start, end, count, avg_depth);
+    }
+
+    let result = stats_test.get(&("A".to_string(), "F".to_string()));
+    
+    let (count, total_depth) = result.unwrap();
+    assert_eq!(*count, 3);
+    assert_eq!(*total_depth, 12);
+}
+
+#[test] // same timestamp
+fn test_dfs_summarize_2() {
+    let mut graph: HashMap<String, HashSet<String>> = HashMap::new();
+    graph.insert("A".into(), ["B"].iter().map(|s| s.to_string()).collect());
+    graph.insert("B".into(), ["C", "F"].iter().map(|s| s.to_string()).collect());
+    graph.insert("C".into(), ["F", "D"].iter().map(|s| s.to_string()).collect());
+    graph.insert("D".into(), ["F"].iter().map(|s| s.to_string()).collect());
+    graph.insert("F".into(), HashSet::new());
+
+    let mut ts: HashMap<String, usize> = HashMap::new();
+    for node in ["A", "B", "C", "D", "F"] {
+        ts.insert(node.to_string(), 1);
+    }
+
+    let start_nodes = vec!["A".to_string()];
+    let end_nodes = vec!["F".to_string()];
+
+
+    let (stats_test, _) = dfsstuff::summarize_paths_to_targets(&dfsstuff::successors(&graph), &ts, &start_nodes, &end_nodes, 10, &budget::SearchBudget::default(), None);
+    for ((start, end), (count, total_depth)) in &stats_test {
+        let avg_depth = *total_depth as f64 / *count as f64;
+        println!("{} → {}: {} paths, avg depth {:.2}", start, end, count, avg_depth);
+    }
+    let result = stats_test.get(&("A".to_string(), "F".to_string()));
+
+    let (count, total_depth) = result.unwrap();
+    assert_eq!(*count, 3);
+    assert_eq!(*total_depth, 12);
+}
+#[test] // one start reusing a node many times vs many starts reusing it once
+fn test_distinct_start_score() {
+    let tally = |freq: &[(&str, usize)], starts: &[(&str, &[&str])]| ReuseTally {
+        node_freq: freq.iter().map(|(n, c)| (n.to_string(), *c)).collect(),
+        node_starts: starts
+            .iter()
+            .map(|(n, s)| (n.to_string(), s.iter().map(|x| x.to_string()).collect()))
+            .collect(),
+        reach_count: HashMap::new(),
+        num_starts: 3,
+        paths: Vec::new(),
+    };
+    let illicit = tally(
+        &[("H", 6), ("M", 3)],
+        &[("H", &["A"]), ("M", &["A", "B", "C"])],
+    );
+    let licit = tally(&[], &[]);
+    let degrees = HashMap::new();
+
+    let tallies = [illicit, licit];
+
+    let ratio: HashMap<String, f64> = analysis::compute_mixer_data(&tallies, 0, 1, &degrees, ScoringMethod::Ratio)
+        .into_iter()
+        .map(|(node, _, score)| (node, score))
+        .collect();
+    let distinct: HashMap<String, f64> = analysis::compute_mixer_data(&tallies, 0, 1, &degrees, ScoringMethod::DistinctStarts)
+        .into_iter()
+        .map(|(node, _, score)| (node, score))
+        .collect();
+
+    assert!(ratio["H"] > ratio["M"]);
+    assert!(distinct["M"] > distinct["H"]);
+    assert_eq!(distinct["M"], 3.0);
+}
+
+#[test] // a third group is reported per node but only the contrasted groups are scored
+fn test_unknown_group_contrast() {
+    let tally = |freq: &[(&str, usize)]| ReuseTally {
+        node_freq: freq.iter().map(|(n, c)| (n.to_string(), *c)).collect(),
+        node_starts: HashMap::new(),
+        reach_count: HashMap::new(),
+        num_starts: 1,
+        paths: Vec::new(),
+    };
+    let tallies = [
+        tally(&[("M", 4), ("X", 2)]), // illicit
+        tally(&[("M", 1)]),           // licit
+        tally(&[("M", 3), ("U", 5)]), // unknown
+    ];
+    let degrees = HashMap::new();
+
+    let data: HashMap<String, (Vec<u32>, f64)> = analysis::compute_mixer_data(&tallies, 0, 2, &degrees, ScoringMethod::Ratio)
+        .into_iter()
+        .map(|(node, freqs, score)| (node, (freqs, score)))
+        .collect();
+
+    assert_eq!(data["M"], (vec![4, 1, 3], 1.0));
+    assert_eq!(data["X"], (vec![2, 0, 0], 2.0));
+    assert_eq!(data["U"], (vec![0, 0, 5], 0.0));
+    assert_eq!(data.len(), 3);
+
+    // A contrast naming a group that isn't configured is an error from `run`, not a panic.
+    let config = config::Config { groups: vec!["illicit".parse().unwrap(), "unknown".parse().unwrap()], ..Default::default() };
+    let analysis = analysis::Analysis::new(HashMap::new(), HashMap::new(), HashMap::new()).with_config(config);
+    assert!(analysis.run().err().unwrap().contains("licit"));
+}
+
+#[test] // NaN scores from degenerate tallies sort last without panicking, ties fall back to the node name
+fn test_summarize_scores_nan() {
+    let score_map: HashMap<String, Vec<f64>> = [("B", vec![1.0]), ("A", vec![1.0]), ("N", vec![f64::NAN]), ("C", vec![2.0])]
+        .into_iter()
+        .map(|(n, s)| (n.to_string(), s))
+        .collect();
+    let nodes: Vec<String> = analysis::summarize_scores(score_map, &HashMap::new()).into_iter().map(|s| s.node).collect();
+    assert_eq!(nodes, vec!["C", "A", "B", "N"]);
+}
+
+#[test] // down-weighting demotes negative LogOdds scores too, exclusion strips reach and paths, bad files are errors
+fn test_annotation_actions() {
+    let tally = |freq: &[(&str, usize)], paths: &[&[&str]]| ReuseTally {
+        node_freq: freq.iter().map(|(n, c)| (n.to_string(), *c)).collect(),
+        node_starts: HashMap::new(),
+        reach_count: freq.iter().map(|(n, _)| (n.to_string(), 1)).collect(),
+        num_starts: 2,
+        paths: paths.iter().map(|p| p.iter().map(|n| n.to_string()).collect()).collect(),
+    };
+    // K is mostly licit traffic, so its LogOdds score is negative.
+    let tallies = [tally(&[("M", 4), ("K", 1)], &[]), tally(&[("K", 6), ("M", 1)], &[])];
+    let scores: HashMap<String, f64> = analysis::compute_mixer_data(&tallies, 0, 1, &HashMap::new(), ScoringMethod::LogOdds)
+        .into_iter()
+        .map(|(node, _, score)| (node, score))
+        .collect();
+    assert!(scores["K"] < 0.0 && scores["M"] > 0.0);
+
+    let dir = std::env::temp_dir().join(format!("tb_annotations_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let good = dir.join("good.csv");
+    std::fs::write(&good, "txId,entity_type,note\nK,exchange,hot wallet, main\n").unwrap();
+    let known = annotations::read_annotations(good.to_str().unwrap()).unwrap();
+    assert_eq!(known["K"].note, "hot wallet, main");
+
+    let weighted = |node: &str, action| annotations::weighted_score(node, scores[node], &known, action, 0.5);
+    assert!(weighted("K", annotations::AnnotationAction::DownWeight) < scores["K"]);
+    assert_eq!(weighted("K", annotations::AnnotationAction::DownWeight), 1.5 * scores["K"]);
+    assert_eq!(weighted("K", annotations::AnnotationAction::Tag), scores["K"]);
+    assert_eq!(weighted("M", annotations::AnnotationAction::DownWeight), scores["M"]);
+    // A weight outside 0..=1 would move annotated nodes up instead of down.
+    for bad in ["1.5", "-0.1", "NaN"] {
+        assert!(config::Config::from_args(&["--annotation-weight".to_string(), bad.to_string()]).is_err(), "{}", bad);
+    }
+
+    let mut tally = tally(&[("K", 2), ("M", 1)], &[&["A", "K", "B"], &["A", "M", "B"]]);
+    annotations::apply_to_tally(&mut tally, &known, annotations::AnnotationAction::Exclude);
+    assert!(!tally.node_freq.contains_key("K") && !tally.reach_count.contains_key("K"));
+    assert_eq!(tally.paths, vec![vec!["A".to_string(), "M".to_string(), "B".to_string()]]);
+
+    let bad = dir.join("bad.csv");
+    std::fs::write(&bad, "K,exchange\nlonely\n").unwrap();
+    let err = annotations::read_annotations(bad.to_str().unwrap()).err().unwrap();
+    assert!(err.contains("line 2"), "{}", err);
+    assert!(annotations::read_annotations(dir.join("missing.csv").to_str().unwrap()).is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test] // explain shows each reaching start's shortest path, its licit exposure, and cut-off searches
+fn test_explain_node() {
+    // S reaches T directly through licit A, or through B and C; L only through C; X reaches nothing.
+    let (edges, timestamps) = graph(
+        &[("S", "A"), ("A", "T"), ("S", "B"), ("B", "C"), ("C", "T"), ("L", "C")],
+        &[("S", 1), ("L", 1), ("X", 1), ("A", 2), ("B", 2), ("C", 3), ("T", 4)],
+    );
+    let labels: HashMap<String, String> = [("S", "2"), ("X", "2"), ("A", "1"), ("L", "1")]
+        .iter()
+        .map(|(n, l)| (n.to_string(), l.to_string()))
+        .collect();
+    let starts: Vec<String> = ["S", "L", "X", "T"].iter().map(|n| n.to_string()).collect();
+    let target = "T".to_string();
+
+    let explanation = explain::explain_node(&edges, &timestamps, &labels, &target, &starts, 5, &budget::SearchBudget::default());
+    assert_eq!((explanation.starts_searched, explanation.cut_off_searches), (4, 0));
+    let s = &explanation.reaching_starts[0];
+    assert_eq!((s.start.as_str(), s.num_paths, s.status), ("S", 2, SearchStatus::Finished));
+    assert_eq!(s.example.path, vec!["S", "A", "T"]);
+    assert_eq!(s.example.timesteps, vec![1, 2, 4]);
+    // A is licit, T is not: half of the hops after the start.
+    assert_eq!(s.example.licit_exposure, 0.5);
+    let l = &explanation.reaching_starts[1];
+    assert_eq!((l.start.as_str(), l.label.as_str(), l.example.licit_exposure), ("L", "licit", 0.0));
+    assert_eq!(explanation.reaching_starts.len(), 2);
+    assert_eq!(explanation.label_breakdown, std::collections::BTreeMap::from([("illicit".to_string(), 1), ("licit".to_string(), 1)]));
+    assert_eq!(explanation.degree_over_time, std::collections::BTreeMap::from([(2, (1, 0)), (3, (1, 0))]));
+
+    // One expansion is enough to finish X's search but not S's or L's.
+    let tight = budget::SearchBudget { max_expansions: Some(1), ..Default::default() };
+    let explanation = explain::explain_node(&edges, &timestamps, &labels, &target, &starts, 5, &tight);
+    assert_eq!(explanation.cut_off_searches, 2);
+    assert!(explanation.reaching_starts.is_empty());
+
+    // Through an Analysis the starts are run 0's sample of every group, so a seed pins them down.
+    let config = config::Config { sample_size: 1, ..Default::default() };
+    let analysis = analysis::Analysis::new(edges, timestamps, labels).with_config(config);
+    let starts = analysis.run_starts(3);
+    assert_eq!(starts, analysis.run_starts(3));
+    assert_eq!(starts.iter().map(|s| s.len()).collect::<Vec<_>>(), vec![1; analysis.config().groups.len()]);
+    let reaching = |e: &explain::Explanation| e.reaching_starts.iter().map(|r| r.start.clone()).collect::<Vec<_>>();
+    let (first, second) = (analysis.explain(&target, 3), analysis.explain(&target, 3));
+    assert_eq!(reaching(&first), reaching(&second));
+    assert_eq!(first.starts_searched, starts.concat().len());
+}
+
+#[test] // every target strategy on one fixed graph; sampling ones stay inside the reachable set and follow the seed
+fn test_target_strategies() {
+    use rand::SeedableRng;
+    use targets::TargetStrategy;
+
+    // Both starts fan out through A to B, C and D, which all lead to E.
+    let (edges, timestamps) = graph(
+        &[("S1", "A"), ("S2", "A"), ("A", "B"), ("A", "C"), ("A", "D"), ("B", "E"), ("C", "E"), ("D", "E")],
+        &[("S1", 1), ("S2", 1), ("A", 2), ("B", 3), ("C", 3), ("D", 3), ("E", 4)],
+    );
+    let labels: HashMap<String, String> = [("S1", "2"), ("S2", "2"), ("C", "2"), ("D", "2"), ("B", "1")]
+        .iter()
+        .map(|(n, l)| (n.to_string(), l.to_string()))
+        .collect();
+    let names = |nodes: &[&str]| nodes.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+    let starts = names(&["S1", "S2"]);
+    let ctx = targets::TargetContext {
+        graph: &edges,
+        timestamps: &timestamps,
+        labels: &labels,
+        start_nodes: &starts,
+        reachable: names(&["A", "B", "C", "D", "E", "S1"]),
+    };
+    let select = |strategy, seed| targets::select_targets(strategy, &ctx, &mut rand::rngs::StdRng::seed_from_u64(seed)).targets;
+
+    // Ties go to the smaller txId.
+    assert_eq!(select(TargetStrategy::TopOutDegree(2), 0), names(&["A", "B"]));
+    assert_eq!(select(TargetStrategy::TopInDegree(2), 0), names(&["E", "A"]));
+    assert_eq!(select(TargetStrategy::TopBetweenness(2), 0), names(&["A", "B"]));
+    // S1 is illicit and reachable but is a start, so only C and D qualify.
+    let mut illicit = select(TargetStrategy::Illicit(5), 0);
+    illicit.sort();
+    assert_eq!(illicit, names(&["C", "D"]));
+
+    let uniform = select(TargetStrategy::Uniform(3), 5);
+    assert_eq!(uniform.len(), 3);
+    assert!(uniform.iter().all(|n| ctx.reachable.contains(n)));
+    assert_eq!(uniform, select(TargetStrategy::Uniform(3), 5));
+
+    // A budget of 20 pairs covers 2 starts × 6 targets; 6 pairs leaves 3 targets per start.
+    assert_eq!(select(TargetStrategy::AllReachable(20), 0), ctx.reachable);
+    let sampled = select(TargetStrategy::AllReachable(6), 9);
+    assert_eq!(sampled.len(), 3);
+    assert_eq!(sampled.iter().collect::<HashSet<_>>().len(), 3);
+    assert!(sampled.iter().all(|n| ctx.reachable.contains(n)));
+    assert_eq!(sampled, select(TargetStrategy::AllReachable(6), 9));
+    let ask = |budget| targets::select_targets(TargetStrategy::AllReachable(budget), &ctx, &mut rand::rngs::StdRng::seed_from_u64(0));
+    assert_eq!(ask(6).sampled_from, Some(6));
+    assert_eq!(ask(20).sampled_from, None);
+    // A budget smaller than the number of starts still leaves one target per start.
+    assert_eq!(ask(1).targets.len(), 1);
+}
+
+#[test] // B is on every shortest path out of A, D is only reachable by going back in time
+fn test_betweenness_time_respecting() {
+    let mut graph: HashMap<String, HashSet<String>> = HashMap::new();
+    graph.insert("A".into(), ["B"].iter().map(|s| s.to_string()).collect());
+    graph.insert("B".into(), ["C", "D"].iter().map(|s| s.to_string()).collect());
+    graph.insert("C".into(), ["E"].iter().map(|s| s.to_string()).collect());
+
+    let mut ts: HashMap<String, usize> = HashMap::new();
+    ts.insert("A".into(), 1);
+    ts.insert("B".into(), 2);
+    ts.insert("C".into(), 3);
+    ts.insert("D".into(), 1);
+    ts.insert("E".into(), 4);
+
+    let scores = centrality::betweenness_from(&graph, &ts, &["A".to_string()]);
+    assert_eq!(scores.get("B"), Some(&2.0)); // A → C, A → E
+    assert_eq!(scores.get("C"), Some(&1.0)); // A → E
+    assert_eq!(scores.get("D"), None);
+}
+
+#[test] // path cap stops the search and is reported as a cut-off
+fn test_collect_paths_budget() {
+    let mut graph: HashMap<String, HashSet<String>> = HashMap::new();
+    graph.insert("A".into(), ["B"].iter().map(|s| s.to_string()).collect());
+    graph.insert("B".into(), ["C", "F"].iter().map(|s| s.to_string()).collect());
+    graph.insert("C".into(), ["F", "D"].iter().map(|s| s.to_string()).collect());
+    graph.insert("D".into(), ["F"].iter().map(|s| s.to_string()).collect());
+
+    let ts: HashMap<String, usize> = HashMap::new();
+    let budget = budget::SearchBudget { max_paths: Some(2), ..Default::default() };
+    let mut tracker = BudgetTracker::new(budget);
+    let mut all_paths = Vec::new();
+    dfsstuff::dfs_collect_paths(
+        &graph, &ts,
+        &"A".to_string(), &"F".to_string(),
+        &mut Vec::new(), &mut all_paths, &mut HashSet::new(),
+        1, 10, &mut tracker,
+    );
+
+    assert_eq!(all_paths.len(), 2);
+    assert_eq!(tracker.status(), SearchStatus::CutOff(budget::CutReason::Paths));
+
+    // Exactly as many paths as the cap allows is a finished search.
+    let mut tracker = BudgetTracker::new(budget::SearchBudget { max_paths: Some(3), ..Default::default() });
+    let mut all_paths = Vec::new();
+    dfsstuff::dfs_collect_paths(
+        &graph, &ts,
+        &"A".to_string(), &"F".to_string(),
+        &mut Vec::new(), &mut all_paths, &mut HashSet::new(),
+        1, 10, &mut tracker,
+    );
+    assert_eq!((all_paths.len(), tracker.status()), (3, SearchStatus::Finished));
+
+    // The default estimator's summary DFS obeys --max-paths-per-pair too.
+    let pair = ("A".to_string(), "F".to_string());
+    let (starts, ends) = (vec![pair.0.clone()], vec![pair.1.clone()]);
+    for (cap, count, status) in [(2, 2, SearchStatus::CutOff(budget::CutReason::Paths)), (3, 3, SearchStatus::Finished)] {
+        let budget = budget::SearchBudget { max_paths: Some(cap), ..Default::default() };
+        let (stats, statuses) = dfsstuff::summarize_paths_to_targets(&dfsstuff::successors(&graph), &ts, &starts, &ends, 10, &budget, None);
+        assert_eq!((stats[&pair].0, statuses[&pair]), (count, status));
+    }
+    // The run's default budget caps paths per pair at 100.
+    assert_eq!(config::Config::default().budget.max_paths, Some(100));
+
+    assert!(config::Config::from_args(&["--time-limit".to_string(), "-1".to_string()]).is_err());
+    assert!(config::Config::from_args(&["--time-limit".to_string(), "nan".to_string()]).is_err());
+}
+
+#[test] // reservoir keeps at most its capacity but counts every path
+fn test_path_reservoir() {
+    let mut reservoir = PathReservoir::new(10);
+    for i in 0..1000 {
+        reservoir.offer(&["S".to_string(), i.to_string(), "T".to_string()]);
+    }
+    assert_eq!(reservoir.total, 1000);
+    assert_eq!(reservoir.paths.len(), 10);
+    assert!(reservoir.paths.iter().all(|p| p.len() == 3 && p[0] == "S"));
+}
+
+#[test] // walks stop where time would go backwards, so C is never reached
+fn test_random_walk_time_respecting() {
+    let mut graph: HashMap<String, HashSet<String>> = HashMap::new();
+    graph.insert("A".into(), ["B"].iter().map(|s| s.to_string()).collect());
+    graph.insert("B".into(), ["C"].iter().map(|s| s.to_string()).collect());
+
+    let mut ts: HashMap<String, usize> = HashMap::new();
+    ts.insert("A".into(), 1);
+    ts.insert("B".into(), 3);
+    ts.insert("C".into(), 2);
+
+    use rand::SeedableRng;
+    let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+    let estimate = walks::estimate_reuse(&graph, &ts, &["A".to_string()], 20, 10, &mut rng);
+    assert_eq!(estimate.num_walks, 20);
+    assert!(estimate.tally.node_freq.is_empty()); // A → B has no intermediary
+    assert_eq!(estimate.tally.reach_count.get("B"), Some(&1));
+    assert_eq!(estimate.tally.reach_count.get("C"), None);
+}
+
+#[test] // shortcut D → F goes back in time, so the min-hop path takes the long way
+fn test_shortest_temporal_path() {
+    let mut graph: HashMap<String, HashSet<String>> = HashMap::new();
+    graph.insert("A".into(), ["B", "D"].iter().map(|s| s.to_string()).collect());
+    graph.insert("B".into(), ["C"].iter().map(|s| s.to_string()).collect());
+    graph.insert("C".into(), ["F"].iter().map(|s| s.to_string()).collect());
+    graph.insert("D".into(), ["F"].iter().map(|s| s.to_string()).collect());
+
+    let mut ts: HashMap<String, usize> = HashMap::new();
+    ts.insert("A".into(), 1);
+    ts.insert("B".into(), 2);
+    ts.insert("C".into(), 3);
+    ts.insert("D".into(), 5);
+    ts.insert("F".into(), 4);
+
+    let path = temporal::shortest_temporal_path(&graph, &ts, &"A".to_string(), &"F".to_string());
+    assert_eq!(path, Some(vec!["A".into(), "B".into(), "C".into(), "F".into()]));
+
+    let arrivals = temporal::earliest_arrival(&graph, &ts, &["A".to_string()]);
+    assert_eq!(arrivals["F"].timestep, 4);
+    assert_eq!(arrivals["F"].hops, 3);
+}
+
+#[test] // two triangles joined by one edge split into two communities
+fn test_louvain_two_triangles() {
+    let mut graph: HashMap<String, HashSet<String>> = HashMap::new();
+    graph.insert("A".into(), ["B", "C"].iter().map(|s| s.to_string()).collect());
+    graph.insert("B".into(), ["C"].iter().map(|s| s.to_string()).collect());
+    graph.insert("C".into(), ["D"].iter().map(|s| s.to_string()).collect());
+    graph.insert("D".into(), ["E", "F"].iter().map(|s| s.to_string()).collect());
+    graph.insert("E".into(), ["F"].iter().map(|s| s.to_string()).collect());
+
+    let (communities, modularity) = community::louvain(&graph);
+    assert_eq!(communities["A"], communities["B"]);
+    assert_eq!(communities["A"], communities["C"]);
+    assert_eq!(communities["D"], communities["E"]);
+    assert_eq!(communities["D"], communities["F"]);
+    assert_ne!(communities["A"], communities["D"]);
+    assert!((modularity - 5.0 / 14.0).abs() < 1e-9);
+}
+
+#[test] // planted mixers in a seeded synthetic graph end up at the top of the ranking
+fn test_synthetic_mixers_recovered() {
+    let graph = synthetic::generate(&synthetic::SyntheticConfig {
+        nodes: 1000,
+        timesteps: 3,
+        mixers: 2,
+        mixer_share: 0.8,
+        homophily: 0.97,
+        ..Default::default()
+    });
+    assert!(graph.edges.iter().all(|(src, targets)| {
+        targets.iter().all(|tgt| dfsstuff::time_respecting(&graph.timestamps, src, tgt))
+    }));
+
+    let group = |label: &str| -> Vec<String> {
+        graph.labels.iter().filter(|(_, l)| *l == label).map(|(n, _)| n.clone()).collect()
+    };
+    use rand::SeedableRng;
+    let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+    let tallies = [
+        walks::estimate_reuse(&graph.edges, &graph.timestamps, &group("2"), 50, 10, &mut rng).tally,
+        walks::estimate_reuse(&graph.edges, &graph.timestamps, &group("1"), 50, 10, &mut rng).tally,
+    ];
+    let mut ranking = analysis::compute_mixer_data(&tallies, 0, 1, &scoring::total_degrees(&graph.edges), ScoringMethod::LogOdds);
+    ranking.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap());
+    // Rare nodes reached by one illicit walk and no licit one also score high,
+    // so only ask for the top 50 of the ~700 scored nodes.
+    for mixer in graph.mixers.keys() {
+        let rank = ranking.iter().position(|(node, _, _)| node == mixer);
+        assert!(rank.is_some_and(|r| r < 50), "planted mixer {} ranked {:?}", mixer, rank);
+    }
+}
+
+#[test] // a saved run reads back identically, and a changed config refuses to resume
+fn test_checkpoint_round_trip() {
+    let dir = std::env::temp_dir().join(format!("tb_checkpoint_{}", std::process::id()));
+    let dir = dir.to_str().unwrap();
+    let _ = std::fs::remove_dir_all(dir);
+    let groups: Vec<scoring::LabelGroup> = vec!["illicit".parse().unwrap(), "licit".parse().unwrap()];
+    let settings = vec![("score", "ratio".to_string())];
+
+    let (checkpoint, seed) = checkpoint::Checkpoint::open(dir, &settings, Some(7)).unwrap();
+    assert_eq!(seed, 7);
+    assert!(checkpoint.load_run(0, &groups).unwrap().is_none());
+    checkpoint
+        .save_run(0, &groups, &checkpoint::RunRecord {
+            node_freq: vec![HashMap::from([("M".to_string(), 3)]), HashMap::new()],
+            scores: HashMap::from([("M".to_string(), 0.1 + 0.2)]),
+            bridged: HashMap::from([("M".to_string(), HashSet::from([1, 4])), ("E".to_string(), HashSet::new())]),
+        })
+        .unwrap();
+
+    let (checkpoint, seed) = checkpoint::Checkpoint::open(dir, &settings, None).unwrap();
+    assert_eq!(seed, 7);
+    let run = checkpoint.load_run(0, &groups).unwrap().unwrap();
+    assert_eq!(run.node_freq[0]["M"], 3);
+    assert!(run.node_freq[1].is_empty());
+    assert_eq!(run.scores["M"], 0.1 + 0.2);
+    assert_eq!(run.bridged["M"], HashSet::from([1, 4]));
+    // A node bridging no communities is written as an empty id list and still reads back.
+    assert!(run.bridged["E"].is_empty());
+
+    // A corrupt finished run is an error for the caller to redo, not a panic.
+    std::fs::write(format!("{}/run_000/scores.csv", dir), "txId,score\nM,not-a-number\n").unwrap();
+    assert!(checkpoint.load_run(0, &groups).err().unwrap().contains("scores.csv"));
+
+    assert!(checkpoint::Checkpoint::open(dir, &[("score", "tfidf".to_string())], None).is_err());
+    std::fs::remove_dir_all(dir).unwrap();
+
+    // The data source is part of the key, so another dataset can't resume these runs.
+    let other = config::Config { data_dir: "/some/other/data".to_string(), ..Default::default() };
+    assert!(other.settings().contains(&("data", "/some/other/data".to_string())));
+    assert_ne!(other.settings(), config::Config::default().settings());
+}
+
+#[test] // one seed gives the same ranking twice, even from separately built (differently hashed) graphs
+fn test_seeded_run_reproducible() {
+    let synthetic = synthetic::SyntheticConfig { nodes: 300, timesteps: 3, mixers: 1, ..Default::default() };
+    for config in [
+        config::Config {
+            estimator: config::Estimator::Dfs,
+            targets: "uniform:10".parse().unwrap(),
+            max_depth: 5,
+            collect_depth: 5,
+            reservoir_size: 3,
+            ..Default::default()
+        },
+        config::Config { estimator: config::Estimator::Walk, walks_per_start: 5, ..Default::default() },
+    ] {
+        let config = config::Config { num_runs: 2, sample_size: 15, seed: Some(11), ..config };
+        let ranking = || {
+            let graph = synthetic::generate(&synthetic);
+            let result = analysis::Analysis::new(graph.edges, graph.timestamps, graph.labels)
+                .with_config(config.clone())
+                .run()
+                .unwrap();
+            result.stats.into_iter().map(|s| (s.node, s.scores, s.reuse)).collect::<Vec<_>>()
+        };
+        let first = ranking();
+        assert!(!first.is_empty());
+        assert_eq!(first, ranking());
+    }
+}
+
+#[test] // the query server answers over localhost with the same results as dfsstuff/cycles
+fn test_query_server() {
+    use std::io::{Read, Write};
+
+    let (edges, timestamps) = graph(&[("A", "B"), ("B", "C"), ("C", "A"), ("C", "D")], &[("A", 1), ("B", 2), ("C", 3), ("D", 3)]);
+    let labels = HashMap::from([("A".to_string(), "2".to_string())]);
+    // A reach search that runs out of budget keeps what it found so far and says so.
+    let mut tracker = BudgetTracker::new(budget::SearchBudget { max_expansions: Some(1), ..Default::default() });
+    assert_eq!(dfsstuff::reachable_within(&edges, &timestamps, &"A".to_string(), 3, &mut tracker), vec!["B".to_string()]);
+    assert!(tracker.exhausted());
+    let mut state = server::ServerState::new(analysis::Analysis::new(edges, timestamps, labels));
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let handle = std::thread::spawn(move || server::serve(listener, &mut state, Some(9)).unwrap());
+
+    let get = |target: &str| {
+        let mut stream = std::net::TcpStream::connect(address).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", target).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    };
+
+    let neighbors = get("/neighbors?tx=C");
+    assert!(neighbors.starts_with("HTTP/1.1 200"));
+    assert!(neighbors.ends_with(r#"{"tx":"C","timestep":3,"label":"unknown","out":["A","D"],"in":["B"]}"#));
+    // C → A goes back in time, so A only reaches B, C and D.
+    assert!(get("/reach?tx=A&depth=2").ends_with(r#""count":2,"nodes":["B","C"]}"#));
+    assert!(get("/cycles?tx=A&k=3").ends_with(r#""count":1,"cycles":[["A","B","C"]]}"#));
+    assert!(get("/neighbors?tx=Z").starts_with("HTTP/1.1 404"));
+    // Depth and k are capped, so one request can't tie up the server.
+    assert!(get(&format!("/reach?tx=A&depth={}", usize::MAX)).starts_with("HTTP/1.1 400"));
+    assert!(get("/cycles?tx=A&k=1000").starts_with("HTTP/1.1 400"));
+    assert!(get("/reach?tx=A&depth=50").contains(r#""status":"finished","count":3"#));
+    // A client that connects and sends nothing times out instead of blocking the next one.
+    let idle = std::net::TcpStream::connect(address).unwrap();
+    // Query values are percent-decoded: %43 is C.
+    assert!(get("/neighbors?tx=%43").contains(r#""tx":"C""#));
+    drop(idle);
+    handle.join().unwrap();
+}
+
+#[test] // REPL commands keep the graph loaded and $n pivots to a txId from the previous listing
+fn test_repl_pivot() {
+    let (edges, timestamps) = graph(&[("A", "B"), ("B", "C"), ("C", "A"), ("C", "D")], &[("A", 1), ("B", 2), ("C", 3), ("D", 3)]);
+    let labels = HashMap::from([("A".to_string(), "2".to_string())]);
+
+    let mut shell = repl::Repl::new(config::Config::default());
+    assert!(shell.execute("info A").is_err());
+    shell.set_dataset(fileread::Dataset { edges, timestamps, labels }).unwrap();
+
+    let info = shell.execute("info A").unwrap();
+    assert!(info.starts_with("A: timestep 1, illicit"));
+    assert!(info.contains("$1    B"));
+    // $1 is B, which reaches C and then D (C → A goes back in time).
+    let reach = shell.execute("reach $1 2").unwrap();
+    assert!(reach.starts_with("2 txs reachable from B within 2 hops"));
+    assert!(reach.contains("$2    D"));
+    assert!(shell.execute("info $2").unwrap().starts_with("D: timestep 3"));
+    assert!(shell.execute("cycles A 3").unwrap().contains("A → B → C → A"));
+    assert!(shell.execute("sample illicit 10").unwrap().starts_with("1 of 1 illicit txs"));
+    // With --seed, a new session samples the same txIds.
+    let names: Vec<String> = (0..20).map(|i| format!("U{}", i)).collect();
+    let seeded = || {
+        let (edges, timestamps) = graph(&[], &names.iter().map(|n| (n.as_str(), 1)).collect::<Vec<_>>());
+        let labels = names.iter().map(|n| (n.clone(), "unknown".to_string())).collect();
+        let mut shell = repl::Repl::new(config::Config { seed: Some(7), ..config::Config::default() });
+        shell.set_dataset(fileread::Dataset { edges, timestamps, labels }).unwrap();
+        shell.execute("sample unknown 5").unwrap()
+    };
+    assert_eq!(seeded(), seeded());
+    assert!(shell.execute("info $9").is_err());
+
+    // A bad folder is reported and the session keeps the loaded graph.
+    let dir = std::env::temp_dir().join(format!("tb_repl_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let missing = shell.execute(&format!("load {}", dir.display())).unwrap_err();
+    assert!(missing.contains("elliptic_txs_classes.csv"));
+    std::fs::write(dir.join("elliptic_txs_classes.csv"), "txId,class\nA,2\n").unwrap();
+    std::fs::write(dir.join("elliptic_txs_edgelist.csv"), "txId1,txId2\nA\n").unwrap();
+    let malformed = shell.execute(&format!("load {}", dir.display())).unwrap_err();
+    assert!(malformed.contains("elliptic_txs_edgelist.csv line 2"));
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(shell.execute("info A").unwrap().starts_with("A: timestep 1"));
+}
+
+#[test] // an IBM AML-style transfer list becomes a time-respecting transaction graph
+fn test_transactions_spec() {
+    let dir = std::env::temp_dir().join(format!("tb_adapter_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("trans.csv"),
+        "Timestamp;From;To;Is Laundering\n\
+         2022/09/01 00:20;A1;A2;1\n\
+         2022/09/01 08:00;A2;A3;1\n\
+         2022/09/02 00:10;A3;A4;0\n\
+         2022/08/31 23:00;A3;A5;0\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("aml.spec"),
+        "layout = transactions  # one row per transfer\n\
+         delimiter = ;\n\
+         transactions = trans.csv\n\
+         transactions.from = From\n\
+         transactions.to = To\n\
+         transactions.time = Timestamp\n\
+         transactions.label = Is Laundering\n\
+         time_type = datetime\n\
+         label_map = 1:illicit,0:licit\n",
+    )
+    .unwrap();
+
+    let spec = adapter::DatasetSpec::read(dir.join("aml.spec").to_str().unwrap()).unwrap();
+    let data = adapter::load(&spec).unwrap();
+    // Row 0 pays A2, which sends row 1; row 1 pays A3, whose only later transfer is row 2.
+    assert_eq!(data.edges["0"], HashSet::from(["1".to_string()]));
+    assert_eq!(data.edges["1"], HashSet::from(["2".to_string()]));
+    assert_eq!(data.edges.len(), 2);
+    assert_eq!((data.timestamps["3"], data.timestamps["0"], data.timestamps["2"]), (1, 1, 2));
+    assert_eq!((data.labels["0"].as_str(), data.labels["2"].as_str()), ("2", "1"));
+    assert_eq!(adapter::parse_datetime("1970-01-02T00:00:01Z"), Some(86401));
+
+    // A wrong time_type or a short row names the line instead of quietly dropping rows.
+    let spec_text = std::fs::read_to_string(dir.join("aml.spec")).unwrap();
+    std::fs::write(dir.join("step.spec"), spec_text.replace("time_type = datetime", "time_type = step")).unwrap();
+    let spec = adapter::DatasetSpec::read(dir.join("step.spec").to_str().unwrap()).unwrap();
+    let err = adapter::load(&spec).err().unwrap();
+    assert!(err.contains("trans.csv line 2") && err.contains("2022/09/01 00:20"), "{}", err);
+    std::fs::write(dir.join("trans.csv"), "Timestamp;From;To;Is Laundering\n2022/09/01 00:20;A1;A2;1\n2022/09/01 08:00,A2,A3,1\n").unwrap();
+    let spec = adapter::DatasetSpec::read(dir.join("aml.spec").to_str().unwrap()).unwrap();
+    assert!(adapter::load_weighted(&spec).err().unwrap().contains("trans.csv line 3: expected at least 4 columns, found 1"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test] // max flow is limited by the mixer's outflow, and the mixer balances in vs out volume
+fn test_max_flow_and_conservation() {
+    let mut graph = flow::WeightedGraph::new();
+    for (from, to, amount) in [("S1", "M", 5.0), ("S2", "M", 5.0), ("M", "T1", 3.0), ("M", "T2", 7.0), ("S2", "T2", 2.0)] {
+        flow::add_transfer(&mut graph, from, to, amount, 1);
+    }
+    flow::add_transfer(&mut graph, "S1", "M", 1.0, 2);
+    assert_eq!(graph["S1"]["M"].amount, 6.0);
+    assert_eq!(graph["S1"]["M"].count, 2);
+
+    let sources = vec!["S1".to_string(), "S2".to_string()];
+    let sinks = vec!["T1".to_string(), "T2".to_string()];
+    let result = flow::max_flow(&graph, &sources, &sinks);
+    assert!((result.value - 12.0).abs() < 1e-9);
+    let mut cut: Vec<(String, String)> = result.cut.iter().map(|(a, b, _)| (a.clone(), b.clone())).collect();
+    cut.sort();
+    let expected = [("M", "T1"), ("M", "T2"), ("S2", "T2")].map(|(a, b)| (a.to_string(), b.to_string()));
+    assert_eq!(cut, expected);
+    assert!((result.throughput["M"] - 10.0).abs() < 1e-9);
+
+    let balance = flow::conservation(&graph, &["M".to_string(), "S2".to_string()], 0.1);
+    assert_eq!(balance[0].node, "M");
+    // 11 in, 10 out: within 10%.
+    assert!(balance[0].balanced && (balance[0].ratio - 10.0 / 11.0).abs() < 1e-9);
+    assert!(!balance[1].balanced);
+}
+
+#[test] // core numbers, reciprocity and label mixing on a triangle with a pendant node
+fn test_structure_report() {
+    let (edges, timestamps) = graph(
+        &[("A", "B"), ("B", "A"), ("B", "C"), ("C", "A"), ("C", "D")],
+        &[("A", 1), ("B", 1), ("C", 2), ("D", 3), ("E", 3)],
+    );
+    let labels: HashMap<String, String> = [("A", "2"), ("B", "2"), ("C", "1"), ("D", "1")]
+        .iter()
+        .map(|(n, l)| (n.to_string(), l.to_string()))
+        .collect();
+    let data = fileread::Dataset { edges, timestamps, labels };
+
+    let report = structure::build_report(&data);
+    assert_eq!((report.nodes, report.edges), (5, 5));
+    assert_eq!(report.core_numbers["A"], 2);
+    assert_eq!(report.core_numbers["C"], 2);
+    assert_eq!(report.core_numbers["D"], 1);
+    assert_eq!(report.core_numbers["E"], 0);
+    assert!((report.reciprocity - 0.4).abs() < 1e-9);
+    assert_eq!(report.per_timestep[&1], (2, 3, 2));
+    assert_eq!(report.degree_distributions[&("illicit".to_string(), "in")], std::collections::BTreeMap::from([(1, 1), (2, 1)]));
+    // 3 of 5 edges join equal labels; by chance it would be 0.6 * 0.6 + 0.4 * 0.4 = 0.52.
+    assert!((report.homophily - 0.6).abs() < 1e-9);
+    assert!((report.assortativity - (0.6 - 0.52) / 0.48).abs() < 1e-9);
+
+    let window = structure::restrict_to_window(&data, 2, 3);
+    assert_eq!(window.edges.len(), 1);
+    assert_eq!(window.timestamps.len(), 3);
+}
+
+#[test] // graph features hide test-period labels, and both classifiers separate an easy temporal split
+fn test_graph_features_and_classifier() {
+    let (edges, timestamps) = graph(
+        &[("A", "B"), ("B", "A"), ("B", "C"), ("C", "A"), ("C", "D")],
+        &[("A", 1), ("B", 1), ("C", 2), ("D", 3)],
+    );
+    let labels: HashMap<String, String> = [("A", "2"), ("B", "2"), ("C", "1"), ("D", "1")]
+        .iter()
+        .map(|(n, l)| (n.to_string(), l.to_string()))
+        .collect();
+    let data = fileread::Dataset { edges, timestamps, labels };
+
+    let visible = features::visible_labels(&data, 2);
+    assert!(!visible.contains_key("D"));
+    let config = config::Config { seed: Some(1), ..Default::default() };
+    let table = features::graph_features(&data, &visible, &config);
+    let column = |node: &str, name: &str| table.rows[node][table.names.iter().position(|n| n == name).unwrap()];
+    assert_eq!((column("A", "in_degree"), column("A", "out_degree")), (2.0, 1.0));
+    // A → B → C → D; C → A goes back in time.
+    assert_eq!((column("A", "reach_1"), column("A", "reach_2"), column("A", "reach_3")), (1.0, 2.0, 3.0));
+    assert!(column("A", "cycles_3") > 0.0);
+    assert_eq!(column("D", "cycles_3"), 0.0);
+    // C's neighbours are A, B (illicit) and D, whose label is in the test period.
+    assert!((column("C", "nbr_illicit") - 2.0 / 3.0).abs() < 1e-9);
+    assert!((column("C", "nbr_unknown") - 1.0 / 3.0).abs() < 1e-9);
+
+    // One informative feature and one constant one; illicit iff the first is above 5.
+    let mut rows = HashMap::new();
+    let mut split = fileread::Dataset { edges: HashMap::new(), timestamps: HashMap::new(), labels: HashMap::new() };
+    for i in 0..40 {
+        let node = i.to_string();
+        rows.insert(node.clone(), vec![(i % 10) as f64, 1.0]);
+        split.timestamps.insert(node.clone(), if i < 30 { 1 } else { 2 });
+        split.labels.insert(node, if i % 10 > 5 { "2" } else { "1" }.to_string());
+    }
+    let raw = features::FeatureTable { names: vec!["x".to_string(), "c".to_string()], rows };
+    for model in [features::Model::LogReg, features::Model::Gbt] {
+        let evaluation = features::temporal_split_eval(&raw, &split, 1, model).unwrap();
+        assert_eq!((evaluation.tp, evaluation.fp, evaluation.tn, evaluation.fn_), (4, 0, 6, 0));
+        assert_eq!(evaluation.f1, 1.0);
+        assert_eq!(evaluation.auc, 1.0);
+    }
+    assert!(features::temporal_split_eval(&raw, &split, 2, features::Model::LogReg).is_err());
+}
+
+#[test] // appended batches extend reach and reuse, and incremental SCCs match a full recompute
+fn test_stream_updates() {
+    let node = |n: &str| n.to_string();
+    let (edges, timestamps) = graph(&[("A", "B")], &[("A", 1), ("B", 1), ("X", 1)]);
+    let labels = HashMap::from([(node("A"), node("2")), (node("X"), node("1"))]);
+    let config = config::Config { seed: Some(1), walks_per_start: 5, ..Default::default() };
+    let bad = config::Config { groups: vec!["licit".parse().unwrap()], ..config.clone() };
+    let (no_edges, no_timestamps) = graph(&[], &[]);
+    assert!(stream::StreamState::new(fileread::Dataset { edges: no_edges, timestamps: no_timestamps, labels: HashMap::new() }, bad).is_err());
+    let mut state = stream::StreamState::new(fileread::Dataset { edges, timestamps, labels }, config).unwrap();
+    assert_eq!(state.score("B").unwrap().reached_by, vec![1, 0]);
+    assert!(state.tallies()[0].node_freq.is_empty());
+
+    // C arrives at timestep 2; C → A closes a cycle but goes back in time.
+    let batch = stream::Batch {
+        timestamps: HashMap::from([(node("C"), 2)]),
+        edges: vec![(node("B"), node("C")), (node("C"), node("A"))],
+        labels: HashMap::from([(node("C"), node("unknown"))]),
+    };
+    let summary = state.apply(&batch);
+    assert_eq!(summary.new_nodes, vec![node("C")]);
+    assert_eq!((summary.new_edges, summary.merged_components, summary.refreshed_starts), (2, 3, 1));
+    let c = state.score("C").unwrap();
+    assert_eq!((c.reached_by.clone(), c.scc_size), (vec![1, 0], 3));
+    // Every walk from A is now A → B → C.
+    assert_eq!(state.tallies()[0].node_freq.get("B"), Some(&5));
+    assert_eq!(state.tallies()[0].reach_count.get("C"), Some(&1));
+
+    // A → C reaches nothing new, but A's walks can now skip B, so they are redone (not added to).
+    let batch = stream::Batch { timestamps: HashMap::new(), edges: vec![(node("A"), node("C"))], labels: HashMap::new() };
+    let summary = state.apply(&batch);
+    assert_eq!(summary.refreshed_starts, 1);
+    // Only walks through B are kept as paths; the old five A → B → C walks are gone.
+    let paths = &state.tallies()[0].paths;
+    assert!(paths.len() < 5 && paths.iter().all(|p| p == &vec![node("A"), node("B"), node("C")]));
+    assert_eq!(state.tallies()[0].node_freq.get("B"), Some(&paths.len()));
+
+    // A turns licit: it leaves the illicit group entirely and fills the licit one.
+    let batch = stream::Batch { timestamps: HashMap::new(), edges: Vec::new(), labels: HashMap::from([(node("A"), node("1"))]) };
+    let summary = state.apply(&batch);
+    assert_eq!((summary.removed_starts, summary.new_starts), (1, 1));
+    assert!(state.starts()[0].is_empty());
+    assert!(state.tallies()[0].reach_count.is_empty() && state.tallies()[0].paths.is_empty());
+    assert_eq!(state.score("C").unwrap().reached_by, vec![0, 1]);
+
+    // Random insertions: the index must agree with Tarjan on the final graph.
+    use rand::{Rng, SeedableRng};
+    let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+    let mut graph: HashMap<String, HashSet<String>> = HashMap::new();
+    let mut index = stream::SccIndex::new(&graph);
+    for _ in 0..150 {
+        let (a, b) = (rng.random_range(0..50).to_string(), rng.random_range(0..50).to_string());
+        graph.entry(a.clone()).or_default().insert(b.clone());
+        index.insert_edge(&a, &b);
+        assert!(index.is_ordered());
+        let mut expected: Vec<Vec<String>> = cycles::strongly_connected_components(&graph).into_iter().filter(|c| c.len() > 1).collect();
+        expected.sort();
+        let mut found: Vec<Vec<String>> = index.nontrivial().into_iter().cloned().collect();
+        found.sort();
+        assert_eq!(found, expected);
+    }
+}
+
+#[test] // sketches are exact for small sets, close for large ones, and estimate group overlap
+fn test_reach_sketches() {
+    // A ⇄ B in timestep 1, B → C → D later, D → A back in time, E isolated.
+    let (edges, timestamps) = graph(
+        &[("A", "B"), ("B", "A"), ("B", "C"), ("C", "D"), ("D", "A")],
+        &[("A", 1), ("B", 1), ("C", 2), ("D", 3), ("E", 3)],
+    );
+    let sketches = sketch::ReachSketches::build(&edges, &timestamps, 64);
+    for node in timestamps.keys() {
+        let forward = sketch::exact_forward(&edges, &timestamps, std::slice::from_ref(node));
+        let backward = timestamps
+            .keys()
+            .filter(|n| sketch::exact_forward(&edges, &timestamps, std::slice::from_ref(*n)).contains(node))
+            .count();
+        assert_eq!(sketches.forward_size(node), (forward.len() - 1) as f64);
+        assert_eq!(sketches.backward_size(node), (backward - 1) as f64);
+    }
+    assert_eq!((sketches.forward_size("A"), sketches.backward_size("D")), (3.0, 3.0));
+
+    // A chain of 3000 nodes: 0 reaches all of it, 1000 the last 2000.
+    let mut chain: HashMap<String, HashSet<String>> = HashMap::new();
+    for i in 0..2999 {
+        chain.entry(i.to_string()).or_default().insert((i + 1).to_string());
+    }
+    let ts: HashMap<String, usize> = (0..3000).map(|i| (i.to_string(), 1)).collect();
+    let sketches = sketch::ReachSketches::build(&chain, &ts, 64);
+    let estimate = sketches.forward_size("1000");
+    assert!((estimate - 1999.0).abs() < 0.3 * 1999.0, "estimate {}", estimate);
+    let a = sketches.union_forward(&["0".to_string()]);
+    let b = sketches.union_forward(&["1000".to_string()]);
+    let overlap = sketch::overlap(&a, &b, 64);
+    // Jaccard = 2000 / 3000.
+    assert!((overlap.jaccard - 2.0 / 3.0).abs() < 0.2, "jaccard {}", overlap.jaccard);
+}
+
+#[test] // diff of two toy versions: node/edge/label changes, reach and SCC changes, and ranking shift
+fn test_graph_diff() {
+    let labels = |pairs: &[(&str, &str)]| pairs.iter().map(|(n, l)| (n.to_string(), l.to_string())).collect();
+    let (edges, timestamps) = graph(&[("A", "B"), ("B", "C"), ("C", "A"), ("C", "D")], &[("A", 1), ("B", 1), ("C", 1), ("D", 1)]);
+    let old = fileread::Dataset { edges, timestamps, labels: labels(&[("A", "2"), ("B", "1")]) };
+    // C → A is gone (breaking the cycle), D → E is new, and B turns illicit.
+    let (edges, timestamps) = graph(&[("A", "B"), ("B", "C"), ("C", "D"), ("D", "E")], &[("A", 1), ("B", 1), ("C", 1), ("D", 1), ("E", 1)]);
+    let new = fileread::Dataset { edges, timestamps, labels: labels(&[("A", "2"), ("B", "2")]) };
+
+    let changes = diff::diff_graphs(&old, &new, 64);
+    assert_eq!((changes.added_nodes.clone(), changes.removed_nodes.len()), (vec!["E".to_string()], 0));
+    assert_eq!(changes.added_edges, vec![("D".to_string(), "E".to_string())]);
+    assert_eq!(changes.removed_edges, vec![("C".to_string(), "A".to_string())]);
+    assert_eq!(changes.label_changes, vec![("B".to_string(), "licit".to_string(), "illicit".to_string())]);
+    // A now reaches E as well; C no longer reaches A or B, but reaches E.
+    let reach: HashMap<&str, (f64, f64)> = changes.reach_changes.iter().map(|(n, a, b)| (n.as_str(), (*a, *b))).collect();
+    assert_eq!((reach["A"], reach["C"], reach["D"]), ((3.0, 4.0), (3.0, 2.0), (0.0, 1.0)));
+    assert_eq!(changes.scc_changes.len(), 3);
+    assert_eq!((changes.scc_changes[0].1, changes.scc_changes[0].2), (3, 1));
+    assert_eq!("1:4".parse::<diff::Version>(), Ok(diff::Version::Window(1, 4)));
+    assert_eq!("../data".parse::<diff::Version>(), Ok(diff::Version::Source("../data".to_string())));
+
+    let ranking = |nodes: &[&str]| -> Vec<analysis::MixerStats> {
+        nodes
+            .iter()
+            .map(|n| analysis::MixerStats {
+                node: n.to_string(), scores: Vec::new(), mean: 0.0, stddev: 0.0, ci_low: 0.0, ci_high: 0.0,
+                entity: None, community: None, communities_bridged: None, reuse: Vec::new(),
+            })
+            .collect()
+    };
+    let shift = diff::rank_shift(&ranking(&["A", "B", "C", "D"]), &ranking(&["B", "A", "C", "E"]), 2);
+    assert_eq!(shift.common, 3);
+    // Common order A B C vs B A C: d² = 1 + 1, rho = 1 - 6 * 2 / (3 * 8).
+    assert!((shift.spearman.unwrap() - 0.5).abs() < 1e-9);
+    assert!(shift.entered.is_empty() && shift.exited.is_empty());
+    let shift = diff::rank_shift(&ranking(&["A", "B", "C"]), &ranking(&["C", "E", "A"]), 2);
+    assert_eq!(shift.entered, vec![("C".to_string(), Some(3), 1), ("E".to_string(), None, 2)]);
+    assert_eq!(shift.exited, vec![("A".to_string(), 1, Some(3)), ("B".to_string(), 2, None)]);
+}
+
+#[test] // simple cycles are found once each, the temporal constraint prunes, and counts add up
+fn test_cycle_analysis() {
+    // A → B → C → A moves forward in time and returns once; A → D → F → A goes back twice.
+    let (edges, timestamps) = graph(
+        &[("A", "B"), ("B", "C"), ("C", "A"), ("A", "D"), ("D", "F"), ("F", "A"), ("B", "A"), ("C", "E")],
+        &[("A", 2), ("B", 3), ("C", 4), ("D", 1), ("E", 4), ("F", 3)],
+    );
+    let labels: HashMap<String, String> = [("A", "2"), ("B", "1")].iter().map(|(n, l)| (n.to_string(), l.to_string())).collect();
+    let names = |cycle: &[&str]| cycle.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+
+    let all = cycles::simple_cycles(&edges, None, 4);
+    assert_eq!(all, vec![names(&["A", "B"]), names(&["A", "B", "C"]), names(&["A", "D", "F"])]);
+    assert_eq!(cycles::simple_cycles(&edges, None, 2), vec![names(&["A", "B"])]);
+    let temporal = cycles::simple_cycles(&edges, Some(&timestamps), 4);
+    assert_eq!(temporal, vec![names(&["A", "B"]), names(&["A", "B", "C"])]);
+
+    let mut tracker = BudgetTracker::new(budget::SearchBudget::default());
+    assert_eq!(cycles::cycles_through(&edges, "A", 3, &mut tracker), all);
+    // B ⇄ C is a loop on the way back to A; going round it again is not another cycle through A.
+    let (looped, _) = graph(&[("A", "B"), ("B", "C"), ("C", "B"), ("B", "A")], &[]);
+    assert_eq!(cycles::cycles_through(&looped, "A", 4, &mut tracker), vec![names(&["A", "B"])]);
+
+    let summary = cycles::summarize_cycles(all, &timestamps, &labels);
+    assert_eq!(summary.cycles[1].span, (2, 4));
+    assert_eq!(summary.cycles[1].label_mix(), "illicit+licit+unknown");
+    assert_eq!(summary.cycles[2].labels, vec!["illicit", "unknown", "unknown"]);
+    assert_eq!(summary.by_length, std::collections::BTreeMap::from([(2, 1), (3, 2)]));
+    assert_eq!(summary.by_label_mix[0].1, 1);
+    assert_eq!(summary.involvement[0], ("A".to_string(), 3));
+    assert_eq!(summary.involvement[1], ("B".to_string(), 2));
+    assert_eq!(summary.involvement.iter().map(|(_, c)| c).sum::<usize>(), 8);
+}
@@ -0,0 +1,67 @@
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+/// Minimum time between two redraws of the progress line.
+const REDRAW_EVERY: Duration = Duration::from_millis(200);
+
+/// Single-line progress indicator on stderr: items done, cut-offs and ETA.
+pub struct Progress {
+    label: String,
+    total: usize,
+    done: usize,
+    cut_off: usize,
+    started: Instant,
+    last_draw: Option<Instant>,
+}
+
+impl Progress {
+    pub fn new(label: &str, total: usize) -> Self {
+        Progress {
+            label: label.to_string(),
+            total,
+            done: 0,
+            cut_off: 0,
+            started: Instant::now(),
+            last_draw: None,
+        }
+    }
+
+    /// Marks one item as done, noting whether its search was cut off.
+    pub fn tick(&mut self, was_cut_off: bool) {
+        self.done += 1;
+        if was_cut_off {
+            self.cut_off += 1;
+        }
+        if self.last_draw.is_none_or(|t| t.elapsed() >= REDRAW_EVERY) {
+            self.draw();
+        }
+    }
+
+    /// Draws the final state and moves to a new line.
+    pub fn finish(&mut self) {
+        self.draw();
+        eprintln!();
+    }
+
+    fn draw(&mut self) {
+        self.last_draw = Some(Instant::now());
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let eta = if self.done == 0 {
+            String::from("?")
+        } else {
+            let remaining = elapsed / self.done as f64 * (self.total - self.done) as f64;
+            format!("{:.0}s", remaining)
+        };
+        eprint!(
+            "\r[{}] {}/{} pairs ({:.1}%), {} cut off, elapsed {:.0}s, ETA {}   ",
+            self.label,
+            self.done,
+            self.total,
+            100.0 * self.done as f64 / self.total.max(1) as f64,
+            self.cut_off,
+            elapsed,
+            eta
+        );
+        let _ = std::io::stderr().flush();
+    }
+}
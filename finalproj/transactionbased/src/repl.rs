@@ -0,0 +1,332 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rustyline::DefaultEditor;
+use rustyline::error::ReadlineError;
+
+use crate::adapter;
+use crate::analysis::{self, Analysis, AnalysisResult};
+use crate::annotations;
+use crate::budget::BudgetTracker;
+use crate::config::Config;
+use crate::cycles;
+use crate::dfsstuff::{self, PathReservoir};
+use crate::explain::label_name;
+use crate::fileread::{self, Dataset};
+use crate::scoring::LabelGroup;
+
+/// Where the shell keeps its command history between sessions.
+const HISTORY_FILE: &str = ".transactionbased_history";
+
+/// Rows printed by `score` without a txId.
+const SCORE_ROWS: usize = 20;
+
+const HELP: &str = "\
+Commands:
+  load [dir|spec]            read Elliptic CSVs or a dataset spec (default: --dataset / --data-dir)
+  info <tx>                  timestep, label and in/out neighbors
+  reach <tx> <depth>         time-respecting reachable set within <depth> hops
+  paths <a> <b> <depth>      sampled time-respecting paths from a to b
+  cycles <tx> <k>            simple cycles of up to k + 1 nodes through tx
+  sample <group> <n>         n random txIds from a label group (illicit, licit, unknown, name=label)
+  score [tx]                 run the mixer experiment once, then show the top rows or one txId's rank
+  last                       show the txIds listed by the previous command again
+  history                    show previous commands
+  help, quit
+Every listed txId is numbered; use $1, $2, ... in the next command to pivot to it.";
+
+/// State kept between commands of one interactive session.
+pub struct Repl {
+    config: Config,
+    analysis: Option<Analysis>,
+    predecessors: HashMap<String, Vec<String>>,
+    /// txIds listed by the last command; `$n` in a later command is the n-th of them.
+    last: Vec<String>,
+    /// Output of the last listing, shown again by `last`.
+    last_output: String,
+    /// Ranking from `score`, kept until the next `load`.
+    scores: Option<AnalysisResult>,
+    /// Draws for `sample`; seeded from `--seed` so a scripted session repeats.
+    rng: StdRng,
+}
+
+impl Repl {
+    /// An empty session; nothing is loaded until the `load` command.
+    pub fn new(config: Config) -> Self {
+        Repl {
+            rng: StdRng::seed_from_u64(config.seed.unwrap_or_else(rand::random)),
+            config,
+            analysis: None,
+            predecessors: HashMap::new(),
+            last: Vec::new(),
+            last_output: String::new(),
+            scores: None,
+        }
+    }
+
+    /// Replaces the loaded graph (and drops results computed on the old one).
+    ///
+    /// # Returns
+    /// An error if the `--annotations` file can't be read; the old graph is kept then.
+    pub fn set_dataset(&mut self, data: Dataset) -> Result<(), String> {
+        let annotations = match &self.config.annotations {
+            Some(path) => annotations::read_annotations(path)?,
+            None => HashMap::new(),
+        };
+        self.predecessors = dfsstuff::predecessors(&data.edges);
+        self.analysis = Some(
+            Analysis::new(data.edges, data.timestamps, data.labels)
+                .with_config(self.config.clone())
+                .with_annotations(annotations),
+        );
+        self.scores = None;
+        self.last.clear();
+        self.last_output.clear();
+        Ok(())
+    }
+
+    /// Runs one command line.
+    ///
+    /// # Returns
+    /// The text to print, or a message explaining what was wrong with the command.
+    pub fn execute(&mut self, line: &str) -> Result<String, String> {
+        let words: Vec<String> = line
+            .split_whitespace()
+            .map(|w| self.resolve(w))
+            .collect::<Result<_, _>>()?;
+        let args: Vec<&str> = words.iter().map(|w| w.as_str()).collect();
+
+        match args.as_slice() {
+            [] => Ok(String::new()),
+            ["help"] => Ok(HELP.to_string()),
+            ["load"] => self.load(None),
+            ["load", path] => self.load(Some(path)),
+            ["info", tx] => self.info(tx),
+            ["reach", tx, depth] => self.reach(tx, parse_number(depth)?),
+            ["paths", from, to, depth] => self.paths(from, to, parse_number(depth)?),
+            ["cycles", tx, k] => self.cycles(tx, parse_number(k)?),
+            ["sample", group, n] => self.sample(group, parse_number(n)?),
+            ["score"] => self.score(None),
+            ["score", tx] => self.score(Some(tx)),
+            ["last"] => Ok(self.last_output.clone()),
+            [command, ..] => Err(format!("Unknown command or wrong arguments: {} (try help)", command)),
+        }
+    }
+
+    /// Replaces `$n` with the n-th txId of the last listing.
+    fn resolve(&self, word: &str) -> Result<String, String> {
+        let Some(index) = word.strip_prefix('$') else {
+            return Ok(word.to_string());
+        };
+        index
+            .parse::<usize>()
+            .ok()
+            .and_then(|i| i.checked_sub(1))
+            .and_then(|i| self.last.get(i))
+            .cloned()
+            .ok_or_else(|| format!("{} does not refer to a listed txId ({} listed)", word, self.last.len()))
+    }
+
+    fn data(&self) -> Result<&Analysis, String> {
+        self.analysis.as_ref().ok_or_else(|| "No dataset loaded (use load [dir])".to_string())
+    }
+
+    fn known(&self, tx: &str) -> Result<String, String> {
+        if self.data()?.timestamps().contains_key(tx) {
+            Ok(tx.to_string())
+        } else {
+            Err(format!("Unknown txId {}", tx))
+        }
+    }
+
+    /// Numbers the listed txIds as `$1, $2, ...` under each heading and remembers
+    /// them for the next command. Each txId comes with a note printed after its label.
+    fn listing(&mut self, sections: Vec<(String, Vec<(String, String)>)>) -> String {
+        let data = self.analysis.as_ref().expect("listing without a dataset");
+        let mut out = Vec::new();
+        let mut last = Vec::new();
+        for (heading, nodes) in sections {
+            out.push(heading);
+            for (node, note) in nodes {
+                let ts = data.timestamps().get(&node).copied().unwrap_or(0);
+                let label = data.labels().get(&node).map_or("unknown", |l| label_name(l));
+                out.push(format!("  ${:<4} {:<12} t={:<3} {:<8} {}", last.len() + 1, node, ts, label, note).trim_end().to_string());
+                last.push(node);
+            }
+        }
+        self.last = last;
+        self.last_output = out.join("\n");
+        self.last_output.clone()
+    }
+
+    /// Loads a dataset spec file, an Elliptic folder, or (without `path`) whatever the options point at.
+    fn load(&mut self, path: Option<&str>) -> Result<String, String> {
+        let data = match path {
+            None => adapter::load_configured(&self.config)?,
+            Some(path) if Path::new(path).is_file() => adapter::load(&adapter::DatasetSpec::read(path)?)?,
+            Some(dir) => fileread::load_dataset(dir)?,
+        };
+        self.set_dataset(data)?;
+        let data = self.data()?;
+        let edges: usize = data.edges().values().map(|t| t.len()).sum();
+        Ok(format!("Loaded {} txs and {} edges", data.timestamps().len(), edges))
+    }
+
+    fn info(&mut self, tx: &str) -> Result<String, String> {
+        let tx = self.known(tx)?;
+        let data = self.data()?;
+        let mut out: Vec<String> = data.edges().get(&tx).map_or(Vec::new(), |t| t.iter().cloned().collect());
+        out.sort();
+        let preds = self.predecessors.get(&tx).cloned().unwrap_or_default();
+        let header = format!(
+            "{}: timestep {}, {}",
+            tx,
+            data.timestamps()[&tx],
+            data.labels().get(&tx).map_or("unknown", |l| label_name(l))
+        );
+        let listed = self.listing(vec![
+            (format!("out ({})", out.len()), plain(out)),
+            (format!("in ({})", preds.len()), plain(preds)),
+        ]);
+        Ok(format!("{}\n{}", header, listed))
+    }
+
+    fn reach(&mut self, tx: &str, depth: usize) -> Result<String, String> {
+        let tx = self.known(tx)?;
+        let data = self.data()?;
+        let mut tracker = BudgetTracker::new(data.config().query_budget());
+        let nodes = dfsstuff::reachable_within(data.edges(), data.timestamps(), &tx, depth, &mut tracker);
+        let heading = format!("{} txs reachable from {} within {} hops ({})", nodes.len(), tx, depth, tracker.status());
+        Ok(self.listing(vec![(heading, plain(nodes))]))
+    }
+
+    fn paths(&mut self, from: &str, to: &str, depth: usize) -> Result<String, String> {
+        let (from, to) = (self.known(from)?, self.known(to)?);
+        let data = self.data()?;
+        let mut reservoir = PathReservoir::new(data.config().reservoir_size);
+        let mut tracker = BudgetTracker::new(data.config().query_budget());
+        dfsstuff::dfs_sample_paths(
+            data.successors(), data.timestamps(),
+            &from, &to,
+            &mut Vec::new(), &mut reservoir, &mut HashSet::new(),
+            1, depth, &mut tracker,
+        );
+
+        let mut lines = vec![format!(
+            "{} paths from {} to {} within depth {} ({}), showing {}",
+            reservoir.total, from, to, depth, tracker.status(), reservoir.paths.len()
+        )];
+        reservoir.paths.sort_by_key(|p| p.len());
+        lines.extend(reservoir.paths.iter().map(|p| format!("  {}", p.join(" → "))));
+        let intermediaries = distinct_nodes(&reservoir.paths, &[&from, &to]);
+        let listed = self.listing(vec![("intermediaries".to_string(), plain(intermediaries))]);
+        Ok(format!("{}\n{}", lines.join("\n"), listed))
+    }
+
+    fn cycles(&mut self, tx: &str, k: usize) -> Result<String, String> {
+        let tx = self.known(tx)?;
+        let data = self.data()?;
+        let mut tracker = BudgetTracker::new(data.config().query_budget());
+        let found = cycles::cycles_through(data.edges(), &tx, k, &mut tracker);
+        let mut lines = vec![format!("{} cycles through {} with up to {} nodes ({})", found.len(), tx, k + 1, tracker.status())];
+        lines.extend(found.iter().map(|c| format!("  {} → {}", c.join(" → "), tx)));
+        let members = distinct_nodes(&found, &[&tx]);
+        let listed = self.listing(vec![("members".to_string(), plain(members))]);
+        Ok(format!("{}\n{}", lines.join("\n"), listed))
+    }
+
+    fn sample(&mut self, group: &str, n: usize) -> Result<String, String> {
+        let group: LabelGroup = match self.config.groups.iter().find(|g| g.name == group) {
+            Some(g) => g.clone(),
+            None => group.parse()?,
+        };
+        let nodes = group.members(self.data()?.labels());
+        let total = nodes.len();
+        let mut sampled = analysis::sampler(nodes, n, &mut self.rng);
+        sampled.sort();
+        Ok(self.listing(vec![(format!("{} of {} {} txs", sampled.len(), total, group.name), plain(sampled))]))
+    }
+
+    fn score(&mut self, tx: Option<&str>) -> Result<String, String> {
+        let tx = tx.map(|t| self.known(t)).transpose()?;
+        if self.scores.is_none() {
+            self.scores = Some(self.data()?.run()?);
+        }
+        let stats = &self.scores.as_ref().unwrap().stats;
+
+        match tx {
+            Some(tx) => Ok(match stats.iter().position(|s| s.node == tx) {
+                Some(rank) => {
+                    let s = &stats[rank];
+                    let reuse: Vec<String> = self.config.groups.iter().zip(&s.reuse).map(|(g, r)| format!("{} {}", g.name, r)).collect();
+                    format!(
+                        "{}: rank {} of {}, mean {:.4} ± {:.4} (95% CI {:.4}..{:.4}), reuse {}",
+                        tx, rank + 1, stats.len(), s.mean, s.stddev, s.ci_low, s.ci_high, reuse.join(", ")
+                    )
+                }
+                None => format!("{} was not scored ({} candidates)", tx, stats.len()),
+            }),
+            None => {
+                let top = stats.iter().take(SCORE_ROWS).map(|s| (s.node.clone(), format!("score {:.4}", s.mean))).collect();
+                let heading = format!("top {} of {} candidates", SCORE_ROWS.min(stats.len()), stats.len());
+                Ok(self.listing(vec![(heading, top)]))
+            }
+        }
+    }
+}
+
+/// Listing entries without a note.
+fn plain(nodes: Vec<String>) -> Vec<(String, String)> {
+    nodes.into_iter().map(|n| (n, String::new())).collect()
+}
+
+fn parse_number(value: &str) -> Result<usize, String> {
+    value.parse().map_err(|_| format!("Expected a number, got {}", value))
+}
+
+/// Nodes of `paths` in order of first appearance, skipping `exclude`.
+fn distinct_nodes(paths: &[Vec<String>], exclude: &[&String]) -> Vec<String> {
+    let mut seen: HashSet<&String> = exclude.iter().copied().collect();
+    paths.iter().flatten().filter(|n| seen.insert(*n)).cloned().collect()
+}
+
+/// Reads commands from the terminal until `quit` or end of input.
+///
+/// History is kept in `.transactionbased_history` in the working directory,
+/// so the arrow keys also reach commands from earlier sessions.
+pub fn run(repl: &mut Repl) -> rustyline::Result<()> {
+    let mut editor = DefaultEditor::new()?;
+    let _ = editor.load_history(HISTORY_FILE);
+    println!("transactionbased shell — type help for commands");
+
+    loop {
+        let line = match editor.readline("tx> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        editor.add_history_entry(line)?;
+
+        match line {
+            "quit" | "exit" => break,
+            "history" => {
+                for (i, entry) in editor.history().iter().enumerate() {
+                    println!("{:>4}  {}", i + 1, entry);
+                }
+            }
+            _ => match repl.execute(line) {
+                Ok(output) => println!("{}", output),
+                Err(msg) => println!("error: {}", msg),
+            },
+        }
+    }
+
+    editor.save_history(HISTORY_FILE)
+}
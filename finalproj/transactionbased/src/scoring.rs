@@ -0,0 +1,211 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+/// Pseudo-count used by the smoothed log-odds score.
+const LOG_ODDS_PRIOR: f64 = 1.0;
+
+/// A named set of class labels that start nodes are drawn from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LabelGroup {
+    pub name: String,
+    pub labels: Vec<String>,
+}
+
+impl FromStr for LabelGroup {
+    type Err = String;
+
+    /// Parses `illicit`, `licit`, `unknown`, or a custom `name=label1+label2`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, labels) = match s.split_once('=') {
+            Some((name, labels)) => (name, labels.split('+').map(|l| l.to_string()).collect()),
+            None => match s {
+                "illicit" => (s, vec!["2".to_string()]),
+                "licit" => (s, vec!["1".to_string()]),
+                "unknown" => (s, vec!["unknown".to_string()]),
+                _ => return Err(format!("Unknown group {} (use illicit, licit, unknown or name=label)", s)),
+            },
+        };
+        Ok(LabelGroup { name: name.to_string(), labels })
+    }
+}
+
+impl LabelGroup {
+    /// Nodes whose label is one of the group's labels, sorted.
+    pub fn members(&self, labels: &HashMap<String, String>) -> Vec<String> {
+        let mut nodes: Vec<String> = labels
+            .iter()
+            .filter(|(_, l)| self.labels.contains(l))
+            .map(|(n, _)| n.clone())
+            .collect();
+        nodes.sort();
+        nodes
+    }
+}
+
+/// Which group is scored against which, e.g. illicit vs unknown.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Contrast {
+    pub target: String,
+    pub baseline: String,
+}
+
+impl FromStr for Contrast {
+    type Err = String;
+
+    /// Parses `target:baseline`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (target, baseline) = s
+            .split_once(':')
+            .ok_or_else(|| format!("Contrast should look like target:baseline, got {}", s))?;
+        Ok(Contrast {
+            target: target.to_string(),
+            baseline: baseline.to_string(),
+        })
+    }
+}
+
+/// What a single `theory_tester` run saw for one group of start nodes.
+pub struct ReuseTally {
+    /// Node → number of collected paths it sits in the middle of.
+    pub node_freq: HashMap<String, usize>,
+    /// Node → distinct start nodes whose collected paths pass through it.
+    pub node_starts: HashMap<String, HashSet<String>>,
+    /// Node → number of start nodes whose reachable set contains it.
+    pub reach_count: HashMap<String, usize>,
+    /// Number of start nodes the tally was built from.
+    pub num_starts: usize,
+    /// The sampled paths (or walks) the counts above were taken from.
+    pub paths: Vec<Vec<String>>,
+}
+
+/// The different ways of turning target/baseline reuse (e.g. illicit/licit) into a mixer score.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScoringMethod {
+    /// `illicit / (licit + 1)`, the original score.
+    Ratio,
+    /// Ratio weighted by how rarely the node is reachable at all (inverse reachability).
+    TfIdf,
+    /// Ratio divided by the log of the node's total degree.
+    DegreeNormalized,
+    /// Difference of smoothed log-odds of appearing in illicit vs licit paths.
+    LogOdds,
+    /// Distinct illicit starts routed through the node vs distinct licit starts.
+    DistinctStarts,
+}
+
+impl ScoringMethod {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScoringMethod::Ratio => "ratio",
+            ScoringMethod::TfIdf => "tfidf",
+            ScoringMethod::DegreeNormalized => "degree",
+            ScoringMethod::LogOdds => "logodds",
+            ScoringMethod::DistinctStarts => "distinct",
+        }
+    }
+}
+
+impl FromStr for ScoringMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ratio" => Ok(ScoringMethod::Ratio),
+            "tfidf" => Ok(ScoringMethod::TfIdf),
+            "degree" => Ok(ScoringMethod::DegreeNormalized),
+            "logodds" => Ok(ScoringMethod::LogOdds),
+            "distinct" => Ok(ScoringMethod::DistinctStarts),
+            _ => Err(format!(
+                "Unknown scoring method {} (expected ratio, tfidf, degree, logodds or distinct)",
+                s
+            )),
+        }
+    }
+}
+
+/// Total in + out degree for every node in the graph.
+pub fn total_degrees(graph: &HashMap<String, HashSet<String>>) -> HashMap<String, usize> {
+    let mut degrees: HashMap<String, usize> = HashMap::new();
+    for (src, targets) in graph {
+        *degrees.entry(src.clone()).or_insert(0) += targets.len();
+        for tgt in targets {
+            *degrees.entry(tgt.clone()).or_insert(0) += 1;
+        }
+    }
+    degrees
+}
+
+/// Group-level totals shared by every node's score in one run.
+///
+/// `target` is the group whose reuse counts as suspicious (usually illicit) and
+/// `baseline` the group it is compared against (usually licit).
+pub struct ScoringContext<'a> {
+    target: &'a ReuseTally,
+    baseline: &'a ReuseTally,
+    degrees: &'a HashMap<String, usize>,
+    target_total: f64,
+    baseline_total: f64,
+    vocab: f64,
+}
+
+impl<'a> ScoringContext<'a> {
+    pub fn new(
+        target: &'a ReuseTally,
+        baseline: &'a ReuseTally,
+        degrees: &'a HashMap<String, usize>,
+    ) -> Self {
+        let vocab = target
+            .node_freq
+            .keys()
+            .chain(baseline.node_freq.keys())
+            .collect::<HashSet<_>>()
+            .len();
+
+        ScoringContext {
+            target,
+            baseline,
+            degrees,
+            target_total: target.node_freq.values().sum::<usize>() as f64,
+            baseline_total: baseline.node_freq.values().sum::<usize>() as f64,
+            vocab: vocab as f64,
+        }
+    }
+
+    /// Scores one node with the given method. Higher means more mixer-like.
+    pub fn score(&self, method: ScoringMethod, node: &str) -> f64 {
+        let target = *self.target.node_freq.get(node).unwrap_or(&0) as f64;
+        let baseline = *self.baseline.node_freq.get(node).unwrap_or(&0) as f64;
+        let ratio = target / (baseline + 1.0);
+
+        match method {
+            ScoringMethod::Ratio => ratio,
+            ScoringMethod::TfIdf => {
+                let starts = (self.target.num_starts + self.baseline.num_starts) as f64;
+                let reached = (self.target.reach_count.get(node).unwrap_or(&0)
+                    + self.baseline.reach_count.get(node).unwrap_or(&0)) as f64;
+                ratio * ((1.0 + starts) / (1.0 + reached)).ln()
+            }
+            ScoringMethod::DegreeNormalized => {
+                let degree = *self.degrees.get(node).unwrap_or(&0) as f64;
+                ratio / (std::f64::consts::E + degree).ln()
+            }
+            ScoringMethod::LogOdds => {
+                // Both groups get the same pseudo-count so rare nodes shrink towards 0.
+                // The extra slot keeps p < 1 when only one node was ever reused.
+                let smoothing = LOG_ODDS_PRIOR * (self.vocab + 1.0);
+                let p_target = (target + LOG_ODDS_PRIOR) / (self.target_total + smoothing);
+                let p_baseline = (baseline + LOG_ODDS_PRIOR) / (self.baseline_total + smoothing);
+                logit(p_target) - logit(p_baseline)
+            }
+            ScoringMethod::DistinctStarts => {
+                let target_starts = self.target.node_starts.get(node).map_or(0, |s| s.len());
+                let baseline_starts = self.baseline.node_starts.get(node).map_or(0, |s| s.len());
+                target_starts as f64 / (baseline_starts as f64 + 1.0)
+            }
+        }
+    }
+}
+
+fn logit(p: f64) -> f64 {
+    (p / (1.0 - p)).ln()
+}
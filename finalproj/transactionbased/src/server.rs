@@ -0,0 +1,355 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use crate::analysis::{Analysis, AnalysisResult};
+use crate::budget::BudgetTracker;
+use crate::cycles;
+use crate::dfsstuff::{self, PathReservoir};
+use crate::sketch::ReachSketches;
+
+/// Largest `depth` `/reach` and `/paths` accept.
+const MAX_DEPTH: usize = 50;
+/// Largest `k` `/cycles` accepts; the number of cycles grows exponentially with it.
+const MAX_CYCLE_K: usize = 8;
+/// Largest number of paths `/paths` keeps.
+const MAX_LIMIT: usize = 10_000;
+/// How long a client may take to send its request before the server moves on.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Minimal JSON value, enough for the server's responses.
+pub enum Json {
+    Null,
+    Bool(bool),
+    Int(usize),
+    Num(f64),
+    Str(String),
+    Arr(Vec<Json>),
+    Obj(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    pub fn strs<S: AsRef<str>>(items: impl IntoIterator<Item = S>) -> Json {
+        Json::Arr(items.into_iter().map(|s| Json::Str(s.as_ref().to_string())).collect())
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Int(n) => write!(f, "{}", n),
+            Json::Num(x) if x.is_finite() => write!(f, "{}", x),
+            Json::Num(_) => write!(f, "null"),
+            Json::Str(s) => {
+                write!(f, "\"")?;
+                for c in s.chars() {
+                    match c {
+                        '"' => write!(f, "\\\"")?,
+                        '\\' => write!(f, "\\\\")?,
+                        c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                        c => write!(f, "{}", c)?,
+                    }
+                }
+                write!(f, "\"")
+            }
+            Json::Arr(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Json::Obj(fields) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "\"{}\":{}", key, value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// The loaded graph plus whatever the server has computed so far.
+pub struct ServerState {
+    analysis: Analysis,
+    predecessors: HashMap<String, Vec<String>>,
+    /// Mixer ranking, computed on the first `/score` request.
+    scores: Option<AnalysisResult>,
+    /// Reachability sketches, built on the first `/sketch` request.
+    sketches: Option<ReachSketches>,
+}
+
+impl ServerState {
+    pub fn new(analysis: Analysis) -> Self {
+        ServerState {
+            predecessors: dfsstuff::predecessors(analysis.edges()),
+            analysis,
+            scores: None,
+            sketches: None,
+        }
+    }
+
+    /// Answers one request path such as `/reach?tx=123&depth=3`.
+    ///
+    /// # Returns
+    /// HTTP status code and JSON body.
+    pub fn handle(&mut self, target: &str) -> (u16, Json) {
+        let (route, query) = target.split_once('?').unwrap_or((target, ""));
+        let params: HashMap<String, String> = query
+            .split('&')
+            .filter_map(|p| p.split_once('='))
+            .map(|(key, value)| (percent_decode(key), percent_decode(value)))
+            .collect();
+
+        let result = match route {
+            "/" | "/health" => Ok(self.health()),
+            "/neighbors" => param(&params, "tx").and_then(|tx| self.neighbors(tx)),
+            "/reach" => param(&params, "tx").and_then(|tx| {
+                let depth = number(&params, "depth", 3, MAX_DEPTH)?;
+                self.reach(tx, depth)
+            }),
+            "/paths" => param(&params, "from").and_then(|from| {
+                let to = param(&params, "to")?;
+                let depth = number(&params, "depth", self.analysis.config().collect_depth, MAX_DEPTH)?;
+                let limit = number(&params, "limit", self.analysis.config().reservoir_size, MAX_LIMIT)?;
+                self.paths(from, to, depth, limit)
+            }),
+            "/cycles" => param(&params, "tx").and_then(|tx| {
+                let k = number(&params, "k", 3, MAX_CYCLE_K)?;
+                self.cycles(tx, k)
+            }),
+            "/score" => param(&params, "tx").and_then(|tx| self.score(tx)),
+            "/sketch" => param(&params, "tx").and_then(|tx| self.sketch(tx)),
+            _ => Err((404, format!("Unknown route {}", route))),
+        };
+
+        match result {
+            Ok(body) => (200, body),
+            Err((status, msg)) => (status, Json::Obj(vec![("error", Json::Str(msg))])),
+        }
+    }
+
+    fn known(&self, tx: &str) -> Result<String, (u16, String)> {
+        if self.analysis.timestamps().contains_key(tx) {
+            Ok(tx.to_string())
+        } else {
+            Err((404, format!("Unknown txId {}", tx)))
+        }
+    }
+
+    fn health(&self) -> Json {
+        let edges: usize = self.analysis.edges().values().map(|t| t.len()).sum();
+        Json::Obj(vec![
+            ("nodes", Json::Int(self.analysis.timestamps().len())),
+            ("edges", Json::Int(edges)),
+            ("scored", Json::Bool(self.scores.is_some())),
+        ])
+    }
+
+    fn neighbors(&self, tx: &str) -> Result<Json, (u16, String)> {
+        let tx = self.known(tx)?;
+        let mut out: Vec<&String> = self.analysis.edges().get(&tx).map_or(Vec::new(), |t| t.iter().collect());
+        out.sort();
+        Ok(Json::Obj(vec![
+            ("tx", Json::Str(tx.clone())),
+            ("timestep", Json::Int(self.analysis.timestamps()[&tx])),
+            ("label", Json::Str(self.analysis.labels().get(&tx).cloned().unwrap_or("unknown".to_string()))),
+            ("out", Json::strs(out)),
+            ("in", Json::strs(self.predecessors.get(&tx).cloned().unwrap_or_default())),
+        ]))
+    }
+
+    fn reach(&self, tx: &str, depth: usize) -> Result<Json, (u16, String)> {
+        let tx = self.known(tx)?;
+        let mut tracker = BudgetTracker::new(self.analysis.config().query_budget());
+        let nodes = dfsstuff::reachable_within(self.analysis.edges(), self.analysis.timestamps(), &tx, depth, &mut tracker);
+        Ok(Json::Obj(vec![
+            ("tx", Json::Str(tx)),
+            ("depth", Json::Int(depth)),
+            ("status", Json::Str(tracker.status().to_string())),
+            ("count", Json::Int(nodes.len())),
+            ("nodes", Json::strs(nodes)),
+        ]))
+    }
+
+    fn paths(&self, from: &str, to: &str, depth: usize, limit: usize) -> Result<Json, (u16, String)> {
+        let (from, to) = (self.known(from)?, self.known(to)?);
+        let mut reservoir = PathReservoir::new(limit);
+        let mut tracker = BudgetTracker::new(self.analysis.config().query_budget());
+        dfsstuff::dfs_sample_paths(
+            self.analysis.successors(), self.analysis.timestamps(),
+            &from, &to,
+            &mut Vec::new(), &mut reservoir, &mut HashSet::new(),
+            1, depth, &mut tracker,
+        );
+        Ok(Json::Obj(vec![
+            ("from", Json::Str(from)),
+            ("to", Json::Str(to)),
+            ("total", Json::Int(reservoir.total)),
+            ("status", Json::Str(tracker.status().to_string())),
+            ("paths", Json::Arr(reservoir.paths.into_iter().map(Json::strs).collect())),
+        ]))
+    }
+
+    fn cycles(&self, tx: &str, k: usize) -> Result<Json, (u16, String)> {
+        let tx = self.known(tx)?;
+        let mut tracker = BudgetTracker::new(self.analysis.config().query_budget());
+        let found = cycles::cycles_through(self.analysis.edges(), &tx, k, &mut tracker);
+        Ok(Json::Obj(vec![
+            ("tx", Json::Str(tx)),
+            ("k", Json::Int(k)),
+            ("status", Json::Str(tracker.status().to_string())),
+            ("count", Json::Int(found.len())),
+            ("cycles", Json::Arr(found.into_iter().map(Json::strs).collect())),
+        ]))
+    }
+
+    fn sketch(&mut self, tx: &str) -> Result<Json, (u16, String)> {
+        let tx = self.known(tx)?;
+        let analysis = &self.analysis;
+        let sketches = self.sketches.get_or_insert_with(|| {
+            ReachSketches::build(analysis.edges(), analysis.timestamps(), analysis.config().sketch_k)
+        });
+        Ok(Json::Obj(vec![
+            ("tx", Json::Str(tx.clone())),
+            ("k", Json::Int(sketches.k)),
+            ("forward", Json::Num(sketches.forward_size(&tx))),
+            ("backward", Json::Num(sketches.backward_size(&tx))),
+        ]))
+    }
+
+    fn score(&mut self, tx: &str) -> Result<Json, (u16, String)> {
+        let tx = self.known(tx)?;
+        if self.scores.is_none() {
+            self.scores = Some(self.analysis.run().map_err(|e| (500, e))?);
+        }
+        let stats = &self.scores.as_ref().unwrap().stats;
+        let Some((rank, stat)) = stats.iter().enumerate().find(|(_, s)| s.node == tx) else {
+            return Ok(Json::Obj(vec![("tx", Json::Str(tx)), ("rank", Json::Null), ("candidates", Json::Int(stats.len()))]));
+        };
+        let groups = &self.analysis.config().groups;
+        Ok(Json::Obj(vec![
+            ("tx", Json::Str(tx)),
+            ("rank", Json::Int(rank + 1)),
+            ("candidates", Json::Int(stats.len())),
+            ("mean", Json::Num(stat.mean)),
+            ("stddev", Json::Num(stat.stddev)),
+            ("ci_low", Json::Num(stat.ci_low)),
+            ("ci_high", Json::Num(stat.ci_high)),
+            ("runs", Json::Int(stat.scores.len())),
+            ("reuse", Json::Arr(
+                groups.iter().zip(&stat.reuse).map(|(g, r)| {
+                    Json::Obj(vec![("group", Json::Str(g.name.clone())), ("count", Json::Int(*r))])
+                }).collect(),
+            )),
+        ]))
+    }
+}
+
+fn param<'a>(params: &'a HashMap<String, String>, name: &str) -> Result<&'a str, (u16, String)> {
+    params.get(name).map(|v| v.as_str()).ok_or((400, format!("Missing parameter {}", name)))
+}
+
+fn number(params: &HashMap<String, String>, name: &str, default: usize, max: usize) -> Result<usize, (u16, String)> {
+    let value = match params.get(name) {
+        Some(v) => v.parse().map_err(|_| (400, format!("{} expects a number, got {}", name, v)))?,
+        None => default,
+    };
+    if value > max {
+        return Err((400, format!("{} must be at most {}, got {}", name, max, value)));
+    }
+    Ok(value)
+}
+
+/// Decodes `%xx` escapes and `+` in a query string component; malformed escapes are kept as they are.
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok()).and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (b'+', _) => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Serves GET requests on `listener`, one at a time, until `max_requests` (if any) have been answered.
+///
+/// A failed accept or a client that sends nothing within `READ_TIMEOUT` is logged and skipped.
+pub fn serve(listener: TcpListener, state: &mut ServerState, max_requests: Option<usize>) -> std::io::Result<()> {
+    let mut served = 0;
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("Accept failed: {}", e);
+                continue;
+            }
+        };
+        if let Err(e) = handle_connection(stream, state) {
+            eprintln!("Request failed: {}", e);
+        }
+        served += 1;
+        if max_requests.is_some_and(|max| served >= max) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn handle_connection(mut stream: TcpStream, state: &mut ServerState) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut request_line = String::new();
+    let mut reader = BufReader::new(&stream);
+    reader.read_line(&mut request_line)?;
+    // Drain the headers; every supported request is a bodyless GET.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) => state.handle(target),
+        _ => (405, Json::Obj(vec![("error", Json::Str("Only GET is supported".to_string()))])),
+    };
+    println!("{} {}", status, request_line.trim());
+
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error",
+    };
+    let body = body.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, reason, body.len(), body
+    )
+}
@@ -0,0 +1,221 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::cycles;
+use crate::dfsstuff;
+
+/// Hash of a node id, uniform over `u64` (FNV-1a, then the splitmix64 finaliser).
+pub fn node_hash(node: &str) -> u64 {
+    let mut h: u64 = 0xcbf29ce484222325;
+    for byte in node.bytes() {
+        h ^= byte as u64;
+        h = h.wrapping_mul(0x100000001b3);
+    }
+    h = (h ^ (h >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94d049bb133111eb);
+    h ^ (h >> 31)
+}
+
+/// Merges two bottom-k sketches (sorted, distinct hashes) into `into`, keeping the `k` smallest.
+pub fn merge(into: &mut Vec<u64>, other: &[u64], k: usize) {
+    let mut merged = Vec::with_capacity(k.min(into.len() + other.len()));
+    let (mut i, mut j) = (0, 0);
+    while merged.len() < k && (i < into.len() || j < other.len()) {
+        let next = match (into.get(i), other.get(j)) {
+            (Some(a), Some(b)) if a == b => { i += 1; j += 1; *a }
+            (Some(a), Some(b)) if a < b => { i += 1; *a }
+            (Some(a), None) => { i += 1; *a }
+            (_, Some(b)) => { j += 1; *b }
+            (None, None) => unreachable!(),
+        };
+        merged.push(next);
+    }
+    *into = merged;
+}
+
+/// Estimated size of the set behind a bottom-k sketch; exact while the set has fewer than `k` members.
+pub fn estimate_size(sketch: &[u64], k: usize) -> f64 {
+    if sketch.len() < k || k < 2 {
+        return sketch.len() as f64;
+    }
+    // The k-th smallest of n uniform values sits near k / n.
+    let kth = sketch[k - 1] as f64 / u64::MAX as f64;
+    (k - 1) as f64 / kth
+}
+
+/// Estimated overlap of two sets from their sketches.
+pub struct Overlap {
+    pub jaccard: f64,
+    pub union: f64,
+    pub intersection: f64,
+}
+
+/// Jaccard similarity, union and intersection size of the sets behind two sketches.
+///
+/// The bottom-k of the union is a uniform sample of it, and a sampled hash is in
+/// both sets exactly when it appears in both sketches.
+pub fn overlap(a: &[u64], b: &[u64], k: usize) -> Overlap {
+    let mut union = a.to_vec();
+    merge(&mut union, b, k);
+    let (in_a, in_b): (HashSet<&u64>, HashSet<&u64>) = (a.iter().collect(), b.iter().collect());
+    let shared = union.iter().filter(|h| in_a.contains(h) && in_b.contains(h)).count();
+    let jaccard = if union.is_empty() { 0.0 } else { shared as f64 / union.len() as f64 };
+    let union_size = estimate_size(&union, k);
+    Overlap { jaccard, union: union_size, intersection: jaccard * union_size }
+}
+
+/// Bottom-k sketches of every node's time-respecting forward and backward reachable set.
+///
+/// Nodes in one strongly connected component of the time-respecting graph reach
+/// exactly the same nodes, so sketches are stored per component. Every set
+/// includes the node itself.
+pub struct ReachSketches {
+    pub k: usize,
+    component: HashMap<String, usize>,
+    forward: Vec<Vec<u64>>,
+    backward: Vec<Vec<u64>>,
+}
+
+impl ReachSketches {
+    /// Builds all sketches in one pass over the components in reverse topological
+    /// order (and one pass the other way for the backward sets).
+    ///
+    /// # Arguments
+    /// * `graph`, `timestamps` - Only edges that don't go back in time are followed.
+    /// * `k` - Hashes kept per sketch; sets smaller than `k` are stored exactly.
+    pub fn build(graph: &HashMap<String, HashSet<String>>, timestamps: &HashMap<String, usize>, k: usize) -> ReachSketches {
+        let mut forward_graph: HashMap<String, HashSet<String>> = timestamps.keys().map(|n| (n.clone(), HashSet::new())).collect();
+        for (src, targets) in graph {
+            let kept = forward_graph.entry(src.clone()).or_default();
+            kept.extend(targets.iter().filter(|t| dfsstuff::time_respecting(timestamps, src, t)).cloned());
+        }
+
+        // Sinks come first, so a component's successors are always done before it.
+        let components = cycles::strongly_connected_components(&forward_graph);
+        let component: HashMap<String, usize> = components
+            .iter()
+            .enumerate()
+            .flat_map(|(i, members)| members.iter().map(move |n| (n.clone(), i)))
+            .collect();
+        let own: Vec<Vec<u64>> = components
+            .iter()
+            .map(|members| {
+                let mut hashes: Vec<u64> = members.iter().map(|n| node_hash(n)).collect();
+                hashes.sort();
+                hashes.dedup();
+                hashes.truncate(k);
+                hashes
+            })
+            .collect();
+        let mut succ: Vec<HashSet<usize>> = vec![HashSet::new(); components.len()];
+        let mut pred: Vec<HashSet<usize>> = vec![HashSet::new(); components.len()];
+        for (src, targets) in &forward_graph {
+            for tgt in targets {
+                let (a, b) = (component[src], component[tgt]);
+                if a != b {
+                    succ[a].insert(b);
+                    pred[b].insert(a);
+                }
+            }
+        }
+
+        let mut forward = own.clone();
+        for c in 0..components.len() {
+            let mut sketch = std::mem::take(&mut forward[c]);
+            for &s in &succ[c] {
+                merge(&mut sketch, &forward[s], k);
+            }
+            forward[c] = sketch;
+        }
+        let mut backward = own;
+        for c in (0..components.len()).rev() {
+            let mut sketch = std::mem::take(&mut backward[c]);
+            for &p in &pred[c] {
+                merge(&mut sketch, &backward[p], k);
+            }
+            backward[c] = sketch;
+        }
+
+        ReachSketches { k, component, forward, backward }
+    }
+
+    pub fn forward(&self, node: &str) -> Option<&[u64]> {
+        self.component.get(node).map(|c| self.forward[*c].as_slice())
+    }
+
+    pub fn backward(&self, node: &str) -> Option<&[u64]> {
+        self.component.get(node).map(|c| self.backward[*c].as_slice())
+    }
+
+    /// Estimated number of nodes `node` reaches, not counting itself (0 for an unknown node).
+    pub fn forward_size(&self, node: &str) -> f64 {
+        self.forward(node).map_or(0.0, |s| (estimate_size(s, self.k) - 1.0).max(0.0))
+    }
+
+    /// Estimated number of nodes that reach `node`, not counting itself.
+    pub fn backward_size(&self, node: &str) -> f64 {
+        self.backward(node).map_or(0.0, |s| (estimate_size(s, self.k) - 1.0).max(0.0))
+    }
+
+    /// Sketch of everything reachable from any of `nodes` (the nodes included).
+    pub fn union_forward(&self, nodes: &[String]) -> Vec<u64> {
+        let mut union = Vec::new();
+        for sketch in nodes.iter().filter_map(|n| self.forward(n)) {
+            merge(&mut union, sketch, self.k);
+        }
+        union
+    }
+
+    /// Number of time-respecting strongly connected components the sketches are stored for.
+    pub fn components(&self) -> usize {
+        self.forward.len()
+    }
+}
+
+/// Exact time-respecting forward reachable set of `starts` (starts included), by BFS.
+///
+/// Used to check the sketch estimates.
+pub fn exact_forward(graph: &HashMap<String, HashSet<String>>, timestamps: &HashMap<String, usize>, starts: &[String]) -> HashSet<String> {
+    let mut seen: HashSet<String> = starts.iter().cloned().collect();
+    let mut queue: VecDeque<&String> = starts.iter().collect();
+    while let Some(node) = queue.pop_front() {
+        for tgt in graph.get(node).into_iter().flatten() {
+            if dfsstuff::time_respecting(timestamps, node, tgt) && seen.insert(tgt.clone()) {
+                queue.push_back(tgt);
+            }
+        }
+    }
+    seen
+}
+
+/// Prints sketch vs exact reach of two start groups, their overlap, and the nodes with the largest reach.
+///
+/// # Arguments
+/// * `groups` - (name, start nodes) of the two compared groups.
+/// * `exact` - Exact forward set of each group, from `exact_forward`.
+/// * `nodes` - Candidate nodes for the top lists (usually every node).
+pub fn print_sketch_report(sketches: &ReachSketches, groups: [(&str, &[String]); 2], exact: [&HashSet<String>; 2], nodes: &[String], top: usize) {
+    println!("\n{:<10} {:>8} {:>12} {:>10} {:>8}", "Group", "Starts", "Est. reach", "Exact", "Error");
+    let unions: Vec<Vec<u64>> = groups.iter().map(|(_, starts)| sketches.union_forward(starts)).collect();
+    for (((name, starts), union), exact) in groups.iter().zip(&unions).zip(exact) {
+        let estimate = estimate_size(union, sketches.k);
+        let error = (estimate - exact.len() as f64) / exact.len().max(1) as f64;
+        println!("{:<10} {:>8} {:>12.0} {:>10} {:>7.1}%", name, starts.len(), estimate, exact.len(), 100.0 * error);
+    }
+    let both = overlap(&unions[0], &unions[1], sketches.k);
+    let shared = exact[0].intersection(exact[1]).count();
+    let exact_union = exact[0].len() + exact[1].len() - shared;
+    println!(
+        "Overlap: Jaccard {:.4} (exact {:.4}), shared nodes {:.0} (exact {})",
+        both.jaccard, shared as f64 / exact_union.max(1) as f64, both.intersection, shared
+    );
+
+    for (title, forward) in [("forward", true), ("backward", false)] {
+        let size = |n: &str| if forward { sketches.forward_size(n) } else { sketches.backward_size(n) };
+        let mut ranked: Vec<(&String, f64)> = nodes.iter().map(|n| (n, size(n))).collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        println!("\nLargest estimated {} reach:", title);
+        for (node, estimate) in ranked.iter().take(top) {
+            println!("{:<15} {:>12.0}", node, estimate);
+        }
+    }
+}
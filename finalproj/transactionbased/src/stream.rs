@@ -0,0 +1,615 @@
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+use rand::prelude::IndexedRandom;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::config::Config;
+use crate::cycles;
+use crate::dfsstuff;
+use crate::explain::label_name;
+use crate::fileread::{self, Dataset};
+use crate::scoring::{ReuseTally, ScoringContext};
+use crate::structure;
+use crate::walks;
+
+/// New transactions, edges and labels arriving together (e.g. one timestep).
+#[derive(Default)]
+pub struct Batch {
+    pub timestamps: HashMap<String, usize>,
+    /// Edges may point at nodes from earlier batches or from this one.
+    pub edges: Vec<(String, String)>,
+    pub labels: HashMap<String, String>,
+}
+
+impl Batch {
+    /// Reads a folder with the three Elliptic files holding only the new rows.
+    ///
+    /// Unlike `load_dataset` nothing is validated, since edges may refer to nodes loaded earlier.
+    pub fn read(dir: &str) -> Result<Batch, String> {
+        let mut labels = fileread::read_to_hashmap(&format!("{}/elliptic_txs_classes.csv", dir))?;
+        labels.remove("txId");
+        let timestamps = fileread::read_to_hashmap(&format!("{}/elliptic_txs_features.csv", dir))?
+            .into_iter()
+            .filter_map(|(k, v)| v.parse::<usize>().ok().map(|ts| (k, ts)))
+            .collect();
+        let mut edges: Vec<(String, String)> = fileread::read_file_directed(&format!("{}/elliptic_txs_edgelist.csv", dir))?
+            .into_iter()
+            .flat_map(|(src, targets)| targets.into_iter().map(move |tgt| (src.clone(), tgt)))
+            .collect();
+        edges.sort();
+        Ok(Batch { timestamps, edges, labels })
+    }
+}
+
+/// Splits a loaded dataset into the graph before `from` and one batch per later timestep.
+///
+/// An edge arrives with the later of its two endpoints, so every batch only
+/// refers to nodes that already arrived.
+pub fn replay(data: &Dataset, from: usize) -> (Dataset, Vec<Batch>) {
+    let initial = structure::restrict_to_window(data, 0, from.saturating_sub(1));
+    let mut batches: std::collections::BTreeMap<usize, Batch> = std::collections::BTreeMap::new();
+    for (node, ts) in data.timestamps.iter().filter(|(_, ts)| **ts >= from) {
+        let batch = batches.entry(*ts).or_default();
+        batch.timestamps.insert(node.clone(), *ts);
+        if let Some(label) = data.labels.get(node) {
+            batch.labels.insert(node.clone(), label.clone());
+        }
+    }
+    for (src, targets) in &data.edges {
+        for tgt in targets {
+            if let (Some(a), Some(b)) = (data.timestamps.get(src), data.timestamps.get(tgt))
+                && *a.max(b) >= from
+            {
+                batches.entry(*a.max(b)).or_default().edges.push((src.clone(), tgt.clone()));
+            }
+        }
+    }
+    let mut batches: Vec<Batch> = batches.into_values().collect();
+    for batch in &mut batches {
+        batch.edges.sort();
+    }
+    (initial, batches)
+}
+
+/// Strongly connected components kept up to date under edge insertions.
+///
+/// Components are kept in a topological order of the condensation. An edge that
+/// agrees with the order changes nothing; one that goes against it only searches
+/// the components ordered between its endpoints, merges them if they now form a
+/// cycle, and reorders the rest (Pearce–Kelly).
+pub struct SccIndex {
+    component: HashMap<String, usize>,
+    members: HashMap<usize, Vec<String>>,
+    succ: HashMap<usize, HashSet<usize>>,
+    pred: HashMap<usize, HashSet<usize>>,
+    /// An edge between components a → b always has `order[a] < order[b]`.
+    order: HashMap<usize, usize>,
+    next_id: usize,
+    next_order: usize,
+}
+
+impl SccIndex {
+    /// Builds the index with `cycles::strongly_connected_components`.
+    pub fn new(graph: &HashMap<String, HashSet<String>>) -> SccIndex {
+        let components = cycles::strongly_connected_components(graph);
+        let count = components.len();
+        let mut index = SccIndex {
+            component: HashMap::new(),
+            members: HashMap::new(),
+            succ: HashMap::new(),
+            pred: HashMap::new(),
+            order: HashMap::new(),
+            next_id: count,
+            next_order: count,
+        };
+        // Tarjan lists sinks first, so the reverse is a topological order.
+        for (id, members) in components.into_iter().enumerate() {
+            for node in &members {
+                index.component.insert(node.clone(), id);
+            }
+            index.members.insert(id, members);
+            index.order.insert(id, count - 1 - id);
+            index.succ.insert(id, HashSet::new());
+            index.pred.insert(id, HashSet::new());
+        }
+        for (src, targets) in graph {
+            for tgt in targets {
+                let (a, b) = (index.component[src], index.component[tgt]);
+                if a != b {
+                    index.succ.get_mut(&a).unwrap().insert(b);
+                    index.pred.get_mut(&b).unwrap().insert(a);
+                }
+            }
+        }
+        index
+    }
+
+    fn add_node(&mut self, node: &String) -> usize {
+        if let Some(&id) = self.component.get(node) {
+            return id;
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.component.insert(node.clone(), id);
+        self.members.insert(id, vec![node.clone()]);
+        self.succ.insert(id, HashSet::new());
+        self.pred.insert(id, HashSet::new());
+        self.order.insert(id, self.next_order);
+        self.next_order += 1;
+        id
+    }
+
+    /// Components reachable from `start` (forwards or backwards) without leaving the order bound.
+    fn search(&self, start: usize, bound: usize, forward: bool) -> HashSet<usize> {
+        let mut seen = HashSet::from([start]);
+        let mut stack = vec![start];
+        while let Some(c) = stack.pop() {
+            let next = if forward { &self.succ[&c] } else { &self.pred[&c] };
+            for &n in next {
+                let inside = if forward { self.order[&n] <= bound } else { self.order[&n] >= bound };
+                if inside && seen.insert(n) {
+                    stack.push(n);
+                }
+            }
+        }
+        seen
+    }
+
+    /// Adds the edge `from → to` (creating either node if needed).
+    ///
+    /// # Returns
+    /// The number of components merged into one by the new edge (0 if none).
+    pub fn insert_edge(&mut self, from: &String, to: &String) -> usize {
+        let (cu, cv) = (self.add_node(from), self.add_node(to));
+        if cu == cv || !self.succ.get_mut(&cu).unwrap().insert(cv) {
+            return 0;
+        }
+        self.pred.get_mut(&cv).unwrap().insert(cu);
+        let (lower, upper) = (self.order[&cv], self.order[&cu]);
+        if lower > upper {
+            return 0;
+        }
+
+        let forward = self.search(cv, upper, true);
+        let backward = self.search(cu, lower, false);
+        // Every component on a path to -> ... -> from now sits on a cycle through the new edge.
+        let merged: HashSet<usize> = if forward.contains(&cu) { forward.intersection(&backward).copied().collect() } else { HashSet::new() };
+
+        let by_order = |set: &HashSet<usize>| {
+            let mut list: Vec<usize> = set.iter().filter(|c| !merged.contains(c)).copied().collect();
+            list.sort_by_key(|c| self.order[c]);
+            list
+        };
+        let (before, after) = (by_order(&backward), by_order(&forward));
+        let mut slots: Vec<usize> = forward.union(&backward).map(|c| self.order[c]).collect();
+        slots.sort();
+        // The backward side takes the lowest free positions and the forward side the highest,
+        // so neither moves past a neighbour outside the searched range; the merged component
+        // (if any) goes in between.
+        let top = slots.len() - after.len();
+        for (c, slot) in before.iter().zip(&slots) {
+            self.order.insert(*c, *slot);
+        }
+        if !merged.is_empty() {
+            self.order.insert(cu, slots[before.len()]);
+        }
+        for (c, slot) in after.iter().zip(&slots[top..]) {
+            self.order.insert(*c, *slot);
+        }
+
+        for &c in merged.iter().filter(|c| **c != cu) {
+            for node in self.members.remove(&c).unwrap() {
+                self.component.insert(node.clone(), cu);
+                self.members.get_mut(&cu).unwrap().push(node);
+            }
+            for s in self.succ.remove(&c).unwrap() {
+                if let Some(preds) = self.pred.get_mut(&s) {
+                    preds.remove(&c);
+                }
+                if !merged.contains(&s) {
+                    self.succ.get_mut(&cu).unwrap().insert(s);
+                    self.pred.get_mut(&s).unwrap().insert(cu);
+                }
+            }
+            for p in self.pred.remove(&c).unwrap() {
+                if let Some(succs) = self.succ.get_mut(&p) {
+                    succs.remove(&c);
+                }
+                if !merged.contains(&p) {
+                    self.pred.get_mut(&cu).unwrap().insert(p);
+                    self.succ.get_mut(&p).unwrap().insert(cu);
+                }
+            }
+            self.order.remove(&c);
+        }
+        let succ = self.succ.get_mut(&cu).unwrap();
+        succ.retain(|s| !merged.contains(s));
+        self.pred.get_mut(&cu).unwrap().retain(|p| !merged.contains(p));
+        if !merged.is_empty() {
+            self.members.get_mut(&cu).unwrap().sort();
+        }
+        merged.len()
+    }
+
+    /// Size of the component holding `node` (0 for an unknown node).
+    pub fn size_of(&self, node: &str) -> usize {
+        self.component.get(node).map_or(0, |c| self.members[c].len())
+    }
+
+    /// Every component with more than one node, each sorted, largest first.
+    pub fn nontrivial(&self) -> Vec<&Vec<String>> {
+        let mut list: Vec<&Vec<String>> = self.members.values().filter(|m| m.len() > 1).collect();
+        list.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        list
+    }
+
+    /// Checks the invariant the updates rely on: every condensation edge goes forward in the order.
+    pub fn is_ordered(&self) -> bool {
+        self.succ.iter().all(|(a, targets)| targets.iter().all(|b| self.order[a] < self.order[b]))
+    }
+}
+
+/// What one batch changed.
+pub struct BatchSummary {
+    pub new_nodes: Vec<String>,
+    pub new_edges: usize,
+    pub new_labels: usize,
+    /// Labelled arrivals that became start nodes of a group that had room.
+    pub new_starts: usize,
+    /// Starts relabelled out of a group, with their walks and reach dropped from it.
+    pub removed_starts: usize,
+    /// Components merged into bigger SCCs by the batch's edges.
+    pub merged_components: usize,
+    /// Start nodes that reach a new time-respecting edge (or just joined), so their reuse was re-estimated.
+    pub refreshed_starts: usize,
+}
+
+/// Exposure and reuse of one transaction under the current stream state.
+pub struct ArrivalScore {
+    pub tx: String,
+    pub timestep: usize,
+    pub label: String,
+    /// Per group: start nodes that reach the transaction (time-respecting, any depth).
+    pub reached_by: Vec<usize>,
+    /// Per group: walks that used the transaction as an intermediary.
+    pub reuse: Vec<usize>,
+    /// Configured mixer score of target vs baseline reuse.
+    pub score: f64,
+    pub scc_size: usize,
+}
+
+/// A loaded graph that accepts new batches and keeps its caches current.
+///
+/// Start nodes are sampled per group once (up to `sample_size`; later labelled
+/// arrivals fill groups that are short). For each start the full time-respecting
+/// reachable set is cached; a new edge only extends the sets of the starts that
+/// already reach its tail. Reuse is estimated with time-respecting walks, which
+/// are independent per start, so a batch only re-walks the starts that reach the
+/// tail of a new time-respecting edge, since only their walk distribution can
+/// change. A start relabelled out of its group leaves that group. (The DFS
+/// estimator keeps the group's top pairs, which has no per-start update.)
+/// `reach_count` in each tally is the exact per-group reach from the cache.
+pub struct StreamState {
+    edges: HashMap<String, HashSet<String>>,
+    timestamps: HashMap<String, usize>,
+    labels: HashMap<String, String>,
+    degrees: HashMap<String, usize>,
+    config: Config,
+    starts: Vec<Vec<String>>,
+    /// Start node → groups it was sampled for.
+    start_groups: HashMap<String, Vec<usize>>,
+    /// Start node → every node it reaches.
+    reach: HashMap<String, HashSet<String>>,
+    /// Node → start nodes that reach it.
+    reached_by: HashMap<String, HashSet<String>>,
+    scc: SccIndex,
+    tallies: Vec<ReuseTally>,
+    /// Target and baseline group indices of `config.contrast`.
+    contrast: (usize, usize),
+    /// Drives start sampling and every walk, seeded from `config.seed`.
+    rng: StdRng,
+}
+
+impl StreamState {
+    /// Samples the start nodes, then builds every cache from scratch once.
+    ///
+    /// # Returns
+    /// The state, or an error if the contrast names a group not in `config.groups`.
+    pub fn new(data: Dataset, config: Config) -> Result<StreamState, String> {
+        let contrast = config.contrast_indices()?;
+        let mut state = StreamState {
+            rng: StdRng::seed_from_u64(config.seed.unwrap_or_else(rand::random)),
+            degrees: crate::scoring::total_degrees(&data.edges),
+            scc: SccIndex::new(&data.edges),
+            edges: data.edges,
+            timestamps: data.timestamps,
+            labels: data.labels,
+            starts: vec![Vec::new(); config.groups.len()],
+            start_groups: HashMap::new(),
+            reach: HashMap::new(),
+            reached_by: HashMap::new(),
+            tallies: config.groups.iter().map(|_| empty_tally()).collect(),
+            contrast,
+            config,
+        };
+        for g in 0..state.config.groups.len() {
+            let members = state.config.groups[g].members(&state.labels);
+            let sampled: Vec<String> = members.choose_multiple(&mut state.rng, state.config.sample_size).cloned().collect();
+            for start in &sampled {
+                state.add_start(g, start);
+            }
+            state.refresh(g, &sampled);
+        }
+        Ok(state)
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn scc(&self) -> &SccIndex {
+        &self.scc
+    }
+
+    pub fn tallies(&self) -> &[ReuseTally] {
+        &self.tallies
+    }
+
+    pub fn starts(&self) -> &[Vec<String>] {
+        &self.starts
+    }
+
+    /// Target and baseline group indices, checked in `new`.
+    pub fn contrast(&self) -> (usize, usize) {
+        self.contrast
+    }
+
+    /// Node count and edge count of the current graph.
+    pub fn size(&self) -> (usize, usize) {
+        (self.timestamps.len(), self.edges.values().map(|t| t.len()).sum())
+    }
+
+    /// Registers `start` for group `g` and caches its reachable set.
+    fn add_start(&mut self, g: usize, start: &String) {
+        self.starts[g].push(start.clone());
+        self.start_groups.entry(start.clone()).or_default().push(g);
+        if !self.reach.contains_key(start) {
+            self.reach.insert(start.clone(), HashSet::new());
+            self.extend_reach(start, start);
+        } else {
+            for node in &self.reach[start] {
+                *self.tallies[g].reach_count.entry(node.clone()).or_insert(0) += 1;
+            }
+        }
+    }
+
+    /// Drops `start` from group `g`: its walks, its share of the reach counts and,
+    /// once it is in no group, its reachable set.
+    fn remove_start(&mut self, g: usize, start: &String) {
+        self.starts[g].retain(|s| s != start);
+        self.drop_walks(g, &HashSet::from([start]));
+        self.tallies[g].num_starts = self.starts[g].len();
+        let reach_count = &mut self.tallies[g].reach_count;
+        for node in &self.reach[start] {
+            let count = reach_count.get_mut(node).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                reach_count.remove(node);
+            }
+        }
+        let groups = self.start_groups.get_mut(start).unwrap();
+        groups.retain(|&h| h != g);
+        if groups.is_empty() {
+            self.start_groups.remove(start);
+            for node in self.reach.remove(start).unwrap() {
+                let starts = self.reached_by.get_mut(&node).unwrap();
+                starts.remove(start);
+                if starts.is_empty() {
+                    self.reached_by.remove(&node);
+                }
+            }
+        }
+    }
+
+    /// Adds everything reachable from `from` that `start` did not reach yet.
+    fn extend_reach(&mut self, start: &String, from: &str) {
+        let mut queue = VecDeque::from([from.to_string()]);
+        while let Some(node) = queue.pop_front() {
+            if node != *start {
+                if !self.reach.get_mut(start).unwrap().insert(node.clone()) {
+                    continue;
+                }
+                self.reached_by.entry(node.clone()).or_default().insert(start.clone());
+                for &g in &self.start_groups[start] {
+                    *self.tallies[g].reach_count.entry(node.clone()).or_insert(0) += 1;
+                }
+            }
+            if let Some(targets) = self.edges.get(&node) {
+                for tgt in targets {
+                    if dfsstuff::time_respecting(&self.timestamps, &node, tgt) && !self.reach[start].contains(tgt) && tgt != start {
+                        queue.push_back(tgt.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Removes the walks of `redo` (and their reuse counts) from group `g`'s tally.
+    fn drop_walks(&mut self, g: usize, redo: &HashSet<&String>) {
+        let tally = &mut self.tallies[g];
+        let (old, kept): (Vec<Vec<String>>, Vec<Vec<String>>) = std::mem::take(&mut tally.paths).into_iter().partition(|p| redo.contains(&p[0]));
+        tally.paths = kept;
+        for path in old {
+            for node in &path[1..path.len() - 1] {
+                let freq = tally.node_freq.get_mut(node).unwrap();
+                *freq -= 1;
+                if *freq == 0 {
+                    tally.node_freq.remove(node);
+                }
+            }
+        }
+        for starts_through in tally.node_starts.values_mut() {
+            starts_through.retain(|s| !redo.contains(s));
+        }
+        tally.node_starts.retain(|_, s| !s.is_empty());
+    }
+
+    /// Replaces the walks of `starts` in group `g`'s tally with fresh ones on the current graph.
+    fn refresh(&mut self, g: usize, starts: &[String]) {
+        if starts.is_empty() {
+            return;
+        }
+        self.drop_walks(g, &starts.iter().collect());
+        let tally = &mut self.tallies[g];
+        let fresh = walks::estimate_reuse(&self.edges, &self.timestamps, starts, self.config.walks_per_start, self.config.walk_length, &mut self.rng).tally;
+        for (node, freq) in fresh.node_freq {
+            *tally.node_freq.entry(node).or_insert(0) += freq;
+        }
+        for (node, through) in fresh.node_starts {
+            tally.node_starts.entry(node).or_default().extend(through);
+        }
+        tally.paths.extend(fresh.paths);
+        tally.num_starts = self.starts[g].len();
+    }
+
+    /// Appends a batch: nodes, then labels, then edges, then the reuse of every affected start.
+    pub fn apply(&mut self, batch: &Batch) -> BatchSummary {
+        let mut new_nodes: Vec<String> = batch.timestamps.keys().filter(|n| !self.timestamps.contains_key(*n)).cloned().collect();
+        new_nodes.sort();
+        for node in &new_nodes {
+            self.timestamps.insert(node.clone(), batch.timestamps[node]);
+        }
+
+        let mut touched: Vec<BTreeSet<String>> = vec![BTreeSet::new(); self.config.groups.len()];
+        let mut new_labels = 0;
+        let mut new_starts = 0;
+        let mut removed_starts = 0;
+        let mut labelled: Vec<(&String, &String)> = batch.labels.iter().collect();
+        labelled.sort();
+        for (node, label) in labelled {
+            if self.labels.insert(node.clone(), label.clone()).as_ref() == Some(label) {
+                continue;
+            }
+            new_labels += 1;
+            let left: Vec<usize> = self
+                .start_groups
+                .get(node)
+                .map_or(Vec::new(), |groups| groups.iter().copied().filter(|&g| !self.config.groups[g].labels.contains(label)).collect());
+            for g in left {
+                self.remove_start(g, node);
+                touched[g].remove(node);
+                removed_starts += 1;
+            }
+            for (g, group_touched) in touched.iter_mut().enumerate() {
+                if self.config.groups[g].labels.contains(label)
+                    && self.starts[g].len() < self.config.sample_size
+                    && !self.starts[g].contains(node)
+                {
+                    self.add_start(g, node);
+                    group_touched.insert(node.clone());
+                    new_starts += 1;
+                }
+            }
+        }
+
+        let mut new_edges = 0;
+        let mut merged_components = 0;
+        for (src, tgt) in &batch.edges {
+            if !self.edges.entry(src.clone()).or_default().insert(tgt.clone()) {
+                continue;
+            }
+            new_edges += 1;
+            *self.degrees.entry(src.clone()).or_insert(0) += 1;
+            *self.degrees.entry(tgt.clone()).or_insert(0) += 1;
+            merged_components += self.scc.insert_edge(src, tgt);
+            if !dfsstuff::time_respecting(&self.timestamps, src, tgt) {
+                continue;
+            }
+            let mut affected: Vec<String> = self.reached_by.get(src).map_or(Vec::new(), |s| s.iter().cloned().collect());
+            if self.reach.contains_key(src) {
+                affected.push(src.clone());
+            }
+            // Even when no reach grows, walks through src can now take the new edge.
+            for start in affected {
+                self.extend_reach(&start, tgt);
+                for &g in &self.start_groups[&start] {
+                    touched[g].insert(start.clone());
+                }
+            }
+        }
+
+        let mut refreshed_starts = 0;
+        for (g, starts) in touched.into_iter().enumerate() {
+            let starts: Vec<String> = starts.into_iter().collect();
+            refreshed_starts += starts.len();
+            self.refresh(g, &starts);
+        }
+
+        BatchSummary { new_nodes, new_edges, new_labels, new_starts, removed_starts, merged_components, refreshed_starts }
+    }
+
+    /// Scores one transaction against the current tallies.
+    pub fn score(&self, tx: &str) -> Option<ArrivalScore> {
+        let timestep = *self.timestamps.get(tx)?;
+        let (target, baseline) = self.contrast;
+        let context = ScoringContext::new(&self.tallies[target], &self.tallies[baseline], &self.degrees);
+        let reaching = self.reached_by.get(tx);
+        Some(ArrivalScore {
+            tx: tx.to_string(),
+            timestep,
+            label: label_name(self.labels.get(tx).map_or("unknown", |l| l.as_str())).to_string(),
+            reached_by: self
+                .starts
+                .iter()
+                .map(|starts| reaching.map_or(0, |r| starts.iter().filter(|s| r.contains(*s)).count()))
+                .collect(),
+            reuse: self.tallies.iter().map(|t| t.node_freq.get(tx).copied().unwrap_or(0)).collect(),
+            score: context.score(self.config.scoring, tx),
+            scc_size: self.scc.size_of(tx),
+        })
+    }
+}
+
+fn empty_tally() -> ReuseTally {
+    ReuseTally {
+        node_freq: HashMap::new(),
+        node_starts: HashMap::new(),
+        reach_count: HashMap::new(),
+        num_starts: 0,
+        paths: Vec::new(),
+    }
+}
+
+/// Prints one batch's summary and its `top` arrivals most exposed to the contrast's target group.
+pub fn print_batch(state: &StreamState, name: &str, summary: &BatchSummary, top: usize) {
+    let (nodes, edges) = state.size();
+    println!(
+        "\n[{}] +{} txs, +{} edges, +{} labels, +{} / -{} starts, {} SCCs merged, {} starts re-walked ({} txs, {} edges, largest SCC {})",
+        name, summary.new_nodes.len(), summary.new_edges, summary.new_labels, summary.new_starts, summary.removed_starts,
+        summary.merged_components, summary.refreshed_starts, nodes, edges,
+        state.scc().nontrivial().first().map_or(1, |c| c.len())
+    );
+    let (target, baseline) = state.contrast();
+    let mut scores: Vec<ArrivalScore> = summary.new_nodes.iter().filter_map(|n| state.score(n)).collect();
+    scores.sort_by(|a, b| {
+        b.reached_by[target].cmp(&a.reached_by[target])
+            .then_with(|| b.score.total_cmp(&a.score))
+            .then_with(|| a.tx.cmp(&b.tx))
+    });
+    let groups = &state.config().groups;
+    println!(
+        "{:<15} {:>8} {:>8} {:>12} {:>12} {:>12} {:>12} {:>9} {:>6}",
+        "New txId", "Timestep", "Label",
+        format!("{} reach", groups[target].name), format!("{} reach", groups[baseline].name),
+        format!("{} reuse", groups[target].name), format!("{} reuse", groups[baseline].name),
+        "Score", "SCC"
+    );
+    for s in scores.iter().take(top) {
+        println!(
+            "{:<15} {:>8} {:>8} {:>12} {:>12} {:>12} {:>12} {:>9.4} {:>6}",
+            s.tx, s.timestep, s.label, s.reached_by[target], s.reached_by[baseline],
+            s.reuse[target], s.reuse[baseline], s.score, s.scc_size
+        );
+    }
+}
@@ -0,0 +1,304 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::Write;
+
+use crate::explain::label_name;
+use crate::fileread::Dataset;
+
+/// Structural summary of one dataset (or one time window of it).
+pub struct StructureReport {
+    pub nodes: usize,
+    pub edges: usize,
+    /// (label, `in`/`out`/`total`) → degree → number of nodes. Label `all` covers every node.
+    pub degree_distributions: BTreeMap<(String, &'static str), BTreeMap<usize, usize>>,
+    /// Core number of every node in the undirected projection.
+    pub core_numbers: HashMap<String, usize>,
+    /// Share of edges `u → v` whose reverse `v → u` also exists.
+    pub reciprocity: f64,
+    /// Timestep → (nodes, edges leaving it, edges staying inside it).
+    pub per_timestep: BTreeMap<usize, (usize, usize, usize)>,
+    /// (source label, target label) → number of edges.
+    pub label_mixing: BTreeMap<(String, String), usize>,
+    /// Share of edges between two known-label nodes that join the same label.
+    pub homophily: f64,
+    /// Newman's assortativity coefficient over the same edges (1 = perfectly assortative, 0 = random).
+    pub assortativity: f64,
+}
+
+/// Keeps only the nodes with a timestep in `from..=to`, and the edges between them.
+pub fn restrict_to_window(data: &Dataset, from: usize, to: usize) -> Dataset {
+    let timestamps: HashMap<String, usize> = data
+        .timestamps
+        .iter()
+        .filter(|(_, ts)| (from..=to).contains(*ts))
+        .map(|(n, ts)| (n.clone(), *ts))
+        .collect();
+    let edges = data
+        .edges
+        .iter()
+        .filter(|(src, _)| timestamps.contains_key(*src))
+        .map(|(src, targets)| {
+            let kept: HashSet<String> = targets.iter().filter(|t| timestamps.contains_key(*t)).cloned().collect();
+            (src.clone(), kept)
+        })
+        .filter(|(_, targets)| !targets.is_empty())
+        .collect();
+    let labels = data
+        .labels
+        .iter()
+        .filter(|(n, _)| timestamps.contains_key(*n))
+        .map(|(n, l)| (n.clone(), l.clone()))
+        .collect();
+    Dataset { edges, timestamps, labels }
+}
+
+/// Core number of every node, on the undirected projection without self-loops
+/// (Batagelj–Zaveršnik bucket algorithm, linear in the number of edges).
+pub fn core_numbers(graph: &HashMap<String, HashSet<String>>) -> HashMap<String, usize> {
+    let mut index: HashMap<&String, usize> = HashMap::new();
+    let mut names: Vec<&String> = Vec::new();
+    for (src, targets) in graph {
+        for node in std::iter::once(src).chain(targets) {
+            index.entry(node).or_insert_with(|| {
+                names.push(node);
+                names.len() - 1
+            });
+        }
+    }
+    let mut neighbors: Vec<HashSet<usize>> = vec![HashSet::new(); names.len()];
+    for (src, targets) in graph {
+        for tgt in targets {
+            let (u, v) = (index[src], index[tgt]);
+            if u != v {
+                neighbors[u].insert(v);
+                neighbors[v].insert(u);
+            }
+        }
+    }
+
+    let n = names.len();
+    let mut degree: Vec<usize> = neighbors.iter().map(|s| s.len()).collect();
+    let max_degree = degree.iter().copied().max().unwrap_or(0);
+    // bin[d] = first position of degree-d nodes in `order`.
+    let mut bin = vec![0; max_degree + 1];
+    for &d in &degree {
+        bin[d] += 1;
+    }
+    let mut start = 0;
+    for count in bin.iter_mut() {
+        let c = *count;
+        *count = start;
+        start += c;
+    }
+    let mut position = vec![0; n];
+    let mut order = vec![0; n];
+    for v in 0..n {
+        position[v] = bin[degree[v]];
+        order[position[v]] = v;
+        bin[degree[v]] += 1;
+    }
+    for d in (1..=max_degree).rev() {
+        bin[d] = bin[d - 1];
+    }
+    bin[0] = 0;
+
+    for i in 0..n {
+        let v = order[i];
+        for &u in &neighbors[v] {
+            if degree[u] > degree[v] {
+                // Move u to the front of its bucket, then shrink its degree by one.
+                let (du, pu) = (degree[u], position[u]);
+                let pw = bin[du];
+                let w = order[pw];
+                if u != w {
+                    order.swap(pu, pw);
+                    position[u] = pw;
+                    position[w] = pu;
+                }
+                bin[du] += 1;
+                degree[u] -= 1;
+            }
+        }
+    }
+
+    names.into_iter().enumerate().map(|(i, node)| (node.clone(), degree[i])).collect()
+}
+
+/// Share of edges `u → v` (u ≠ v) whose reverse edge also exists.
+pub fn reciprocity(graph: &HashMap<String, HashSet<String>>) -> f64 {
+    let (mut total, mut mutual) = (0, 0);
+    for (src, targets) in graph {
+        for tgt in targets.iter().filter(|t| *t != src) {
+            total += 1;
+            if graph.get(tgt).is_some_and(|back| back.contains(src)) {
+                mutual += 1;
+            }
+        }
+    }
+    if total == 0 { 0.0 } else { mutual as f64 / total as f64 }
+}
+
+/// Newman's categorical assortativity `r = (Σ e_ii - Σ a_i b_i) / (1 - Σ a_i b_i)`
+/// of an edge mixing matrix, and the plain share of same-label edges.
+///
+/// # Returns
+/// `(homophily, assortativity)`, both 0 without edges.
+pub fn assortativity(mixing: &BTreeMap<(String, String), usize>) -> (f64, f64) {
+    let total: usize = mixing.values().sum();
+    if total == 0 {
+        return (0.0, 0.0);
+    }
+    let total = total as f64;
+    let mut same = 0.0;
+    let mut out_share: HashMap<&String, f64> = HashMap::new();
+    let mut in_share: HashMap<&String, f64> = HashMap::new();
+    for ((src, tgt), count) in mixing {
+        let share = *count as f64 / total;
+        if src == tgt {
+            same += share;
+        }
+        *out_share.entry(src).or_insert(0.0) += share;
+        *in_share.entry(tgt).or_insert(0.0) += share;
+    }
+    let expected: f64 = out_share.iter().map(|(l, a)| a * in_share.get(l).copied().unwrap_or(0.0)).sum();
+    let r = if expected < 1.0 { (same - expected) / (1.0 - expected) } else { 0.0 };
+    (same, r)
+}
+
+/// Computes every statistic in `StructureReport`.
+///
+/// Labels are shown by name (`illicit`, `licit`, `unknown`); nodes without a label are `unknown`.
+/// Homophily and assortativity only count edges whose endpoints both have a known label.
+pub fn build_report(data: &Dataset) -> StructureReport {
+    let mut nodes: HashSet<&String> = data.timestamps.keys().collect();
+    let mut in_degree: HashMap<&String, usize> = HashMap::new();
+    let mut edge_count = 0;
+    for (src, targets) in &data.edges {
+        nodes.insert(src);
+        for tgt in targets {
+            nodes.insert(tgt);
+            *in_degree.entry(tgt).or_insert(0) += 1;
+            edge_count += 1;
+        }
+    }
+    let label_of = |node: &String| label_name(data.labels.get(node).map_or("unknown", |l| l.as_str())).to_string();
+
+    let mut degree_distributions: BTreeMap<(String, &'static str), BTreeMap<usize, usize>> = BTreeMap::new();
+    for node in &nodes {
+        let din = in_degree.get(*node).copied().unwrap_or(0);
+        let dout = data.edges.get(*node).map_or(0, |t| t.len());
+        for label in [label_of(node), "all".to_string()] {
+            for (kind, degree) in [("in", din), ("out", dout), ("total", din + dout)] {
+                *degree_distributions.entry((label.clone(), kind)).or_default().entry(degree).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut per_timestep: BTreeMap<usize, (usize, usize, usize)> = BTreeMap::new();
+    for ts in data.timestamps.values() {
+        per_timestep.entry(*ts).or_insert((0, 0, 0)).0 += 1;
+    }
+    let mut label_mixing: BTreeMap<(String, String), usize> = BTreeMap::new();
+    for (src, targets) in &data.edges {
+        let src_ts = data.timestamps.get(src);
+        for tgt in targets {
+            if let Some(&ts) = src_ts {
+                let entry = per_timestep.entry(ts).or_insert((0, 0, 0));
+                entry.1 += 1;
+                if data.timestamps.get(tgt) == Some(&ts) {
+                    entry.2 += 1;
+                }
+            }
+            *label_mixing.entry((label_of(src), label_of(tgt))).or_insert(0) += 1;
+        }
+    }
+
+    let known: BTreeMap<(String, String), usize> = label_mixing
+        .iter()
+        .filter(|((a, b), _)| a != "unknown" && b != "unknown")
+        .map(|(k, v)| (k.clone(), *v))
+        .collect();
+    let (homophily, assortativity) = assortativity(&known);
+
+    let mut cores = core_numbers(&data.edges);
+    for node in nodes.iter() {
+        cores.entry((*node).clone()).or_insert(0);
+    }
+
+    StructureReport {
+        nodes: nodes.len(),
+        edges: edge_count,
+        degree_distributions,
+        core_numbers: cores,
+        reciprocity: reciprocity(&data.edges),
+        per_timestep,
+        label_mixing,
+        homophily,
+        assortativity,
+    }
+}
+
+/// Writes the report as one file of `# section` blocks, each a small CSV table.
+///
+/// Every table has a fixed header, so reports of two datasets or two windows
+/// can be diffed or loaded section by section.
+///
+/// # Arguments
+/// * `path` - Output file.
+/// * `report` - Output of `build_report`.
+/// * `source` - What the report describes (dataset and window), written at the top.
+pub fn write_report(path: &str, report: &StructureReport, source: &str) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
+    writeln!(file, "# structure report: {}", source)?;
+
+    let mut cores: BTreeMap<usize, usize> = BTreeMap::new();
+    for core in report.core_numbers.values() {
+        *cores.entry(*core).or_insert(0) += 1;
+    }
+    writeln!(file, "\n# summary\nstat,value")?;
+    writeln!(file, "nodes,{}", report.nodes)?;
+    writeln!(file, "edges,{}", report.edges)?;
+    writeln!(file, "reciprocity,{:.6}", report.reciprocity)?;
+    writeln!(file, "degeneracy,{}", cores.keys().next_back().copied().unwrap_or(0))?;
+    writeln!(file, "label_homophily,{:.6}", report.homophily)?;
+    writeln!(file, "label_assortativity,{:.6}", report.assortativity)?;
+
+    writeln!(file, "\n# degree_summary\nlabel,kind,nodes,mean,max")?;
+    for ((label, kind), dist) in &report.degree_distributions {
+        let count: usize = dist.values().sum();
+        let sum: usize = dist.iter().map(|(d, c)| d * c).sum();
+        let max = dist.keys().next_back().copied().unwrap_or(0);
+        writeln!(file, "{},{},{},{:.4},{}", label, kind, count, sum as f64 / count.max(1) as f64, max)?;
+    }
+
+    writeln!(file, "\n# degree_distribution\nlabel,kind,degree,nodes")?;
+    for ((label, kind), dist) in &report.degree_distributions {
+        for (degree, count) in dist {
+            writeln!(file, "{},{},{},{}", label, kind, degree, count)?;
+        }
+    }
+
+    writeln!(file, "\n# timesteps\ntimestep,nodes,edges_out,edges_within")?;
+    for (ts, (nodes, edges, within)) in &report.per_timestep {
+        writeln!(file, "{},{},{},{}", ts, nodes, edges, within)?;
+    }
+
+    writeln!(file, "\n# label_mixing\nsource_label,target_label,edges")?;
+    for ((src, tgt), count) in &report.label_mixing {
+        writeln!(file, "{},{},{}", src, tgt, count)?;
+    }
+
+    writeln!(file, "\n# core_distribution\ncore,nodes")?;
+    for (core, count) in &cores {
+        writeln!(file, "{},{}", core, count)?;
+    }
+
+    writeln!(file, "\n# core_numbers\ntxId,core")?;
+    let mut nodes: Vec<(&String, &usize)> = report.core_numbers.iter().collect();
+    nodes.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    for (node, core) in nodes {
+        writeln!(file, "{},{}", node, core)?;
+    }
+
+    Ok(())
+}
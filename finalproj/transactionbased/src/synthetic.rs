@@ -0,0 +1,251 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::Write;
+use std::str::FromStr;
+
+use rand::prelude::IndexedRandom;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Largest out-degree drawn for a single node, so heavy tails stay bounded.
+const MAX_DEGREE: usize = 100;
+
+/// How many out-edges each ordinary node gets.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DegreeDistribution {
+    /// Uniform on `0..=2 * mean`.
+    Uniform(f64),
+    /// Discrete power law `P(d) ∝ d^-alpha` for `d ≥ 1`, capped at `MAX_DEGREE`.
+    PowerLaw(f64),
+}
+
+impl DegreeDistribution {
+    fn sample(&self, rng: &mut StdRng) -> usize {
+        match *self {
+            DegreeDistribution::Uniform(mean) => rng.random_range(0..=(2.0 * mean).round() as usize),
+            DegreeDistribution::PowerLaw(alpha) => {
+                let u: f64 = rng.random_range(f64::EPSILON..1.0);
+                (u.powf(-1.0 / (alpha - 1.0)).floor() as usize).min(MAX_DEGREE)
+            }
+        }
+    }
+}
+
+impl FromStr for DegreeDistribution {
+    type Err = String;
+
+    /// Parses `uniform:<mean>` or `powerlaw:<alpha>` (alpha > 1).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = s
+            .split_once(':')
+            .ok_or_else(|| format!("Degree distribution should look like uniform:3 or powerlaw:2.5, got {}", s))?;
+        let value: f64 = value
+            .parse()
+            .map_err(|_| format!("Degree distribution expects a number, got {}", value))?;
+        match name {
+            "uniform" if value >= 0.0 => Ok(DegreeDistribution::Uniform(value)),
+            "powerlaw" if value > 1.0 => Ok(DegreeDistribution::PowerLaw(value)),
+            "uniform" | "powerlaw" => Err(format!("Bad parameter {} for {}", value, name)),
+            _ => Err(format!("Unknown degree distribution {} (expected uniform or powerlaw)", name)),
+        }
+    }
+}
+
+/// Knobs for `generate`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SyntheticConfig {
+    /// Ordinary transactions (mixers come on top).
+    pub nodes: usize,
+    /// Timesteps 1..=timesteps, spread uniformly over the nodes.
+    pub timesteps: usize,
+    pub illicit_share: f64,
+    pub licit_share: f64,
+    pub degree: DegreeDistribution,
+    /// Chance an edge stays on its source's side (illicit vs everything else),
+    /// instead of going to any transaction.
+    pub homophily: f64,
+    /// Number of planted mixer nodes.
+    pub mixers: usize,
+    /// Share of illicit nodes that send an edge into a mixer.
+    pub mixer_share: f64,
+    /// Out-edges from each mixer into later transactions.
+    pub mixer_fanout: usize,
+    /// Noise columns written after the timestep in the features file.
+    pub features: usize,
+    pub seed: u64,
+}
+
+impl Default for SyntheticConfig {
+    fn default() -> Self {
+        SyntheticConfig {
+            nodes: 5000,
+            timesteps: 10,
+            illicit_share: 0.1,
+            licit_share: 0.2,
+            degree: DegreeDistribution::PowerLaw(2.5),
+            homophily: 0.9,
+            mixers: 5,
+            mixer_share: 0.5,
+            mixer_fanout: 10,
+            features: 5,
+            seed: 42,
+        }
+    }
+}
+
+/// A generated graph in the same shape `main` builds from the Elliptic CSVs.
+pub struct SyntheticGraph {
+    pub edges: HashMap<String, HashSet<String>>,
+    pub timestamps: HashMap<String, usize>,
+    pub labels: HashMap<String, String>,
+    pub features: HashMap<String, Vec<f64>>,
+    /// Planted mixer → number of illicit transactions routed through it.
+    pub mixers: HashMap<String, usize>,
+}
+
+/// Generates a random time-respecting transaction graph with planted mixers.
+///
+/// Background edges, and a mixer's `mixer_fanout` outgoing edges, go to a node
+/// in the same or the next timestep. Each illicit node routed through a mixer
+/// gets one edge to a mixer picked from all mixers at its own or any later
+/// timestep, so these edges can skip timesteps; none goes back in time.
+/// Mixers are labeled unknown and only receive edges from illicit nodes, so
+/// they sit in the middle of illicit flows but (almost) never licit ones.
+///
+/// # Returns
+/// The graph, reproducible from `config.seed`.
+pub fn generate(config: &SyntheticConfig) -> SyntheticGraph {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let timesteps = config.timesteps.max(1);
+
+    let mut timestamps = HashMap::new();
+    let mut labels = HashMap::new();
+    let mut features = HashMap::new();
+    let mut by_timestep: Vec<Vec<String>> = vec![Vec::new(); timesteps + 2];
+    let mut illicit = Vec::new();
+    let mut illicit_set = HashSet::new();
+
+    for i in 0..config.nodes {
+        let node = i.to_string();
+        let ts = rng.random_range(1..=timesteps);
+        let roll: f64 = rng.random();
+        let label = if roll < config.illicit_share {
+            illicit.push(node.clone());
+            illicit_set.insert(node.clone());
+            "2"
+        } else if roll < config.illicit_share + config.licit_share {
+            "1"
+        } else {
+            "unknown"
+        };
+        timestamps.insert(node.clone(), ts);
+        labels.insert(node.clone(), label.to_string());
+        features.insert(node.clone(), (0..config.features).map(|_| rng.random()).collect());
+        by_timestep[ts].push(node);
+    }
+
+    let mut edges: HashMap<String, HashSet<String>> = HashMap::new();
+    for ts in 1..=timesteps {
+        let pool: Vec<&String> = by_timestep[ts].iter().chain(&by_timestep[ts + 1]).collect();
+        let (illicit_pool, other_pool): (Vec<&String>, Vec<&String>) =
+            pool.iter().partition(|n| illicit_set.contains(**n));
+        for node in &by_timestep[ts] {
+            let side = if illicit_set.contains(node) { &illicit_pool } else { &other_pool };
+            for _ in 0..config.degree.sample(&mut rng) {
+                let options = if rng.random::<f64>() < config.homophily { side } else { &pool };
+                let Some(&target) = options.choose(&mut rng) else { break };
+                if target != node {
+                    edges.entry(node.clone()).or_default().insert(target.clone());
+                }
+            }
+        }
+    }
+
+    let mut mixers = HashMap::new();
+    let mut mixer_ts = Vec::new();
+    for i in 0..config.mixers {
+        let node = (config.nodes + i).to_string();
+        let ts = rng.random_range(1..=timesteps);
+        timestamps.insert(node.clone(), ts);
+        labels.insert(node.clone(), "unknown".to_string());
+        features.insert(node.clone(), (0..config.features).map(|_| rng.random()).collect());
+        let pool: Vec<&String> = by_timestep[ts].iter().chain(&by_timestep[ts + 1]).collect();
+        let outputs: HashSet<String> = pool
+            .choose_multiple(&mut rng, config.mixer_fanout)
+            .map(|n| (*n).clone())
+            .collect();
+        edges.insert(node.clone(), outputs);
+        mixers.insert(node.clone(), 0);
+        mixer_ts.push((node, ts));
+    }
+
+    for node in &illicit {
+        if rng.random::<f64>() >= config.mixer_share {
+            continue;
+        }
+        let ts = timestamps[node];
+        let options: Vec<&String> = mixer_ts.iter().filter(|(_, m)| *m >= ts).map(|(m, _)| m).collect();
+        if let Some(&mixer) = options.choose(&mut rng) {
+            edges.entry(node.clone()).or_default().insert(mixer.clone());
+            *mixers.get_mut(mixer).unwrap() += 1;
+        }
+    }
+
+    SyntheticGraph {
+        edges,
+        timestamps,
+        labels,
+        features,
+        mixers,
+    }
+}
+
+/// Writes the graph as Elliptic CSVs, plus the planted mixers as an annotation file.
+///
+/// # Arguments
+/// * `dir` - Output folder (created if missing); usable afterwards as `--data-dir`.
+/// * `graph` - Output of `generate`.
+///
+/// Files: `elliptic_txs_edgelist.csv`, `elliptic_txs_classes.csv`,
+/// `elliptic_txs_features.csv` and `planted_mixers.csv` (`txId,entity_type,note`,
+/// so it can be passed straight to `--annotations`).
+pub fn write_elliptic(dir: &str, graph: &SyntheticGraph) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+
+    let mut nodes: Vec<&String> = graph.timestamps.keys().collect();
+    nodes.sort_by_key(|n| n.parse::<usize>().unwrap_or(usize::MAX));
+
+    let mut file = File::create(format!("{}/elliptic_txs_edgelist.csv", dir))?;
+    writeln!(file, "txId1,txId2")?;
+    for src in &nodes {
+        if let Some(targets) = graph.edges.get(*src) {
+            let mut targets: Vec<&String> = targets.iter().collect();
+            targets.sort();
+            for tgt in targets {
+                writeln!(file, "{},{}", src, tgt)?;
+            }
+        }
+    }
+
+    let mut file = File::create(format!("{}/elliptic_txs_classes.csv", dir))?;
+    writeln!(file, "txId,class")?;
+    for node in &nodes {
+        writeln!(file, "{},{}", node, graph.labels[*node])?;
+    }
+
+    let mut file = File::create(format!("{}/elliptic_txs_features.csv", dir))?;
+    for node in &nodes {
+        let noise: String = graph.features[*node].iter().map(|f| format!(",{:.4}", f)).collect();
+        writeln!(file, "{},{}{}", node, graph.timestamps[*node], noise)?;
+    }
+
+    let mut file = File::create(format!("{}/planted_mixers.csv", dir))?;
+    writeln!(file, "txId,entity_type,note")?;
+    let mut mixers: Vec<(&String, &usize)> = graph.mixers.iter().collect();
+    mixers.sort();
+    for (mixer, routed) in mixers {
+        writeln!(file, "{},mixer,planted: routes {} illicit txs", mixer, routed)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,162 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use rand::prelude::IndexedRandom;
+use rand::rngs::StdRng;
+
+use crate::centrality;
+
+/// How `theory_tester` picks DFS targets out of the reachable set.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TargetStrategy {
+    /// The `k` reachable nodes with the highest out-degree (the original behavior).
+    TopOutDegree(usize),
+    /// `k` reachable nodes sampled uniformly.
+    Uniform(usize),
+    /// The `k` reachable nodes with the highest in-degree.
+    TopInDegree(usize),
+    /// The `k` reachable nodes with the highest time-respecting betweenness from the starts.
+    TopBetweenness(usize),
+    /// Up to `k` reachable nodes that are themselves labeled illicit (starts excluded).
+    Illicit(usize),
+    /// Every reachable node, as long as starts × targets stays within the pair budget.
+    AllReachable(usize),
+}
+
+impl TargetStrategy {
+    /// Builds a strategy from its name and the `k` / budget parameter.
+    pub fn from_name(name: &str, k: usize) -> Result<Self, String> {
+        match name {
+            "outdegree" => Ok(TargetStrategy::TopOutDegree(k)),
+            "uniform" => Ok(TargetStrategy::Uniform(k)),
+            "indegree" => Ok(TargetStrategy::TopInDegree(k)),
+            "betweenness" => Ok(TargetStrategy::TopBetweenness(k)),
+            "illicit" => Ok(TargetStrategy::Illicit(k)),
+            "all" => Ok(TargetStrategy::AllReachable(k)),
+            _ => Err(format!(
+                "Unknown target strategy {} (expected outdegree, uniform, indegree, betweenness, illicit or all)",
+                name
+            )),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TargetStrategy::TopOutDegree(_) => "outdegree",
+            TargetStrategy::Uniform(_) => "uniform",
+            TargetStrategy::TopInDegree(_) => "indegree",
+            TargetStrategy::TopBetweenness(_) => "betweenness",
+            TargetStrategy::Illicit(_) => "illicit",
+            TargetStrategy::AllReachable(_) => "all",
+        }
+    }
+
+    fn k(&self) -> usize {
+        match *self {
+            TargetStrategy::TopOutDegree(k)
+            | TargetStrategy::Uniform(k)
+            | TargetStrategy::TopInDegree(k)
+            | TargetStrategy::TopBetweenness(k)
+            | TargetStrategy::Illicit(k)
+            | TargetStrategy::AllReachable(k) => k,
+        }
+    }
+}
+
+impl fmt::Display for TargetStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TargetStrategy::AllReachable(budget) => write!(f, "all (pair budget {})", budget),
+            _ => write!(f, "{} (k={})", self.name(), self.k()),
+        }
+    }
+}
+
+impl FromStr for TargetStrategy {
+    type Err = String;
+
+    /// Parses `name` or `name:k`; `k` defaults to 100.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, k) = match s.split_once(':') {
+            Some((name, k)) => (name, k.parse::<usize>().map_err(|_| format!("Bad target count in {}", s))?),
+            None => (s, 100),
+        };
+        TargetStrategy::from_name(name, k)
+    }
+}
+
+/// Everything a target strategy might need to rank candidates.
+pub struct TargetContext<'a> {
+    pub graph: &'a HashMap<String, HashSet<String>>,
+    pub timestamps: &'a HashMap<String, usize>,
+    pub labels: &'a HashMap<String, String>,
+    pub start_nodes: &'a [String],
+    pub reachable: Vec<String>,
+}
+
+/// The targets a strategy picked.
+pub struct TargetSelection {
+    pub targets: Vec<String>,
+    /// For `AllReachable` over the pair budget: how many reachable nodes the targets were sampled from.
+    pub sampled_from: Option<usize>,
+}
+
+/// Picks DFS targets from the reachable set according to `strategy`.
+///
+/// Sampling strategies draw from `rng`; with a seeded `rng` and a sorted
+/// `ctx.reachable` the choice is reproducible.
+///
+/// # Returns
+/// The chosen target node IDs, and whether `AllReachable` had to sample them.
+pub fn select_targets(strategy: TargetStrategy, ctx: &TargetContext, rng: &mut StdRng) -> TargetSelection {
+    let k = strategy.k();
+
+    let targets = match strategy {
+        TargetStrategy::TopOutDegree(_) => {
+            top_k_by(&ctx.reachable, k, |node| ctx.graph.get(node).map_or(0, |n| n.len()) as f64)
+        }
+        TargetStrategy::Uniform(_) => ctx.reachable.choose_multiple(rng, k).cloned().collect(),
+        TargetStrategy::TopInDegree(_) => {
+            let mut in_degree: HashMap<&String, usize> = HashMap::new();
+            for targets in ctx.graph.values() {
+                for tgt in targets {
+                    *in_degree.entry(tgt).or_insert(0) += 1;
+                }
+            }
+            top_k_by(&ctx.reachable, k, |node| *in_degree.get(node).unwrap_or(&0) as f64)
+        }
+        TargetStrategy::TopBetweenness(_) => {
+            let scores = centrality::betweenness_from(ctx.graph, ctx.timestamps, ctx.start_nodes);
+            top_k_by(&ctx.reachable, k, |node| *scores.get(node).unwrap_or(&0.0))
+        }
+        TargetStrategy::Illicit(_) => {
+            let starts: HashSet<&String> = ctx.start_nodes.iter().collect();
+            let illicit: Vec<String> = ctx
+                .reachable
+                .iter()
+                .filter(|node| !starts.contains(node))
+                .filter(|node| ctx.labels.get(*node).map(|l| l.as_str()) == Some("2"))
+                .cloned()
+                .collect();
+            illicit.choose_multiple(rng, k).cloned().collect()
+        }
+        TargetStrategy::AllReachable(budget) => {
+            // A budget below the number of starts still gets one target per start.
+            let per_start = (budget / ctx.start_nodes.len().max(1)).max(1);
+            if ctx.reachable.len() <= per_start {
+                ctx.reachable.clone()
+            } else {
+                let targets = ctx.reachable.choose_multiple(rng, per_start).cloned().collect();
+                return TargetSelection { targets, sampled_from: Some(ctx.reachable.len()) };
+            }
+        }
+    };
+    TargetSelection { targets, sampled_from: None }
+}
+
+fn top_k_by<F: Fn(&String) -> f64>(nodes: &[String], k: usize, key: F) -> Vec<String> {
+    let mut ranked: Vec<(&String, f64)> = nodes.iter().map(|n| (n, key(n))).collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    ranked.into_iter().take(k).map(|(n, _)| n.clone()).collect()
+}
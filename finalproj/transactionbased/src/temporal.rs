@@ -0,0 +1,152 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet, VecDeque};
+
+use crate::dfsstuff;
+
+/// When and how a node is first reached from a source set.
+pub struct Arrival {
+    /// Earliest timestep at which a time-respecting path can arrive.
+    pub timestep: usize,
+    /// Fewest hops among the paths arriving at that timestep.
+    pub hops: usize,
+    /// The source that path starts from.
+    pub source: String,
+}
+
+/// Earliest-arrival timestep from any node in `sources` to every reachable node.
+///
+/// Dijkstra over (arrival timestep, hops), only following edges that don't go
+/// back in time, so it runs in O((V + E) log V) instead of enumerating paths.
+///
+/// # Returns
+/// Node → `Arrival` for every node reachable from the sources (sources included).
+pub fn earliest_arrival(
+    graph: &HashMap<String, HashSet<String>>,
+    timestamps: &HashMap<String, usize>,
+    sources: &[String],
+) -> HashMap<String, Arrival> {
+    let mut arrivals: HashMap<String, Arrival> = HashMap::new();
+    let mut heap = BinaryHeap::new();
+
+    for source in sources {
+        let ts = timestamps.get(source).copied().unwrap_or(0);
+        heap.push(Reverse((ts, 0, source.clone(), source.clone())));
+    }
+
+    while let Some(Reverse((ts, hops, node, source))) = heap.pop() {
+        if arrivals.contains_key(&node) {
+            continue;
+        }
+        if let Some(neighbors) = graph.get(&node) {
+            for neighbor in neighbors {
+                if arrivals.contains_key(neighbor) || !dfsstuff::time_respecting(timestamps, &node, neighbor) {
+                    continue;
+                }
+                let neighbor_ts = timestamps.get(neighbor).copied().unwrap_or(usize::MAX);
+                heap.push(Reverse((ts.max(neighbor_ts), hops + 1, neighbor.clone(), source.clone())));
+            }
+        }
+        arrivals.insert(node, Arrival { timestep: ts, hops, source });
+    }
+
+    arrivals
+}
+
+/// Minimum-hop time-respecting path between two txIds (BFS).
+///
+/// # Returns
+/// The path including both endpoints, or `None` if `to` can't be reached.
+pub fn shortest_temporal_path(
+    graph: &HashMap<String, HashSet<String>>,
+    timestamps: &HashMap<String, usize>,
+    from: &String,
+    to: &String,
+) -> Option<Vec<String>> {
+    let mut parent: HashMap<&String, &String> = HashMap::new();
+    let mut seen: HashSet<&String> = HashSet::from([from]);
+    let mut queue = VecDeque::from([from]);
+
+    while let Some(current) = queue.pop_front() {
+        if current == to {
+            let mut path = vec![current.clone()];
+            let mut node = current;
+            while let Some(prev) = parent.get(node) {
+                path.push((*prev).clone());
+                node = prev;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        let Some(neighbors) = graph.get(current) else { continue };
+        for neighbor in neighbors {
+            if !seen.contains(neighbor) && dfsstuff::time_respecting(timestamps, current, neighbor) {
+                seen.insert(neighbor);
+                parent.insert(neighbor, current);
+                queue.push_back(neighbor);
+            }
+        }
+    }
+
+    None
+}
+
+/// Hop distance from `source` to every node it reaches over time-respecting edges.
+pub fn temporal_distances(
+    graph: &HashMap<String, HashSet<String>>,
+    timestamps: &HashMap<String, usize>,
+    source: &str,
+) -> HashMap<String, usize> {
+    let mut dist: HashMap<String, usize> = HashMap::from([(source.to_string(), 0)]);
+    let mut queue = VecDeque::from([source.to_string()]);
+
+    while let Some(current) = queue.pop_front() {
+        let d = dist[&current];
+        let Some(neighbors) = graph.get(&current) else { continue };
+        for neighbor in neighbors {
+            if !dist.contains_key(neighbor) && dfsstuff::time_respecting(timestamps, &current, neighbor) {
+                dist.insert(neighbor.clone(), d + 1);
+                queue.push_back(neighbor.clone());
+            }
+        }
+    }
+
+    dist
+}
+
+/// Histogram of temporal hop distances from each source to the nodes it reaches.
+///
+/// # Returns
+/// Distance (≥ 1) → number of (source, node) pairs at that distance.
+pub fn distance_distribution(
+    graph: &HashMap<String, HashSet<String>>,
+    timestamps: &HashMap<String, usize>,
+    sources: &[String],
+) -> BTreeMap<usize, usize> {
+    let mut histogram = BTreeMap::new();
+    for source in sources {
+        for d in temporal_distances(graph, timestamps, source).into_values() {
+            if d > 0 {
+                *histogram.entry(d).or_insert(0) += 1;
+            }
+        }
+    }
+    histogram
+}
+
+/// Prints illicit and licit distance distributions side by side, as counts and shares.
+pub fn print_distance_comparison(illicit: &BTreeMap<usize, usize>, licit: &BTreeMap<usize, usize>) {
+    let illicit_total = illicit.values().sum::<usize>().max(1) as f64;
+    let licit_total = licit.values().sum::<usize>().max(1) as f64;
+    let max_d = illicit.keys().chain(licit.keys()).max().copied().unwrap_or(0);
+
+    println!("\nTemporal distance distribution:");
+    println!("{:<6} {:>12} {:>8} {:>12} {:>8}", "Hops", "Illicit", "Share", "Licit", "Share");
+    for d in 1..=max_d {
+        let i = *illicit.get(&d).unwrap_or(&0);
+        let l = *licit.get(&d).unwrap_or(&0);
+        println!(
+            "{:<6} {:>12} {:>8.3} {:>12} {:>8.3}",
+            d, i, i as f64 / illicit_total, l, l as f64 / licit_total
+        );
+    }
+}
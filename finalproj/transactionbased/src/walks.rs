@@ -0,0 +1,126 @@
+use std::collections::{HashMap, HashSet};
+
+use rand::prelude::IndexedRandom;
+use rand::rngs::StdRng;
+
+use crate::dfsstuff;
+use crate::scoring::ReuseTally;
+
+/// Monte Carlo estimate of intermediary reuse from time-respecting random walks.
+pub struct WalkEstimate {
+    /// Same shape as the DFS tally, so it can go straight into `compute_mixer_data`.
+    pub tally: ReuseTally,
+    pub num_walks: usize,
+    /// Node → (share of walks using it as an intermediary, standard error).
+    pub visit_rate: HashMap<String, (f64, f64)>,
+}
+
+/// Runs one random walk from `start`, only following edges that don't go back in time.
+///
+/// The walk never revisits a node and stops at a dead end or after `max_len` nodes.
+/// Neighbours are chosen from in sorted order, so a seeded `rng` gives the same walk every time.
+///
+/// # Returns
+/// The visited nodes, starting with `start`.
+pub fn random_walk(
+    graph: &HashMap<String, HashSet<String>>,
+    timestamps: &HashMap<String, usize>,
+    start: &String,
+    max_len: usize,
+    rng: &mut StdRng,
+) -> Vec<String> {
+    let mut walk = vec![start.clone()];
+    let mut on_walk: HashSet<&String> = HashSet::from([start]);
+
+    while walk.len() < max_len {
+        let current = walk.last().unwrap();
+        let Some(neighbors) = graph.get(current) else { break };
+        let mut options: Vec<&String> = neighbors
+            .iter()
+            .filter(|n| !on_walk.contains(n) && dfsstuff::time_respecting(timestamps, current, n))
+            .collect();
+        options.sort();
+        let Some(next) = options.choose(rng) else { break };
+        on_walk.insert(next);
+        walk.push((*next).clone());
+    }
+
+    walk
+}
+
+/// Estimates how often each node is an intermediary on walks from `start_nodes`.
+///
+/// # Arguments
+/// * `graph`, `timestamps` - The transaction graph and its timestamps.
+/// * `start_nodes` - Sampled illicit or licit starts.
+/// * `walks_per_start` - Number of walks launched from every start.
+/// * `max_len` - Longest walk, in nodes.
+/// * `rng` - Source of every random step (seed it for reproducible runs).
+///
+/// # Returns
+/// A `WalkEstimate` whose tally counts intermediary visits per node.
+pub fn estimate_reuse(
+    graph: &HashMap<String, HashSet<String>>,
+    timestamps: &HashMap<String, usize>,
+    start_nodes: &[String],
+    walks_per_start: usize,
+    max_len: usize,
+    rng: &mut StdRng,
+) -> WalkEstimate {
+    let mut node_freq: HashMap<String, usize> = HashMap::new();
+    let mut node_starts: HashMap<String, HashSet<String>> = HashMap::new();
+    let mut reach_count: HashMap<String, usize> = HashMap::new();
+    let mut paths = Vec::new();
+
+    for start in start_nodes {
+        let mut reached: HashSet<String> = HashSet::new();
+        for _ in 0..walks_per_start {
+            let walk = random_walk(graph, timestamps, start, max_len, rng);
+            if walk.len() > 2 {
+                for node in &walk[1..walk.len() - 1] {
+                    *node_freq.entry(node.clone()).or_insert(0) += 1;
+                    node_starts.entry(node.clone()).or_default().insert(start.clone());
+                }
+                paths.push(walk.clone());
+            }
+            reached.extend(walk);
+        }
+        for node in reached {
+            *reach_count.entry(node).or_insert(0) += 1;
+        }
+    }
+
+    // A node appears at most once per walk, so each walk is a Bernoulli trial.
+    let num_walks = start_nodes.len() * walks_per_start;
+    let n = num_walks.max(1) as f64;
+    let visit_rate = node_freq
+        .iter()
+        .map(|(node, hits)| {
+            let p = *hits as f64 / n;
+            (node.clone(), (p, (p * (1.0 - p) / n).sqrt()))
+        })
+        .collect();
+
+    WalkEstimate {
+        tally: ReuseTally {
+            node_freq,
+            node_starts,
+            reach_count,
+            num_starts: start_nodes.len(),
+            paths,
+        },
+        num_walks,
+        visit_rate,
+    }
+}
+
+/// Prints the most visited intermediaries with their standard errors.
+pub fn print_top_rates(estimate: &WalkEstimate, label: &str, top: usize) {
+    let mut rates: Vec<(&String, &(f64, f64))> = estimate.visit_rate.iter().collect();
+    rates.sort_by(|a, b| b.1.0.total_cmp(&a.1.0).then_with(|| a.0.cmp(b.0)));
+
+    println!("[{}] {} walks, top intermediaries:", label, estimate.num_walks);
+    for (node, (rate, se)) in rates.into_iter().take(top) {
+        println!("[{}]   {:<15} {:.4} ± {:.4}", label, node, rate, se);
+    }
+}